@@ -0,0 +1,293 @@
+use crate::config::{AlertRule, Config, SegmentConfig};
+use crate::core::segments::SegmentData;
+use crate::utils::{shell_command, StateFile};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::process::Stdio;
+
+/// Debounce state, kept in ~/.claude/glmcode
+const STATE_FILE: &str = "alert_state.json";
+
+/// A rule that has fired and not yet re-armed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct FiredAlert {
+    fired_at: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    window: Option<String>,
+}
+
+impl FiredAlert {
+    /// Still debounced: the quota window it fired in has not reset yet
+    fn is_active(&self) -> bool {
+        match self.window.as_deref().and_then(|w| w.parse::<i64>().ok()) {
+            Some(reset_at) => reset_at > chrono::Utc::now().timestamp_millis(),
+            None => true,
+        }
+    }
+}
+
+/// Restyle segments whose rules are tripped and fire newly tripped rules
+pub fn apply(config: &Config, segments: &mut [(SegmentConfig, SegmentData)]) {
+    if config.alerts.is_empty() {
+        return;
+    }
+    if let Some(state) = StateFile::new(STATE_FILE) {
+        apply_with_state(&state, config, segments);
+    }
+}
+
+fn apply_with_state(
+    state: &StateFile,
+    config: &Config,
+    segments: &mut [(SegmentConfig, SegmentData)],
+) {
+    let mut fired: HashMap<String, FiredAlert> = state.load().unwrap_or_default();
+    let mut changed = false;
+    let mut rearmed = Vec::new();
+    let mut to_fire = Vec::new();
+
+    for rule in &config.alerts {
+        let key = rule.key();
+        let Some((segment_config, data)) = segments.iter_mut().find(|(c, _)| rule.targets(c))
+        else {
+            continue;
+        };
+        let Some(value) = data
+            .metadata
+            .get(&rule.metric)
+            .and_then(|v| v.parse::<f64>().ok())
+        else {
+            continue;
+        };
+
+        if value < rule.above {
+            if fired.remove(&key).is_some() {
+                rearmed.push(key);
+                changed = true;
+            }
+            continue;
+        }
+
+        restyle(segment_config, rule);
+        data.metadata.insert("alert".to_string(), key.clone());
+
+        // Values replayed after a missed deadline do not fire anything
+        if data.metadata.contains_key("fallback") {
+            continue;
+        }
+        let window = data.metadata.get("next_reset_time").cloned();
+        if !fired.get(&key).is_some_and(FiredAlert::is_active) {
+            to_fire.push((rule, value, window));
+        }
+    }
+
+    if !to_fire.is_empty() {
+        // Concurrent renders would otherwise fire the same alert twice
+        if let Some(_lock) = state.lock() {
+            // Another render may have fired while this one was collecting
+            fired = state.load().unwrap_or(fired);
+            for key in &rearmed {
+                fired.remove(key);
+            }
+            for (rule, value, window) in to_fire {
+                let key = rule.key();
+                if fired.get(&key).is_some_and(FiredAlert::is_active) {
+                    continue;
+                }
+                crate::trace::record(format!(
+                    "alert {} tripped: {} >= {}",
+                    key, value, rule.above
+                ));
+                if let Some(command) = &rule.command {
+                    run_command(command, rule, &key, value);
+                }
+                fired.insert(
+                    key,
+                    FiredAlert {
+                        fired_at: chrono::Utc::now().to_rfc3339(),
+                        window,
+                    },
+                );
+                changed = true;
+            }
+        }
+    }
+
+    if changed {
+        if let Err(e) = state.store(&fired) {
+            crate::trace::record(format!("failed to write {}: {}", state.path().display(), e));
+        }
+    }
+}
+
+/// Override the segment's colors and weight with the rule's
+fn restyle(segment_config: &mut SegmentConfig, rule: &AlertRule) {
+    if let Some(colors) = &rule.colors {
+        if colors.icon.is_some() {
+            segment_config.colors.icon = colors.icon.clone();
+        }
+        if colors.text.is_some() {
+            segment_config.colors.text = colors.text.clone();
+        }
+        if colors.background.is_some() {
+            segment_config.colors.background = colors.background.clone();
+        }
+    }
+    if let Some(text_bold) = rule.text_bold {
+        segment_config.styles.text_bold = text_bold;
+    }
+}
+
+/// Run the hook detached; details are passed as `GLMCODE_ALERT_*` variables
+fn run_command(command: &str, rule: &AlertRule, key: &str, value: f64) {
+    let mut process = shell_command(command);
+    process
+        .env("GLMCODE_ALERT_NAME", key)
+        .env("GLMCODE_ALERT_METRIC", &rule.metric)
+        .env("GLMCODE_ALERT_VALUE", format!("{:.1}", value))
+        .env("GLMCODE_ALERT_THRESHOLD", format!("{}", rule.above))
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null());
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+        // Keep the hook running after the statusline render exits
+        process.process_group(0);
+    }
+
+    if let Err(e) = process.spawn() {
+        crate::trace::record(format!("alert command failed to start: {}", e));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::SegmentId;
+    use std::path::PathBuf;
+
+    fn state(name: &str) -> (StateFile, PathBuf) {
+        let dir = std::env::temp_dir().join(format!(
+            "glmcode-alerts-test-{}-{}",
+            name,
+            std::process::id()
+        ));
+        let _ = std::fs::remove_dir_all(&dir);
+        (StateFile::at(dir.join(STATE_FILE)), dir)
+    }
+
+    fn config() -> Config {
+        let rule: AlertRule = serde_json::from_value(serde_json::json!({
+            "name": "tokens",
+            "segment": "glm_usage",
+            "metric": "tokens_percentage",
+            "above": 80.0,
+            "text_bold": true
+        }))
+        .unwrap();
+        Config {
+            alerts: vec![rule],
+            ..Config::default()
+        }
+    }
+
+    fn segments(value: f64, window: Option<i64>) -> Vec<(SegmentConfig, SegmentData)> {
+        let mut segment = Config::default().segments[0].clone();
+        segment.id = SegmentId::GlmUsage;
+        segment.styles.text_bold = false;
+        let mut metadata = HashMap::new();
+        metadata.insert("tokens_percentage".to_string(), value.to_string());
+        if let Some(window) = window {
+            metadata.insert("next_reset_time".to_string(), window.to_string());
+        }
+        let data = SegmentData {
+            primary: String::new(),
+            secondary: String::new(),
+            metadata,
+        };
+        vec![(segment, data)]
+    }
+
+    fn fired_at(state: &StateFile) -> Option<String> {
+        let fired: HashMap<String, FiredAlert> = state.load()?;
+        fired.get("tokens").map(|alert| alert.fired_at.clone())
+    }
+
+    fn later_window() -> i64 {
+        chrono::Utc::now().timestamp_millis() + 3_600_000
+    }
+
+    #[test]
+    fn crossing_the_threshold_restyles_and_fires() {
+        let (state, dir) = state("crossing");
+        let config = config();
+
+        let mut below = segments(79.9, None);
+        apply_with_state(&state, &config, &mut below);
+        assert!(!below[0].1.metadata.contains_key("alert"));
+        assert!(!below[0].0.styles.text_bold);
+        assert!(fired_at(&state).is_none());
+
+        let mut above = segments(80.0, Some(later_window()));
+        apply_with_state(&state, &config, &mut above);
+        assert_eq!(above[0].1.metadata.get("alert").unwrap(), "tokens");
+        assert!(above[0].0.styles.text_bold);
+        assert!(fired_at(&state).is_some());
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn fired_rule_is_debounced_for_its_window() {
+        let (state, dir) = state("debounce");
+        let config = config();
+        let window = later_window();
+
+        apply_with_state(&state, &config, &mut segments(85.0, Some(window)));
+        let first = fired_at(&state).unwrap();
+        std::thread::sleep(std::time::Duration::from_millis(5));
+
+        let mut again = segments(95.0, Some(window));
+        apply_with_state(&state, &config, &mut again);
+        // Still restyled, but not fired a second time
+        assert!(again[0].0.styles.text_bold);
+        assert_eq!(fired_at(&state).unwrap(), first);
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn rule_rearms_below_threshold_or_after_its_window() {
+        let (state, dir) = state("rearm");
+        let config = config();
+
+        apply_with_state(&state, &config, &mut segments(85.0, Some(later_window())));
+        apply_with_state(&state, &config, &mut segments(50.0, Some(later_window())));
+        assert!(fired_at(&state).is_none());
+
+        let past = chrono::Utc::now().timestamp_millis() - 1000;
+        apply_with_state(&state, &config, &mut segments(85.0, Some(past)));
+        let first = fired_at(&state).unwrap();
+        std::thread::sleep(std::time::Duration::from_millis(5));
+
+        // The window it fired in has reset, so it fires again
+        apply_with_state(&state, &config, &mut segments(85.0, Some(later_window())));
+        assert_ne!(fired_at(&state).unwrap(), first);
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn fallback_values_restyle_without_firing() {
+        let (state, dir) = state("fallback");
+        let mut replayed = segments(90.0, None);
+        replayed[0]
+            .1
+            .metadata
+            .insert("fallback".to_string(), "cached".to_string());
+
+        apply_with_state(&state, &config(), &mut replayed);
+        assert!(replayed[0].0.styles.text_bold);
+        assert!(fired_at(&state).is_none());
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
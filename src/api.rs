@@ -1,23 +1,41 @@
+use crate::config::{GlmConfig, TokenSource};
+use crate::utils::FileLock;
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
 use std::path::PathBuf;
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// 默认请求超时（秒）
+const DEFAULT_TIMEOUT_SECS: u64 = 10;
 
 /// GLM API 配置
 #[derive(Debug, Clone)]
 pub struct GlmApiConfig {
     pub base_url: String,
     pub auth_token: String,
+    pub timeout: Duration,
+    /// 监控接口根地址，未设置时取 base_url 的 origin
+    pub monitor_base_url: Option<String>,
+    /// 平台类型，未设置时按地址识别
+    pub platform: Option<GlmPlatform>,
+    /// 套餐识别阈值
+    pub plan_thresholds: PlanThresholds,
 }
 
 /// GLM 平台类型
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum GlmPlatform {
     ZAI,
     ZHIPU,
 }
 
 /// GLM 套餐类型
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum GlmPlan {
     Lite,
     Pro,
@@ -25,21 +43,80 @@ pub enum GlmPlan {
     Unknown,
 }
 
+/// 套餐识别阈值：5 小时 Token 配额达到该值即视为对应套餐
+///
+/// 默认值取在官方配额（Lite 4千万 / Pro 2亿 / Max 8亿）之间，
+/// 活动或地区调整后的配额也能落到正确的档位。
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PlanThresholds {
+    pub pro: f64,
+    pub max: f64,
+}
+
+impl Default for PlanThresholds {
+    fn default() -> Self {
+        Self {
+            pro: 100_000_000.0, // 1亿
+            max: 500_000_000.0, // 5亿
+        }
+    }
+}
+
+/// 套餐类型的来源
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PlanSource {
+    /// 段配置中的 plan 选项
+    Override,
+    /// 接口返回的套餐字段
+    Api,
+    /// 按 Token 配额与阈值推断
+    Quota,
+    Unknown,
+}
+
+impl PlanSource {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Override => "override",
+            Self::Api => "api",
+            Self::Quota => "quota",
+            Self::Unknown => "unknown",
+        }
+    }
+}
+
 impl GlmPlan {
-    /// 从 Token usage 配额识别套餐类型
+    /// 按默认阈值从 Token usage 配额识别套餐类型
     pub fn from_token_usage(usage: f64) -> Self {
-        const PRO_LIMIT: f64 = 200_000_000.0;  // 2亿
-        const LITE_LIMIT: f64 = PRO_LIMIT / 5.0;  // 4千万
-        const MAX_LIMIT: f64 = PRO_LIMIT * 4.0;   // 8亿
+        Self::from_quota(usage, &PlanThresholds::default())
+    }
 
-        if (usage - LITE_LIMIT).abs() < 1.0 {
-            Self::Lite
-        } else if (usage - PRO_LIMIT).abs() < 1.0 {
-            Self::Pro
-        } else if (usage - MAX_LIMIT).abs() < 1.0 {
+    /// 按阈值表从 Token usage 配额识别套餐类型
+    pub fn from_quota(usage: f64, thresholds: &PlanThresholds) -> Self {
+        if !usage.is_finite() || usage <= 0.0 {
+            Self::Unknown
+        } else if usage >= thresholds.max {
             Self::Max
+        } else if usage >= thresholds.pro {
+            Self::Pro
         } else {
-            Self::Unknown
+            Self::Lite
+        }
+    }
+
+    /// 从套餐名称识别（接口字段或配置项，如 "GLM Coding Pro"、"max"）
+    pub fn from_name(name: &str) -> Option<Self> {
+        let name = name.to_ascii_lowercase();
+        if name.contains("max") {
+            Some(Self::Max)
+        } else if name.contains("pro") {
+            Some(Self::Pro)
+        } else if name.contains("lite") {
+            Some(Self::Lite)
+        } else {
+            None
         }
     }
 
@@ -64,36 +141,237 @@ impl GlmPlan {
     }
 }
 
+/// GLM API 错误类型
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum GlmApiError {
+    /// settings.json 中没有可用的 GLM 配置
+    NotConfigured,
+    /// Token 缺失或无效（401/403）
+    Unauthorized { status: u16 },
+    /// 触发限流（429），retry_after 为服务端建议的等待秒数
+    RateLimited { retry_after: Option<u64> },
+    /// 请求超时
+    Timeout,
+    /// 连接失败等网络错误
+    Network { message: String },
+    /// 其他非 2xx 状态码
+    Http { status: u16 },
+    /// 接口返回业务错误码
+    Api { code: i64, message: String },
+    /// 响应结构与预期不符
+    Schema { message: String },
+}
+
+impl GlmApiError {
+    /// 错误类别（用于 metadata 与 error_indicators 配置键）
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::NotConfigured => "not_configured",
+            Self::Unauthorized { .. } => "unauthorized",
+            Self::RateLimited { .. } => "rate_limited",
+            Self::Timeout => "timeout",
+            Self::Network { .. } => "network",
+            Self::Http { .. } => "http",
+            Self::Api { .. } => "api",
+            Self::Schema { .. } => "schema",
+        }
+    }
+
+    /// 是否为临时错误（重试可能恢复，期间可继续显示旧数据）
+    pub fn is_transient(&self) -> bool {
+        matches!(
+            self,
+            Self::RateLimited { .. } | Self::Timeout | Self::Network { .. }
+        )
+    }
+
+    /// 默认状态提示
+    pub fn default_indicator(&self) -> &'static str {
+        match self {
+            Self::NotConfigured => "未配置 GLM",
+            Self::Unauthorized { .. } => "🔒 认证失败",
+            Self::RateLimited { .. } => "🐢 请求受限",
+            Self::Timeout => "⌛ 请求超时",
+            Self::Network { .. } => "📡 网络错误",
+            Self::Http { .. } | Self::Api { .. } => "⚠ 接口错误",
+            Self::Schema { .. } => "⚠ 数据格式异常",
+        }
+    }
+
+    /// 状态提示，优先使用 overrides 中按 kind 配置的文本
+    pub fn indicator(&self, overrides: &HashMap<String, String>) -> String {
+        overrides
+            .get(self.kind())
+            .cloned()
+            .unwrap_or_else(|| self.default_indicator().to_string())
+    }
+
+    /// 根据 HTTP 状态码分类
+    fn from_status(status: reqwest::StatusCode, retry_after: Option<u64>) -> Self {
+        match status.as_u16() {
+            401 | 403 => Self::Unauthorized {
+                status: status.as_u16(),
+            },
+            429 => Self::RateLimited { retry_after },
+            status => Self::Http { status },
+        }
+    }
+}
+
+impl std::fmt::Display for GlmApiError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NotConfigured => write!(f, "GLM API not configured"),
+            Self::Unauthorized { status } => {
+                write!(f, "GLM API rejected the auth token (HTTP {})", status)
+            }
+            Self::RateLimited {
+                retry_after: Some(secs),
+            } => write!(f, "GLM API rate limited, retry after {}s", secs),
+            Self::RateLimited { retry_after: None } => write!(f, "GLM API rate limited"),
+            Self::Timeout => write!(f, "GLM API request timed out"),
+            Self::Network { message } => write!(f, "GLM API network error: {}", message),
+            Self::Http { status } => write!(f, "GLM API request failed: HTTP {}", status),
+            Self::Api { code, message } => write!(f, "GLM API error {}: {}", code, message),
+            Self::Schema { message } => write!(f, "unexpected GLM API response: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for GlmApiError {}
+
+impl From<reqwest::Error> for GlmApiError {
+    fn from(e: reqwest::Error) -> Self {
+        if e.is_timeout() {
+            Self::Timeout
+        } else if e.is_decode() {
+            Self::Schema {
+                message: e.to_string(),
+            }
+        } else if let Some(status) = e.status() {
+            Self::from_status(status, None)
+        } else {
+            Self::Network {
+                message: e.to_string(),
+            }
+        }
+    }
+}
+
+impl From<serde_json::Error> for GlmApiError {
+    fn from(e: serde_json::Error) -> Self {
+        Self::Schema {
+            message: e.to_string(),
+        }
+    }
+}
+
+impl GlmPlatform {
+    /// 按地址识别平台
+    pub fn detect(url: &str) -> Option<Self> {
+        if url.contains("api.z.ai") {
+            Some(Self::ZAI)
+        } else if url.contains("bigmodel.cn") {
+            Some(Self::ZHIPU)
+        } else {
+            None
+        }
+    }
+}
+
 impl GlmApiConfig {
     /// 从配置创建 GLM API 配置
     pub fn new(base_url: String, auth_token: String) -> Self {
-        Self { base_url, auth_token }
+        Self {
+            base_url,
+            auth_token,
+            timeout: Duration::from_secs(DEFAULT_TIMEOUT_SECS),
+            monitor_base_url: None,
+            platform: None,
+            plan_thresholds: PlanThresholds::default(),
+        }
+    }
+
+    /// 设置请求超时
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
     }
 
-    /// 判断平台类型
+    /// 设置监控接口根地址（如内部反向代理）
+    pub fn with_monitor_base_url(mut self, monitor_base_url: String) -> Self {
+        self.monitor_base_url = Some(monitor_base_url);
+        self
+    }
+
+    /// 设置套餐识别阈值
+    pub fn with_plan_thresholds(mut self, plan_thresholds: PlanThresholds) -> Self {
+        self.plan_thresholds = plan_thresholds;
+        self
+    }
+
+    /// 显式指定平台类型
+    pub fn with_platform(mut self, platform: GlmPlatform) -> Self {
+        self.platform = Some(platform);
+        self
+    }
+
+    /// 判断平台类型：显式配置优先，其次按地址识别，默认为 ZHIPU
     pub fn platform(&self) -> GlmPlatform {
-        if self.base_url.contains("api.z.ai") {
-            GlmPlatform::ZAI
-        } else if self.base_url.contains("bigmodel.cn") {
-            GlmPlatform::ZHIPU
-        } else {
-            // 默认为 ZHIPU
-            GlmPlatform::ZHIPU
+        self.platform
+            .or_else(|| GlmPlatform::detect(&self.base_url))
+            .or_else(|| {
+                self.monitor_base_url
+                    .as_deref()
+                    .and_then(GlmPlatform::detect)
+            })
+            .unwrap_or(GlmPlatform::ZHIPU)
+    }
+
+    /// 获取监控接口根地址（scheme://host[:port]）
+    pub fn monitor_base(&self) -> String {
+        if let Some(monitor_base_url) = &self.monitor_base_url {
+            return monitor_base_url.trim_end_matches('/').to_string();
+        }
+        match url::Url::parse(&self.base_url) {
+            Ok(parsed) => parsed.origin().ascii_serialization(),
+            Err(_) => self.base_url.trim_end_matches('/').to_string(),
         }
     }
 
     /// 构建 quota limit URL
     pub fn quota_limit_url(&self) -> String {
-        let parsed = url::Url::parse(&self.base_url).unwrap();
-        let base = format!("{}://{}", parsed.scheme(), parsed.host().unwrap());
-        format!("{}/api/monitor/usage/quota/limit", base)
+        format!("{}/api/monitor/usage/quota/limit", self.monitor_base())
     }
 
     /// 构建模型性能 URL
     pub fn model_performance_url(&self) -> String {
-        let parsed = url::Url::parse(&self.base_url).unwrap();
-        let base = format!("{}://{}", parsed.scheme(), parsed.host().unwrap());
-        format!("{}/api/monitor/usage/model-performance", base)
+        format!(
+            "{}/api/monitor/usage/model-performance",
+            self.monitor_base()
+        )
+    }
+
+    /// 构建模型用量 URL
+    pub fn model_usage_url(&self) -> String {
+        format!("{}/api/monitor/usage/model-usage", self.monitor_base())
+    }
+
+    /// 构建工具用量 URL
+    pub fn tool_usage_url(&self) -> String {
+        format!("{}/api/monitor/usage/tool-usage", self.monitor_base())
+    }
+
+    /// 缓存键：区分不同的接口地址与账号
+    pub fn cache_key(&self, endpoint: &str) -> String {
+        use std::collections::hash_map::DefaultHasher;
+        use std::hash::{Hash, Hasher};
+
+        let mut hasher = DefaultHasher::new();
+        self.monitor_base().hash(&mut hasher);
+        self.auth_token.hash(&mut hasher);
+        format!("{}_{:016x}", endpoint, hasher.finish())
     }
 }
 
@@ -101,6 +379,32 @@ impl GlmApiConfig {
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct GlmQuotaLimit {
     pub limits: Vec<QuotaLimitItem>,
+    /// 接口返回的套餐名称或等级（如有）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub plan_name: Option<String>,
+}
+
+impl GlmQuotaLimit {
+    /// 5 小时 Token 配额（TOKENS_LIMIT）
+    pub fn tokens_limit(&self) -> Option<&QuotaLimitItem> {
+        self.limits
+            .iter()
+            .find(|limit| limit.limit_type == "TOKENS_LIMIT" || limit.limit_type.contains("TOKENS"))
+    }
+
+    /// 识别套餐：优先使用接口返回的套餐字段，其次按 Token 配额与阈值推断
+    pub fn detect_plan(&self, thresholds: &PlanThresholds) -> (GlmPlan, PlanSource) {
+        if let Some(plan) = self.plan_name.as_deref().and_then(GlmPlan::from_name) {
+            return (plan, PlanSource::Api);
+        }
+        match self.tokens_limit().and_then(|limit| limit.usage) {
+            Some(usage) => match GlmPlan::from_quota(usage, thresholds) {
+                GlmPlan::Unknown => (GlmPlan::Unknown, PlanSource::Unknown),
+                plan => (plan, PlanSource::Quota),
+            },
+            None => (GlmPlan::Unknown, PlanSource::Unknown),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -108,85 +412,310 @@ pub struct QuotaLimitItem {
     #[serde(rename = "type")]
     pub limit_type: String,
     pub percentage: f64,
-    #[serde(default)]
+    /// 当前窗口已用量
+    #[serde(rename = "currentValue", alias = "current_value", default)]
     pub current_value: Option<f64>,
+    /// 窗口总额度
     #[serde(default)]
     pub usage: Option<f64>,
     #[serde(default)]
+    pub remaining: Option<f64>,
+    #[serde(rename = "usageDetails", alias = "usage_details", default)]
     pub usage_details: Option<serde_json::Value>,
     #[serde(rename = "nextResetTime", default)]
     pub next_reset_time: Option<i64>,
+    /// 窗口长度，单位见 unit
+    #[serde(default)]
+    pub number: Option<i64>,
+    /// 窗口单位：3 = 小时，5 = 月，其余为天
+    #[serde(default)]
+    pub unit: Option<i64>,
 }
 
-/// Claude settings.json 结构
-#[derive(Debug, Deserialize)]
-struct ClaudeSettings {
-    env: Option<ClaudeEnv>,
+impl QuotaLimitItem {
+    /// 剩余额度（接口未返回时按 usage - current_value 计算）
+    pub fn remaining_value(&self) -> Option<f64> {
+        self.remaining
+            .or_else(|| Some((self.usage? - self.current_value?).max(0.0)))
+    }
+
+    /// 窗口描述，如 "5 hours"、"1 month"
+    pub fn window_label(&self) -> Option<String> {
+        let number = self.number?;
+        let unit = match (self.unit?, number == 1) {
+            (3, true) => "hour",
+            (3, false) => "hours",
+            (5, true) => "month",
+            (5, false) => "months",
+            (_, true) => "day",
+            (_, false) => "days",
+        };
+        Some(format!("{} {}", number, unit))
+    }
+
+    /// 简短窗口描述，如 "5h"、"1mo"、"7d"
+    pub fn window_short(&self) -> Option<String> {
+        let unit = match self.unit? {
+            3 => "h",
+            5 => "mo",
+            _ => "d",
+        };
+        Some(format!("{}{}", self.number?, unit))
+    }
+
+    /// usage_details 中的分项用量，如 MCP 各工具调用次数
+    pub fn details(&self) -> Vec<(String, f64)> {
+        let Some(items) = self.usage_details.as_ref().and_then(|v| v.as_array()) else {
+            return Vec::new();
+        };
+        items
+            .iter()
+            .filter_map(|item| {
+                let name = first_field(item, &["modelCode", "model", "toolName", "name", "type"])?
+                    .as_str()?
+                    .to_string();
+                let value = first_field(item, &["usage", "count", "currentValue", "value"])
+                    .and_then(sum_value)?;
+                Some((name, value))
+            })
+            .collect()
+    }
 }
 
+/// Claude settings.json 结构
 #[derive(Debug, Deserialize)]
-struct ClaudeEnv {
-    #[serde(rename = "ANTHROPIC_AUTH_TOKEN")]
-    auth_token: Option<String>,
-    #[serde(rename = "ANTHROPIC_BASE_URL")]
-    base_url: Option<String>,
+struct ClaudeSettings {
+    env: Option<HashMap<String, serde_json::Value>>,
 }
 
 /// 获取 Claude settings.json 路径
-fn get_claude_settings_path() -> Option<PathBuf> {
+pub fn get_claude_settings_path() -> Option<PathBuf> {
     dirs::home_dir().map(|home| home.join(".claude").join("settings.json"))
 }
 
-/// 从 Claude settings.json 读取 GLM API 配置
-pub fn get_glm_api_config() -> Option<GlmApiConfig> {
-    let settings_path = get_claude_settings_path()?;
+/// 读取 settings.json 的 env 段
+fn load_settings_env() -> HashMap<String, String> {
+    let Some(settings_path) = get_claude_settings_path() else {
+        return HashMap::new();
+    };
 
     if !settings_path.exists() {
-        return None;
+        crate::trace::record(format!("{} not found", settings_path.display()));
+        return HashMap::new();
     }
 
-    let content = std::fs::read_to_string(settings_path).ok()?;
-    let settings: ClaudeSettings = serde_json::from_str(&content).ok()?;
+    let Ok(content) = std::fs::read_to_string(&settings_path) else {
+        return HashMap::new();
+    };
+    let settings: ClaudeSettings = match serde_json::from_str(&content) {
+        Ok(settings) => settings,
+        Err(e) => {
+            crate::trace::record(format!(
+                "failed to parse {}: {}",
+                settings_path.display(),
+                e
+            ));
+            return HashMap::new();
+        }
+    };
+
+    settings
+        .env
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(|(key, value)| Some((key, value.as_str()?.to_string())))
+        .collect()
+}
 
-    let env = settings.env?;
+/// 进程环境变量读取函数（测试中替换）
+type ProcessEnv<'a> = &'a dyn Fn(&str) -> Option<String>;
 
-    // 检查是否是 Z.ai 或 ZHIPU 平台
-    let base_url = env.base_url?;
-    if !base_url.contains("api.z.ai") && !base_url.contains("bigmodel.cn") {
-        return None;
+/// 按优先级读取变量：进程环境变量，其次 settings.json
+fn lookup_env(
+    name: &str,
+    process_env: ProcessEnv,
+    settings_env: &HashMap<String, String>,
+) -> Option<String> {
+    if let Some(value) = process_env(name).filter(|v| !v.is_empty()) {
+        crate::trace::record(format!("{} from process environment", name));
+        return Some(value);
     }
+    if let Some(value) = settings_env.get(name).filter(|v| !v.is_empty()) {
+        crate::trace::record(format!("{} from settings.json", name));
+        return Some(value.clone());
+    }
+    crate::trace::record(format!(
+        "{} not set in process environment or settings.json",
+        name
+    ));
+    None
+}
+
+/// 按 [glm] token 配置读取 Token
+fn read_token(
+    source: &TokenSource,
+    process_env: ProcessEnv,
+    settings_env: &HashMap<String, String>,
+) -> Option<String> {
+    let token = match source {
+        TokenSource::Env(name) => process_env(name),
+        TokenSource::SettingsKey(key) => settings_env.get(key).cloned(),
+        TokenSource::File(path) => {
+            let path = match path.strip_prefix("~") {
+                Ok(rest) => dirs::home_dir()?.join(rest),
+                Err(_) => path.clone(),
+            };
+            std::fs::read_to_string(&path)
+                .inspect_err(|e| {
+                    crate::trace::record(format!("failed to read {}: {}", path.display(), e))
+                })
+                .ok()
+        }
+    };
 
-    let auth_token = env.auth_token?;
+    let token = token
+        .map(|token| token.trim().to_string())
+        .filter(|token| !token.is_empty());
+    match &token {
+        Some(_) => crate::trace::record(format!("auth token from [glm] token = {:?}", source)),
+        None => crate::trace::record(format!("[glm] token = {:?} is empty or missing", source)),
+    }
+    token
+}
 
-    Some(GlmApiConfig::new(base_url, auth_token))
+/// 按 [glm] 配置解析 GLM API 配置（再读取进程环境变量与 settings.json）
+pub fn resolve_glm_api_config(glm: &GlmConfig) -> Option<GlmApiConfig> {
+    resolve_with_env(glm, &|name| std::env::var(name).ok(), &load_settings_env())
 }
 
-/// 查询 GLM quota limit
-pub fn fetch_glm_quota_limit(
+fn resolve_with_env(
+    glm: &GlmConfig,
+    process_env: ProcessEnv,
+    settings_env: &HashMap<String, String>,
+) -> Option<GlmApiConfig> {
+    let base_url = lookup_env("ANTHROPIC_BASE_URL", process_env, settings_env);
+
+    // 未显式配置监控地址或平台时，只接受 Z.ai 或 ZHIPU 平台的地址
+    let explicit = glm.monitor_base_url.is_some() || glm.platform.is_some();
+    let base_url = match base_url {
+        Some(base_url) if explicit || GlmPlatform::detect(&base_url).is_some() => base_url,
+        Some(base_url) => {
+            crate::trace::record(format!(
+                "ANTHROPIC_BASE_URL is not a GLM endpoint: {} (set [glm] monitor_base_url or platform to use it)",
+                base_url
+            ));
+            return None;
+        }
+        None => glm.monitor_base_url.clone()?,
+    };
+
+    let auth_token = match &glm.token {
+        Some(source) => read_token(source, process_env, settings_env)?,
+        None => lookup_env("ANTHROPIC_AUTH_TOKEN", process_env, settings_env)?,
+    };
+
+    let mut config = GlmApiConfig::new(base_url, auth_token)
+        .with_plan_thresholds(glm.plan_thresholds.unwrap_or_default());
+    if let Some(monitor_base_url) = &glm.monitor_base_url {
+        config = config.with_monitor_base_url(monitor_base_url.clone());
+    }
+    match glm.platform {
+        Some(platform) => config = config.with_platform(platform),
+        None if GlmPlatform::detect(&config.base_url).is_none()
+            && config
+                .monitor_base_url
+                .as_deref()
+                .and_then(GlmPlatform::detect)
+                .is_none() =>
+        {
+            crate::trace::record(
+                "platform not recognised from URL, assuming zhipu (set [glm] platform)",
+            )
+        }
+        None => {}
+    }
+
+    Some(config)
+}
+
+/// 从缓存读取 GLM quota limit（不发起网络请求，过期时触发后台刷新）
+pub fn cached_glm_quota_limit(
     config: &GlmApiConfig,
-) -> Result<GlmQuotaLimit, Box<dyn std::error::Error>> {
+    ttl: Duration,
+) -> CacheRead<GlmQuotaLimit, GlmApiError> {
+    ApiCache::new(config.cache_key("quota_limit"), ttl).read_with_error()
+}
+
+/// 刷新 GLM quota limit 缓存（仅在过期时请求）
+pub fn refresh_glm_quota_limit(config: &GlmApiConfig, ttl: Duration) -> Result<(), GlmApiError> {
+    ApiCache::new(config.cache_key("quota_limit"), ttl).refresh(|| fetch_glm_quota_limit(config))
+}
+
+/// 请求 GLM 监控接口并返回 JSON，按状态码与业务错误码分类错误
+fn get_monitor_json(config: &GlmApiConfig, url: &str) -> Result<serde_json::Value, GlmApiError> {
     use reqwest::blocking::Client;
-    use std::time::Duration;
 
     let client = Client::builder()
-        .timeout(Duration::from_secs(10))
+        .timeout(config.timeout)
         .user_agent("glmcode/1.0.0")
         .build()?;
 
     let response = client
-        .get(&config.quota_limit_url())
+        .get(url)
         .header("Authorization", &config.auth_token)
         .header("Accept-Language", "en-US,en")
         .header("Content-Type", "application/json")
-        .send()?;
+        .send()
+        .inspect_err(|e| crate::trace::record(format!("GET {} failed: {}", url, e)))?;
+    crate::trace::record(format!("GET {} -> {}", url, response.status()));
 
-    if !response.status().is_success() {
-        return Err(format!("GLM Quota API request failed: {}", response.status()).into());
+    let status = response.status();
+    if !status.is_success() {
+        let retry_after = response
+            .headers()
+            .get(reqwest::header::RETRY_AFTER)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.trim().parse().ok());
+        return Err(GlmApiError::from_status(status, retry_after));
     }
 
     let response_text = response.text()?;
     let json: serde_json::Value = serde_json::from_str(&response_text)?;
 
+    // 接口可能以 200 状态返回业务错误：{"code": 401, "msg": "...", "success": false}
+    let code = json.get("code").and_then(|v| v.as_i64());
+    let failed = json.get("success").and_then(|v| v.as_bool()) == Some(false)
+        || code.is_some_and(|code| code != 200 && code != 0);
+    if failed {
+        let message = json
+            .get("msg")
+            .or_else(|| json.get("message"))
+            .and_then(|v| v.as_str())
+            .unwrap_or_default()
+            .to_string();
+        crate::trace::record(format!(
+            "GET {} returned error code {:?}: {}",
+            url, code, message
+        ));
+        return Err(match code {
+            Some(401) | Some(403) => GlmApiError::Unauthorized {
+                status: code.unwrap_or_default() as u16,
+            },
+            Some(429) => GlmApiError::RateLimited { retry_after: None },
+            _ => GlmApiError::Api {
+                code: code.unwrap_or_default(),
+                message,
+            },
+        });
+    }
+
+    Ok(json)
+}
+
+/// 查询 GLM quota limit
+pub fn fetch_glm_quota_limit(config: &GlmApiConfig) -> Result<GlmQuotaLimit, GlmApiError> {
+    let json = get_monitor_json(config, &config.quota_limit_url())?;
+
     // Debug: 输出原始 JSON 响应（仅在开发模式下）
     #[cfg(debug_assertions)]
     eprintln!("[DEBUG] GLM API Response: {}", serde_json::to_string_pretty(&json).unwrap_or_default());
@@ -197,21 +726,43 @@ pub fn fetch_glm_quota_limit(
 }
 
 /// 从 JSON 响应中提取 quota limit 数据
-fn extract_quota_limit_data(
-    json: &serde_json::Value,
-) -> Result<GlmQuotaLimit, Box<dyn std::error::Error>> {
+fn extract_quota_limit_data(json: &serde_json::Value) -> Result<GlmQuotaLimit, GlmApiError> {
     // 尝试获取顶层数据对象：json.data 或 json 本身
     let data_obj = json.get("data").unwrap_or(json);
 
     // 从数据对象中提取 limits 数组
     let limits_value = data_obj
         .get("limits")
-        .ok_or("Missing 'limits' field in response")?;
+        .ok_or_else(|| missing_field("limits"))?;
 
     // 解析 limits 数组
     let limits: Vec<QuotaLimitItem> = serde_json::from_value(limits_value.clone())?;
 
-    Ok(GlmQuotaLimit { limits })
+    // 套餐字段（不同平台字段名不一，可能是名称或等级）
+    let plan_name = first_field(
+        data_obj,
+        &[
+            "planName",
+            "plan",
+            "level",
+            "planLevel",
+            "packageName",
+            "productName",
+        ],
+    )
+    .and_then(|value| match value {
+        serde_json::Value::String(name) => Some(name.clone()),
+        serde_json::Value::Number(level) => Some(level.to_string()),
+        _ => None,
+    });
+
+    Ok(GlmQuotaLimit { limits, plan_name })
+}
+
+fn missing_field(field: &str) -> GlmApiError {
+    GlmApiError::Schema {
+        message: format!("missing '{}' field", field),
+    }
 }
 
 /// 格式化重置时间为相对时间显示（如 "3h 40m", "25m", "2d 5h"）
@@ -266,46 +817,39 @@ pub struct GlmModelPerformance {
     pub pro_max_success_rate: Vec<f64>,
 }
 
-/// 查询 GLM 模型性能
-pub fn fetch_glm_model_performance(
+/// 从缓存读取 GLM 模型性能（不发起网络请求，过期时触发后台刷新）
+pub fn cached_glm_model_performance(
     config: &GlmApiConfig,
     hours: i64,
-) -> Result<GlmModelPerformance, Box<dyn std::error::Error>> {
-    use reqwest::blocking::Client;
-    use std::time::Duration;
-
-    let client = Client::builder()
-        .timeout(Duration::from_secs(10))
-        .user_agent("glmcode/1.0.0")
-        .build()?;
-
-    // 计算时间范围：过去 N 小时
-    let now = chrono::Local::now();
-    let end_time = now.format("%Y-%m-%d %H:%M:%S").to_string();
-    let start_time = (now - chrono::Duration::hours(hours))
-        .format("%Y-%m-%d %H:%M:%S")
-        .to_string();
-
-    let url = format!(
-        "{}?startTime={}&endTime={}",
-        config.model_performance_url(),
-        urlencoding::encode(&start_time),
-        urlencoding::encode(&end_time)
-    );
-
-    let response = client
-        .get(&url)
-        .header("Authorization", &config.auth_token)
-        .header("Accept-Language", "en-US,en")
-        .header("Content-Type", "application/json")
-        .send()?;
+    ttl: Duration,
+) -> CacheRead<GlmModelPerformance, GlmApiError> {
+    ApiCache::new(
+        config.cache_key(&format!("model_performance_{}h", hours)),
+        ttl,
+    )
+    .read_with_error()
+}
 
-    if !response.status().is_success() {
-        return Err(format!("GLM Model Performance API request failed: {}", response.status()).into());
-    }
+/// 刷新 GLM 模型性能缓存（仅在过期时请求）
+pub fn refresh_glm_model_performance(
+    config: &GlmApiConfig,
+    hours: i64,
+    ttl: Duration,
+) -> Result<(), GlmApiError> {
+    ApiCache::new(
+        config.cache_key(&format!("model_performance_{}h", hours)),
+        ttl,
+    )
+    .refresh(|| fetch_glm_model_performance(config, hours))
+}
 
-    let response_text = response.text()?;
-    let json: serde_json::Value = serde_json::from_str(&response_text)?;
+/// 查询 GLM 模型性能
+pub fn fetch_glm_model_performance(
+    config: &GlmApiConfig,
+    hours: i64,
+) -> Result<GlmModelPerformance, GlmApiError> {
+    let url = format!("{}{}", config.model_performance_url(), window_query(hours));
+    let json = get_monitor_json(config, &url)?;
 
     // Debug: 输出原始 JSON 响应（仅在开发模式下）
     #[cfg(debug_assertions)]
@@ -319,30 +863,26 @@ pub fn fetch_glm_model_performance(
 /// 从 JSON 响应中提取模型性能数据
 fn extract_model_performance_data(
     json: &serde_json::Value,
-) -> Result<GlmModelPerformance, Box<dyn std::error::Error>> {
+) -> Result<GlmModelPerformance, GlmApiError> {
     // 尝试获取顶层数据对象：json.data 或 json 本身
     let data_obj = json.get("data").unwrap_or(json);
 
     // 解析各个字段
-    let x_time: Vec<String> = serde_json::from_value(
-        data_obj.get("x_time").ok_or("Missing 'x_time' field")?.clone()
-    )?;
-
-    let lite_decode_speed: Vec<f64> = serde_json::from_value(
-        data_obj.get("liteDecodeSpeed").ok_or("Missing 'liteDecodeSpeed' field")?.clone()
-    )?;
+    let field = |name: &str| -> Result<Vec<f64>, GlmApiError> {
+        let value = data_obj.get(name).ok_or_else(|| missing_field(name))?;
+        Ok(serde_json::from_value(value.clone())?)
+    };
 
-    let pro_max_decode_speed: Vec<f64> = serde_json::from_value(
-        data_obj.get("proMaxDecodeSpeed").ok_or("Missing 'proMaxDecodeSpeed' field")?.clone()
-    )?;
-
-    let lite_success_rate: Vec<f64> = serde_json::from_value(
-        data_obj.get("liteSuccessRate").ok_or("Missing 'liteSuccessRate' field")?.clone()
-    )?;
-
-    let pro_max_success_rate: Vec<f64> = serde_json::from_value(
-        data_obj.get("proMaxSuccessRate").ok_or("Missing 'proMaxSuccessRate' field")?.clone()
+    let x_time: Vec<String> = serde_json::from_value(
+        data_obj
+            .get("x_time")
+            .ok_or_else(|| missing_field("x_time"))?
+            .clone(),
     )?;
+    let lite_decode_speed = field("liteDecodeSpeed")?;
+    let pro_max_decode_speed = field("proMaxDecodeSpeed")?;
+    let lite_success_rate = field("liteSuccessRate")?;
+    let pro_max_success_rate = field("proMaxSuccessRate")?;
 
     Ok(GlmModelPerformance {
         x_time,
@@ -352,3 +892,1101 @@ fn extract_model_performance_data(
         pro_max_success_rate,
     })
 }
+
+/// 构建时间范围查询参数：过去 N 小时
+fn window_query(hours: i64) -> String {
+    let now = chrono::Local::now();
+    let end_time = now.format("%Y-%m-%d %H:%M:%S").to_string();
+    let start_time = (now - chrono::Duration::hours(hours))
+        .format("%Y-%m-%d %H:%M:%S")
+        .to_string();
+
+    format!(
+        "?startTime={}&endTime={}",
+        urlencoding::encode(&start_time),
+        urlencoding::encode(&end_time)
+    )
+}
+
+/// 按候选字段名依次查找
+fn first_field<'a>(obj: &'a serde_json::Value, names: &[&str]) -> Option<&'a serde_json::Value> {
+    names.iter().find_map(|name| obj.get(*name))
+}
+
+/// 读取数值字段；按时间序列返回的数组取总和
+fn sum_value(value: &serde_json::Value) -> Option<f64> {
+    match value {
+        serde_json::Value::Array(items) => Some(items.iter().filter_map(sum_value).sum()),
+        serde_json::Value::String(text) => text.parse().ok(),
+        value => value.as_f64(),
+    }
+}
+
+/// 在数据对象中查找按条目返回的列表（data 本身或其中第一个对象数组）
+fn find_item_list(data: &serde_json::Value) -> Option<&Vec<serde_json::Value>> {
+    let is_item_list = |value: &serde_json::Value| {
+        value
+            .as_array()
+            .is_some_and(|items| !items.is_empty() && items.iter().all(|item| item.is_object()))
+    };
+    if is_item_list(data) {
+        return data.as_array();
+    }
+    data.as_object()?
+        .values()
+        .find(|value| is_item_list(value))
+        .and_then(|value| value.as_array())
+}
+
+/// GLM 模型用量数据（单个时间窗口）
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct GlmModelUsage {
+    /// 按 Token 用量降序排列
+    pub models: Vec<ModelUsageItem>,
+    pub total_tokens: f64,
+    pub total_calls: f64,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ModelUsageItem {
+    pub model: String,
+    pub tokens: f64,
+    pub calls: f64,
+}
+
+/// 从缓存读取 GLM 模型用量（不发起网络请求，过期时触发后台刷新）
+pub fn cached_glm_model_usage(
+    config: &GlmApiConfig,
+    hours: i64,
+    ttl: Duration,
+) -> CacheRead<GlmModelUsage, GlmApiError> {
+    ApiCache::new(config.cache_key(&format!("model_usage_{}h", hours)), ttl).read_with_error()
+}
+
+/// 刷新 GLM 模型用量缓存（仅在过期时请求）
+pub fn refresh_glm_model_usage(
+    config: &GlmApiConfig,
+    hours: i64,
+    ttl: Duration,
+) -> Result<(), GlmApiError> {
+    ApiCache::new(config.cache_key(&format!("model_usage_{}h", hours)), ttl)
+        .refresh(|| fetch_glm_model_usage(config, hours))
+}
+
+/// 查询 GLM 模型用量
+pub fn fetch_glm_model_usage(
+    config: &GlmApiConfig,
+    hours: i64,
+) -> Result<GlmModelUsage, GlmApiError> {
+    let url = format!("{}{}", config.model_usage_url(), window_query(hours));
+    let json = get_monitor_json(config, &url)?;
+
+    extract_model_usage_data(&json)
+}
+
+/// 从 JSON 响应中提取模型用量数据
+///
+/// 兼容两种结构：按模型返回的条目列表，或只有 totalUsage 汇总。
+fn extract_model_usage_data(json: &serde_json::Value) -> Result<GlmModelUsage, GlmApiError> {
+    const MODEL_KEYS: &[&str] = &["modelName", "model", "model_name", "name"];
+    const TOKEN_KEYS: &[&str] = &["totalTokens", "tokensUsage", "tokenUsage", "tokens"];
+    const CALL_KEYS: &[&str] = &["modelCallCount", "callCount", "calls", "count"];
+
+    let data_obj = json.get("data").unwrap_or(json);
+
+    let mut models: Vec<ModelUsageItem> = find_item_list(data_obj)
+        .map(|items| {
+            items
+                .iter()
+                .filter_map(|item| {
+                    let model = first_field(item, MODEL_KEYS)?.as_str()?.to_string();
+                    Some(ModelUsageItem {
+                        model,
+                        tokens: first_field(item, TOKEN_KEYS).and_then(sum_value)?,
+                        calls: first_field(item, CALL_KEYS)
+                            .and_then(sum_value)
+                            .unwrap_or(0.0),
+                    })
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    models.sort_by(|a, b| b.tokens.total_cmp(&a.tokens));
+
+    let totals = data_obj.get("totalUsage").unwrap_or(data_obj);
+    let total_tokens = first_field(totals, &["totalTokensUsage", "totalTokens"])
+        .or_else(|| first_field(data_obj, TOKEN_KEYS))
+        .and_then(sum_value)
+        .unwrap_or_else(|| models.iter().map(|m| m.tokens).sum());
+    let total_calls = first_field(totals, &["totalModelCallCount", "totalCallCount"])
+        .or_else(|| first_field(data_obj, CALL_KEYS))
+        .and_then(sum_value)
+        .unwrap_or_else(|| models.iter().map(|m| m.calls).sum());
+
+    if models.is_empty() && data_obj.get("totalUsage").is_none() {
+        return Err(missing_field("totalUsage"));
+    }
+
+    Ok(GlmModelUsage {
+        models,
+        total_tokens,
+        total_calls,
+    })
+}
+
+/// GLM MCP 工具调用次数（单个时间窗口）
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct GlmToolUsage {
+    /// 按调用次数降序排列
+    pub tools: Vec<ToolUsageItem>,
+}
+
+impl GlmToolUsage {
+    /// 按调用次数降序排列
+    fn sorted(mut tools: Vec<ToolUsageItem>) -> Self {
+        tools.sort_by(|a, b| b.count.total_cmp(&a.count));
+        Self { tools }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ToolUsageItem {
+    /// 接口返回的工具名，如 networkSearch、webRead、vision
+    pub tool: String,
+    pub count: f64,
+}
+
+/// 从缓存读取 GLM 工具用量（不发起网络请求，过期时触发后台刷新）
+pub fn cached_glm_tool_usage(
+    config: &GlmApiConfig,
+    hours: i64,
+    ttl: Duration,
+) -> CacheRead<GlmToolUsage, GlmApiError> {
+    ApiCache::new(config.cache_key(&format!("tool_usage_{}h", hours)), ttl).read_with_error()
+}
+
+/// 刷新 GLM 工具用量缓存（仅在过期时请求）
+pub fn refresh_glm_tool_usage(
+    config: &GlmApiConfig,
+    hours: i64,
+    ttl: Duration,
+) -> Result<(), GlmApiError> {
+    ApiCache::new(config.cache_key(&format!("tool_usage_{}h", hours)), ttl)
+        .refresh(|| fetch_glm_tool_usage(config, hours))
+}
+
+/// 查询 GLM 工具用量
+pub fn fetch_glm_tool_usage(
+    config: &GlmApiConfig,
+    hours: i64,
+) -> Result<GlmToolUsage, GlmApiError> {
+    let url = format!("{}{}", config.tool_usage_url(), window_query(hours));
+    let json = get_monitor_json(config, &url)?;
+
+    extract_tool_usage_data(&json)
+}
+
+/// 从 JSON 响应中提取工具调用次数
+///
+/// 兼容两种结构：按工具返回的条目列表，或 totalUsage 中的 `total*Count` 汇总字段。
+fn extract_tool_usage_data(json: &serde_json::Value) -> Result<GlmToolUsage, GlmApiError> {
+    let data_obj = json.get("data").unwrap_or(json);
+
+    if let Some(items) = find_item_list(data_obj) {
+        let tools: Vec<ToolUsageItem> = items
+            .iter()
+            .filter_map(|item| {
+                Some(ToolUsageItem {
+                    tool: first_field(item, &["toolName", "tool", "name"])?
+                        .as_str()?
+                        .to_string(),
+                    count: first_field(item, &["count", "callCount", "totalCount"])
+                        .and_then(sum_value)?,
+                })
+            })
+            .collect();
+        if !tools.is_empty() {
+            return Ok(GlmToolUsage::sorted(tools));
+        }
+    }
+
+    let totals = data_obj
+        .get("totalUsage")
+        .and_then(|v| v.as_object())
+        .or_else(|| data_obj.as_object())
+        .ok_or_else(|| missing_field("totalUsage"))?;
+    let tools: Vec<ToolUsageItem> = totals
+        .iter()
+        .filter_map(|(key, value)| {
+            let name = key.strip_suffix("Count")?;
+            let name = name.strip_prefix("total").unwrap_or(name);
+            let name = name.strip_suffix("Mcp").unwrap_or(name);
+            let mut chars = name.chars();
+            let tool = chars.next()?.to_lowercase().chain(chars).collect();
+            Some(ToolUsageItem {
+                tool,
+                count: sum_value(value)?,
+            })
+        })
+        .collect();
+
+    if tools.is_empty() {
+        return Err(missing_field("totalUsage"));
+    }
+    Ok(GlmToolUsage::sorted(tools))
+}
+
+/// 为 true 时刷新忽略 TTL（诊断模式使用）
+static FORCE_REFRESH: AtomicBool = AtomicBool::new(false);
+
+/// 设置刷新是否忽略 TTL，强制请求接口
+pub fn set_force_refresh(force: bool) {
+    FORCE_REFRESH.store(force, Ordering::SeqCst);
+}
+
+/// 缓存文件内容
+#[derive(Debug, Serialize, Deserialize)]
+struct CacheEntry<T> {
+    cached_at: String,
+    data: T,
+}
+
+/// 最近一次刷新失败的记录
+#[derive(Debug, Serialize, Deserialize)]
+struct ErrorEntry<E> {
+    failed_at: String,
+    error: E,
+}
+
+/// 渲染路径读取结果：缓存数据及最近一次刷新错误（成功刷新后清除）
+#[derive(Debug, Clone)]
+pub struct CacheRead<T, E> {
+    pub data: Option<T>,
+    pub error: Option<E>,
+}
+
+/// 判断写入时间是否仍在 TTL 内
+fn within_ttl(timestamp: &str, ttl: Duration) -> Option<bool> {
+    let written_at = chrono::DateTime::parse_from_rfc3339(timestamp).ok()?;
+    let elapsed = chrono::Utc::now().signed_duration_since(written_at);
+    Some(elapsed.num_milliseconds() >= 0 && (elapsed.num_milliseconds() as u128) < ttl.as_millis())
+}
+
+/// 通用磁盘缓存（stale-while-revalidate）
+///
+/// 渲染进程只通过 [`ApiCache::read`] 读取缓存，数据缺失或过期时启动
+/// 后台刷新进程；刷新进程通过 [`ApiCache::refresh`] 请求接口并写回缓存，
+/// 锁文件保证同一时间只有一个进程在请求同一接口。刷新失败时记录错误，
+/// 一个 TTL 内不再重试。
+#[derive(Debug, Clone)]
+pub struct ApiCache {
+    key: String,
+    ttl: Duration,
+    dir: Option<PathBuf>,
+}
+
+impl ApiCache {
+    pub fn new(key: impl Into<String>, ttl: Duration) -> Self {
+        Self {
+            key: key.into(),
+            ttl,
+            dir: None,
+        }
+    }
+
+    /// 使用指定的缓存目录（默认 ~/.claude/glmcode/cache）
+    pub fn with_dir(mut self, dir: PathBuf) -> Self {
+        self.dir = Some(dir);
+        self
+    }
+
+    /// 默认缓存目录 ~/.claude/glmcode/cache
+    pub fn default_dir() -> Option<PathBuf> {
+        dirs::home_dir().map(|home| home.join(".claude").join("glmcode").join("cache"))
+    }
+
+    fn cache_dir(&self) -> Option<PathBuf> {
+        self.dir.clone().or_else(Self::default_dir)
+    }
+
+    fn cache_path(&self) -> Option<PathBuf> {
+        self.cache_dir()
+            .map(|dir| dir.join(format!("{}.json", self.key)))
+    }
+
+    fn error_path(&self) -> Option<PathBuf> {
+        self.cache_dir()
+            .map(|dir| dir.join(format!("{}.error.json", self.key)))
+    }
+
+    fn lock_path(&self) -> Option<PathBuf> {
+        self.cache_dir()
+            .map(|dir| dir.join(format!("{}.lock", self.key)))
+    }
+
+    /// 读取缓存，返回数据及其是否仍在 TTL 内
+    pub fn load<T: DeserializeOwned>(&self) -> Option<(T, bool)> {
+        let content = fs::read_to_string(self.cache_path()?).ok()?;
+        let entry: CacheEntry<T> = serde_json::from_str(&content).ok()?;
+        let fresh = within_ttl(&entry.cached_at, self.ttl)?;
+        Some((entry.data, fresh))
+    }
+
+    /// 读取最近一次刷新错误，返回错误及其是否仍在重试退避期内
+    pub fn load_error<E: DeserializeOwned>(&self) -> Option<(E, bool)> {
+        let content = fs::read_to_string(self.error_path()?).ok()?;
+        let entry: ErrorEntry<E> = serde_json::from_str(&content).ok()?;
+        let recent = within_ttl(&entry.failed_at, self.ttl)?;
+        Some((entry.error, recent))
+    }
+
+    /// 写入缓存（先写临时文件再重命名，避免读到半截内容），并清除错误记录
+    pub fn store<T: Serialize>(&self, data: &T) {
+        self.store_at(data, chrono::Utc::now());
+    }
+
+    /// 同 [`ApiCache::store`]，但使用给定的缓存时间（迁移旧缓存时保留原时间）
+    pub fn store_at<T: Serialize>(&self, data: &T, cached_at: chrono::DateTime<chrono::Utc>) {
+        let entry = CacheEntry {
+            cached_at: cached_at.to_rfc3339(),
+            data,
+        };
+        self.write_atomic(self.cache_path(), &entry);
+        if let Some(path) = self.error_path() {
+            let _ = fs::remove_file(path);
+        }
+    }
+
+    /// 记录刷新错误
+    pub fn store_error<E: Serialize>(&self, error: &E) {
+        let entry = ErrorEntry {
+            failed_at: chrono::Utc::now().to_rfc3339(),
+            error,
+        };
+        self.write_atomic(self.error_path(), &entry);
+    }
+
+    fn write_atomic<V: Serialize>(&self, path: Option<PathBuf>, value: &V) {
+        let (Some(dir), Some(path)) = (self.cache_dir(), path) else {
+            return;
+        };
+        let _ = fs::create_dir_all(&dir);
+
+        if let Ok(json) = serde_json::to_string(value) {
+            let tmp_path = dir.join(format!("{}.{}.tmp", self.key, std::process::id()));
+            if fs::write(&tmp_path, json).is_ok() && fs::rename(&tmp_path, &path).is_err() {
+                let _ = fs::remove_file(&tmp_path);
+            }
+        }
+    }
+
+    /// 渲染路径：只读缓存，缺失或过期时请求后台刷新
+    pub fn read<T: DeserializeOwned>(&self) -> Option<T> {
+        self.read_with_error::<T, serde::de::IgnoredAny>().data
+    }
+
+    /// 同 [`ApiCache::read`]，同时返回最近一次刷新错误
+    ///
+    /// 退避期内不再请求后台刷新。
+    pub fn read_with_error<T: DeserializeOwned, E: DeserializeOwned>(&self) -> CacheRead<T, E> {
+        let loaded = self.load::<T>();
+        let (error, backoff) = match self.load_error::<E>() {
+            Some((error, recent)) => (Some(error), recent),
+            None => (None, false),
+        };
+
+        let state = match &loaded {
+            Some((_, true)) => "fresh",
+            Some((_, false)) => "stale",
+            None => "missing",
+        };
+        let needs_refresh = !matches!(loaded, Some((_, true)));
+        if needs_refresh && backoff {
+            crate::trace::record(format!(
+                "cache {}: {}, last refresh failed, waiting before retry",
+                self.key, state
+            ));
+        } else if needs_refresh {
+            crate::trace::record(format!("cache {}: {}, refresh requested", self.key, state));
+            crate::refresh::request_refresh();
+        } else {
+            crate::trace::record(format!("cache {}: {}", self.key, state));
+        }
+
+        CacheRead {
+            data: loaded.map(|(data, _)| data),
+            error,
+        }
+    }
+
+    /// 刷新路径：缓存过期时请求接口并写回，其他进程正在刷新或上次失败
+    /// 仍在退避期内时直接跳过
+    pub fn refresh<T, E, F>(&self, fetch: F) -> Result<(), E>
+    where
+        T: Serialize + DeserializeOwned,
+        E: Serialize + DeserializeOwned,
+        F: FnOnce() -> Result<T, E>,
+    {
+        let force = FORCE_REFRESH.load(Ordering::Relaxed);
+        if !force && matches!(self.load::<T>(), Some((_, true))) {
+            return Ok(());
+        }
+        if !force {
+            if let Some((error, true)) = self.load_error::<E>() {
+                crate::trace::record(format!("cache {}: last refresh failed recently", self.key));
+                return Err(error);
+            }
+        }
+
+        let Some(_lock) = self.lock_path().and_then(FileLock::try_acquire) else {
+            crate::trace::record(format!("cache {}: another process is refreshing", self.key));
+            return Ok(());
+        };
+
+        match fetch() {
+            Ok(data) => {
+                self.store(&data);
+                Ok(())
+            }
+            Err(error) => {
+                self.store_error(&error);
+                Err(error)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+    use std::sync::atomic::AtomicUsize;
+    use std::sync::Arc;
+
+    const QUOTA_BODY: &str = r#"{"code":200,"success":true,"data":{"limits":[{"type":"TOKENS_LIMIT","percentage":42.0}]}}"#;
+
+    /// 本地 HTTP 服务：每个请求都返回 body，并统计请求次数
+    fn mock_server(body: &'static str) -> (String, Arc<AtomicUsize>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let hits = Arc::new(AtomicUsize::new(0));
+        let counter = hits.clone();
+        std::thread::spawn(move || {
+            for mut stream in listener.incoming().flatten() {
+                let mut buf = [0u8; 4096];
+                let _ = stream.read(&mut buf);
+                counter.fetch_add(1, Ordering::SeqCst);
+                let response = format!(
+                    "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    body.len(),
+                    body
+                );
+                let _ = stream.write_all(response.as_bytes());
+            }
+        });
+        (format!("http://{}", addr), hits)
+    }
+
+    /// 本地 HTTP 服务：等待 delay 后返回完整的原始响应
+    fn mock_response(response: String, delay: Duration) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        std::thread::spawn(move || {
+            for mut stream in listener.incoming().flatten() {
+                let mut buf = [0u8; 4096];
+                let _ = stream.read(&mut buf);
+                std::thread::sleep(delay);
+                let _ = stream.write_all(response.as_bytes());
+            }
+        });
+        format!("http://{}", addr)
+    }
+
+    fn api_config(base_url: &str) -> GlmApiConfig {
+        GlmApiConfig {
+            base_url: base_url.to_string(),
+            auth_token: "test-token".to_string(),
+            timeout: Duration::from_secs(3),
+            monitor_base_url: None,
+            platform: Some(GlmPlatform::ZHIPU),
+            plan_thresholds: PlanThresholds::default(),
+        }
+    }
+
+    fn temp_cache_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("glmcode-api-test-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    fn quota_cache(name: &str, ttl: Duration) -> (ApiCache, PathBuf) {
+        let dir = temp_cache_dir(name);
+        (ApiCache::new("glm_quota", ttl).with_dir(dir.clone()), dir)
+    }
+
+    fn sample_quota(percentage: f64) -> GlmQuotaLimit {
+        let json = serde_json::json!({
+            "limits": [{ "type": "TOKENS_LIMIT", "percentage": percentage }]
+        });
+        extract_quota_limit_data(&json).unwrap()
+    }
+
+    #[test]
+    fn refresh_skips_fresh_entry() {
+        let (url, hits) = mock_server(QUOTA_BODY);
+        let (cache, dir) = quota_cache("fresh", Duration::from_secs(60));
+        cache.store(&sample_quota(10.0));
+
+        let config = api_config(&url);
+        let result = cache.refresh(|| fetch_glm_quota_limit(&config));
+
+        assert!(result.is_ok());
+        assert_eq!(hits.load(Ordering::SeqCst), 0);
+        let (quota, fresh) = cache.load::<GlmQuotaLimit>().unwrap();
+        assert!(fresh);
+        assert_eq!(quota.limits[0].percentage, 10.0);
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn refresh_fetches_stale_entry() {
+        let (url, hits) = mock_server(QUOTA_BODY);
+        let (cache, dir) = quota_cache("stale", Duration::ZERO);
+        cache.store(&sample_quota(10.0));
+
+        let config = api_config(&url);
+        let result = cache.refresh(|| fetch_glm_quota_limit(&config));
+
+        assert!(result.is_ok());
+        assert_eq!(hits.load(Ordering::SeqCst), 1);
+        let (quota, _) = cache.load::<GlmQuotaLimit>().unwrap();
+        assert_eq!(quota.limits[0].percentage, 42.0);
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn refresh_fetches_missing_entry() {
+        let (url, hits) = mock_server(QUOTA_BODY);
+        let (cache, dir) = quota_cache("missing", Duration::from_secs(60));
+        assert!(cache.load::<GlmQuotaLimit>().is_none());
+
+        let config = api_config(&url);
+        let result = cache.refresh(|| fetch_glm_quota_limit(&config));
+
+        assert!(result.is_ok());
+        assert_eq!(hits.load(Ordering::SeqCst), 1);
+        let (quota, fresh) = cache.load::<GlmQuotaLimit>().unwrap();
+        assert!(fresh);
+        assert_eq!(quota.limits[0].percentage, 42.0);
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn refresh_skips_while_lock_is_held() {
+        let (url, hits) = mock_server(QUOTA_BODY);
+        let (cache, dir) = quota_cache("locked", Duration::from_secs(60));
+        let lock = FileLock::try_acquire(dir.join("glm_quota.lock")).unwrap();
+
+        let config = api_config(&url);
+        let result = cache.refresh(|| fetch_glm_quota_limit(&config));
+
+        assert!(result.is_ok());
+        assert_eq!(hits.load(Ordering::SeqCst), 0);
+        assert!(cache.load::<GlmQuotaLimit>().is_none());
+
+        drop(lock);
+        assert!(cache.refresh(|| fetch_glm_quota_limit(&config)).is_ok());
+        assert_eq!(hits.load(Ordering::SeqCst), 1);
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn refresh_backs_off_after_error() {
+        let (url, hits) = mock_server(r#"{"code":401,"msg":"token expired","success":false}"#);
+        let (cache, dir) = quota_cache("backoff", Duration::from_secs(60));
+
+        let config = api_config(&url);
+        let first = cache.refresh(|| fetch_glm_quota_limit(&config));
+        assert_eq!(first, Err(GlmApiError::Unauthorized { status: 401 }));
+        assert_eq!(hits.load(Ordering::SeqCst), 1);
+
+        let second = cache.refresh(|| fetch_glm_quota_limit(&config));
+        assert_eq!(second, first);
+        assert_eq!(hits.load(Ordering::SeqCst), 1);
+
+        let read = cache.read_with_error::<GlmQuotaLimit, GlmApiError>();
+        assert!(read.data.is_none());
+        assert_eq!(read.error, first.err());
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn read_reports_cached_data() {
+        let (fresh_cache, fresh_dir) = quota_cache("read-fresh", Duration::from_secs(60));
+        fresh_cache.store(&sample_quota(30.0));
+        let read = fresh_cache.read_with_error::<GlmQuotaLimit, GlmApiError>();
+        assert_eq!(read.data.unwrap().limits[0].percentage, 30.0);
+        assert!(read.error.is_none());
+
+        // 过期数据照常返回，由后台进程刷新
+        let (stale_cache, stale_dir) = quota_cache("read-stale", Duration::ZERO);
+        stale_cache.store(&sample_quota(70.0));
+        let stale: Option<GlmQuotaLimit> = stale_cache.read();
+        assert_eq!(stale.unwrap().limits[0].percentage, 70.0);
+
+        let (missing_cache, missing_dir) = quota_cache("read-missing", Duration::from_secs(60));
+        assert!(missing_cache.read::<GlmQuotaLimit>().is_none());
+
+        for dir in [fresh_dir, stale_dir, missing_dir] {
+            let _ = fs::remove_dir_all(dir);
+        }
+    }
+
+    #[test]
+    fn status_codes_map_to_error_kinds() {
+        use reqwest::StatusCode;
+
+        assert_eq!(
+            GlmApiError::from_status(StatusCode::UNAUTHORIZED, None),
+            GlmApiError::Unauthorized { status: 401 }
+        );
+        assert_eq!(
+            GlmApiError::from_status(StatusCode::FORBIDDEN, None),
+            GlmApiError::Unauthorized { status: 403 }
+        );
+        assert_eq!(
+            GlmApiError::from_status(StatusCode::TOO_MANY_REQUESTS, Some(30)),
+            GlmApiError::RateLimited {
+                retry_after: Some(30)
+            }
+        );
+        assert_eq!(
+            GlmApiError::from_status(StatusCode::INTERNAL_SERVER_ERROR, None),
+            GlmApiError::Http { status: 500 }
+        );
+        assert_eq!(
+            GlmApiError::from_status(StatusCode::SERVICE_UNAVAILABLE, Some(5)),
+            GlmApiError::Http { status: 503 }
+        );
+    }
+
+    #[test]
+    fn only_rate_limits_timeouts_and_network_errors_are_transient() {
+        let transient = [
+            GlmApiError::RateLimited { retry_after: None },
+            GlmApiError::Timeout,
+            GlmApiError::Network {
+                message: "connection refused".to_string(),
+            },
+        ];
+        let permanent = [
+            GlmApiError::NotConfigured,
+            GlmApiError::Unauthorized { status: 401 },
+            GlmApiError::Http { status: 502 },
+            GlmApiError::Api {
+                code: 500,
+                message: "internal".to_string(),
+            },
+            GlmApiError::Schema {
+                message: "missing data".to_string(),
+            },
+        ];
+        assert!(transient.iter().all(GlmApiError::is_transient));
+        assert!(!permanent.iter().any(GlmApiError::is_transient));
+    }
+
+    #[test]
+    fn indicator_prefers_configured_override() {
+        let mut overrides = HashMap::new();
+        overrides.insert("timeout".to_string(), "slow".to_string());
+
+        assert_eq!(GlmApiError::Timeout.indicator(&overrides), "slow");
+        assert_eq!(
+            GlmApiError::RateLimited { retry_after: None }.indicator(&overrides),
+            "🐢 请求受限"
+        );
+        assert_eq!(
+            GlmApiError::Http { status: 500 }.indicator(&HashMap::new()),
+            GlmApiError::Api {
+                code: 1,
+                message: String::new()
+            }
+            .default_indicator()
+        );
+    }
+
+    #[test]
+    fn fetch_classifies_http_failures() {
+        let rate_limited = mock_response(
+            "HTTP/1.1 429 Too Many Requests\r\nRetry-After: 30\r\nContent-Length: 0\r\nConnection: close\r\n\r\n".to_string(),
+            Duration::ZERO,
+        );
+        assert_eq!(
+            fetch_glm_quota_limit(&api_config(&rate_limited)).err(),
+            Some(GlmApiError::RateLimited {
+                retry_after: Some(30)
+            })
+        );
+
+        let unavailable = mock_response(
+            "HTTP/1.1 503 Service Unavailable\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+                .to_string(),
+            Duration::ZERO,
+        );
+        assert_eq!(
+            fetch_glm_quota_limit(&api_config(&unavailable)).err(),
+            Some(GlmApiError::Http { status: 503 })
+        );
+    }
+
+    #[test]
+    fn fetch_reports_undecodable_body_as_schema_error() {
+        let body = "<html>gateway</html>";
+        let url = mock_response(
+            format!(
+                "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                body.len(),
+                body
+            ),
+            Duration::ZERO,
+        );
+        let result = fetch_glm_quota_limit(&api_config(&url));
+        assert!(matches!(result, Err(GlmApiError::Schema { .. })));
+    }
+
+    #[test]
+    fn fetch_times_out() {
+        let url = mock_response(
+            format!(
+                "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                QUOTA_BODY.len(),
+                QUOTA_BODY
+            ),
+            Duration::from_secs(2),
+        );
+        let config = api_config(&url).with_timeout(Duration::from_millis(100));
+        let error = fetch_glm_quota_limit(&config).err();
+        assert_eq!(error, Some(GlmApiError::Timeout));
+    }
+
+    fn env(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    const ZHIPU_BASE_URL: &str = "https://open.bigmodel.cn/api/anthropic";
+
+    #[test]
+    fn resolve_prefers_process_env_over_settings() {
+        let process = env(&[
+            ("ANTHROPIC_BASE_URL", "https://api.z.ai/api/anthropic"),
+            ("ANTHROPIC_AUTH_TOKEN", ""),
+        ]);
+        let settings = env(&[
+            ("ANTHROPIC_BASE_URL", ZHIPU_BASE_URL),
+            ("ANTHROPIC_AUTH_TOKEN", "settings-token"),
+        ]);
+        let config = resolve_with_env(
+            &GlmConfig::default(),
+            &|name| process.get(name).cloned(),
+            &settings,
+        )
+        .unwrap();
+
+        assert_eq!(config.base_url, "https://api.z.ai/api/anthropic");
+        // 空的进程环境变量回退到 settings.json
+        assert_eq!(config.auth_token, "settings-token");
+        assert_eq!(config.platform(), GlmPlatform::ZAI);
+        assert_eq!(
+            config.quota_limit_url(),
+            "https://api.z.ai/api/monitor/usage/quota/limit"
+        );
+    }
+
+    #[test]
+    fn resolve_accepts_other_hosts_only_when_configured() {
+        let settings = env(&[
+            ("ANTHROPIC_BASE_URL", "https://llm.example.com/anthropic"),
+            ("ANTHROPIC_AUTH_TOKEN", "token"),
+        ]);
+        assert!(resolve_with_env(&GlmConfig::default(), &|_| None, &settings).is_none());
+
+        let glm = GlmConfig {
+            platform: Some(GlmPlatform::ZAI),
+            ..GlmConfig::default()
+        };
+        let config = resolve_with_env(&glm, &|_| None, &settings).unwrap();
+        assert_eq!(config.platform(), GlmPlatform::ZAI);
+        assert_eq!(config.monitor_base(), "https://llm.example.com");
+    }
+
+    #[test]
+    fn resolve_uses_monitor_base_url_override() {
+        let glm = GlmConfig {
+            monitor_base_url: Some("https://glm-proxy.example.com/".to_string()),
+            ..GlmConfig::default()
+        };
+
+        // 没有 ANTHROPIC_BASE_URL 时以监控地址为准
+        let settings = env(&[("ANTHROPIC_AUTH_TOKEN", "token")]);
+        let config = resolve_with_env(&glm, &|_| None, &settings).unwrap();
+        assert_eq!(config.base_url, "https://glm-proxy.example.com/");
+        assert_eq!(
+            config.quota_limit_url(),
+            "https://glm-proxy.example.com/api/monitor/usage/quota/limit"
+        );
+        // 地址无法识别平台时默认 ZHIPU
+        assert_eq!(config.platform(), GlmPlatform::ZHIPU);
+
+        let settings = env(&[
+            ("ANTHROPIC_BASE_URL", ZHIPU_BASE_URL),
+            ("ANTHROPIC_AUTH_TOKEN", "token"),
+        ]);
+        let config = resolve_with_env(&glm, &|_| None, &settings).unwrap();
+        assert_eq!(config.base_url, ZHIPU_BASE_URL);
+        assert_eq!(config.monitor_base(), "https://glm-proxy.example.com");
+        assert_eq!(config.platform(), GlmPlatform::ZHIPU);
+    }
+
+    #[test]
+    fn platform_defaults_follow_the_base_url() {
+        let config = GlmApiConfig::new(ZHIPU_BASE_URL.to_string(), "token".to_string());
+        assert_eq!(config.platform(), GlmPlatform::ZHIPU);
+        assert_eq!(config.monitor_base(), "https://open.bigmodel.cn");
+
+        let config = GlmApiConfig::new("https://api.z.ai/api/anthropic".to_string(), String::new());
+        assert_eq!(config.platform(), GlmPlatform::ZAI);
+        assert_eq!(
+            config.with_platform(GlmPlatform::ZHIPU).platform(),
+            GlmPlatform::ZHIPU
+        );
+    }
+
+    #[test]
+    fn token_source_selects_where_the_token_comes_from() {
+        let process = env(&[
+            ("GLM_TOKEN", "env-token"),
+            ("ANTHROPIC_AUTH_TOKEN", "default"),
+        ]);
+        let process_env = |name: &str| process.get(name).cloned();
+        let settings = env(&[
+            ("ANTHROPIC_BASE_URL", ZHIPU_BASE_URL),
+            ("GLM_KEY", "settings-token"),
+        ]);
+        let token_file = temp_cache_dir("token").with_extension("txt");
+        fs::write(&token_file, "  file-token\n").unwrap();
+
+        let resolve = |token: Option<TokenSource>| {
+            let glm = GlmConfig {
+                token,
+                ..GlmConfig::default()
+            };
+            resolve_with_env(&glm, &process_env, &settings).map(|config| config.auth_token)
+        };
+
+        assert_eq!(resolve(None).as_deref(), Some("default"));
+        assert_eq!(
+            resolve(Some(TokenSource::Env("GLM_TOKEN".to_string()))).as_deref(),
+            Some("env-token")
+        );
+        assert_eq!(
+            resolve(Some(TokenSource::SettingsKey("GLM_KEY".to_string()))).as_deref(),
+            Some("settings-token")
+        );
+        assert_eq!(
+            resolve(Some(TokenSource::File(token_file.clone()))).as_deref(),
+            Some("file-token")
+        );
+        // 配置的来源缺失时不回退到 ANTHROPIC_AUTH_TOKEN
+        assert_eq!(
+            resolve(Some(TokenSource::Env("MISSING_TOKEN".to_string()))),
+            None
+        );
+        let _ = fs::remove_file(token_file);
+    }
+
+    #[test]
+    fn model_usage_from_item_list() {
+        let json = serde_json::json!({
+            "code": 200,
+            "data": {
+                "modelDataList": [
+                    { "modelName": "glm-4.6", "totalTokens": [100, 200], "modelCallCount": 3 },
+                    { "modelName": "glm-4.5-air", "totalTokens": 5000, "modelCallCount": "7" },
+                    { "modelName": "no-tokens", "modelCallCount": 1 }
+                ],
+                "totalUsage": { "totalTokensUsage": 5300, "totalModelCallCount": 10 }
+            }
+        });
+        let usage = extract_model_usage_data(&json).unwrap();
+
+        let models: Vec<(&str, f64, f64)> = usage
+            .models
+            .iter()
+            .map(|m| (m.model.as_str(), m.tokens, m.calls))
+            .collect();
+        assert_eq!(
+            models,
+            vec![("glm-4.5-air", 5000.0, 7.0), ("glm-4.6", 300.0, 3.0)]
+        );
+        assert_eq!(usage.total_tokens, 5300.0);
+        assert_eq!(usage.total_calls, 10.0);
+    }
+
+    #[test]
+    fn model_usage_accepts_renamed_fields_and_sums_missing_totals() {
+        let json = serde_json::json!({
+            "data": [
+                { "model": "glm-4.6", "tokens": 40, "calls": 2 },
+                { "name": "glm-4.5", "tokenUsage": "60" }
+            ]
+        });
+        let usage = extract_model_usage_data(&json).unwrap();
+
+        assert_eq!(usage.models[0].model, "glm-4.5");
+        assert_eq!(usage.models[0].calls, 0.0);
+        assert_eq!(usage.total_tokens, 100.0);
+        assert_eq!(usage.total_calls, 2.0);
+    }
+
+    #[test]
+    fn model_usage_without_items_or_totals_is_a_schema_error() {
+        let json = serde_json::json!({ "data": { "unrelated": true } });
+        assert_eq!(
+            extract_model_usage_data(&json).unwrap_err(),
+            missing_field("totalUsage")
+        );
+    }
+
+    #[test]
+    fn tool_usage_from_item_list() {
+        let json = serde_json::json!({
+            "data": {
+                "list": [
+                    { "toolName": "webRead", "count": 3 },
+                    { "tool": "vision", "callCount": [1, 2, 4] },
+                    { "name": "unnamed-count" }
+                ]
+            }
+        });
+        let usage = extract_tool_usage_data(&json).unwrap();
+
+        let tools: Vec<(&str, f64)> = usage
+            .tools
+            .iter()
+            .map(|t| (t.tool.as_str(), t.count))
+            .collect();
+        assert_eq!(tools, vec![("vision", 7.0), ("webRead", 3.0)]);
+    }
+
+    #[test]
+    fn tool_usage_from_total_counters() {
+        let json = serde_json::json!({
+            "data": {
+                "totalUsage": {
+                    "totalNetworkSearchCount": 5,
+                    "totalWebReadMcpCount": 2,
+                    "totalZreadMcpCount": "1",
+                    "totalSearchMcpTokens": 900
+                }
+            }
+        });
+        let usage = extract_tool_usage_data(&json).unwrap();
+
+        let tools: Vec<(&str, f64)> = usage
+            .tools
+            .iter()
+            .map(|t| (t.tool.as_str(), t.count))
+            .collect();
+        assert_eq!(
+            tools,
+            vec![("networkSearch", 5.0), ("webRead", 2.0), ("zread", 1.0)]
+        );
+    }
+
+    #[test]
+    fn tool_usage_without_counters_is_a_schema_error() {
+        for json in [
+            serde_json::json!({ "data": [] }),
+            serde_json::json!({ "data": { "totalUsage": { "totalTokens": 10 } } }),
+        ] {
+            assert_eq!(
+                extract_tool_usage_data(&json).unwrap_err(),
+                missing_field("totalUsage")
+            );
+        }
+    }
+
+    #[test]
+    fn plan_from_quota_uses_threshold_boundaries() {
+        let thresholds = PlanThresholds::default();
+        assert_eq!(GlmPlan::from_quota(0.0, &thresholds), GlmPlan::Unknown);
+        assert_eq!(GlmPlan::from_quota(f64::NAN, &thresholds), GlmPlan::Unknown);
+        assert_eq!(GlmPlan::from_quota(1.0, &thresholds), GlmPlan::Lite);
+        assert_eq!(
+            GlmPlan::from_quota(99_999_999.0, &thresholds),
+            GlmPlan::Lite
+        );
+        assert_eq!(GlmPlan::from_quota(1e8, &thresholds), GlmPlan::Pro);
+        assert_eq!(
+            GlmPlan::from_quota(499_999_999.0, &thresholds),
+            GlmPlan::Pro
+        );
+        assert_eq!(GlmPlan::from_quota(5e8, &thresholds), GlmPlan::Max);
+
+        let custom = PlanThresholds {
+            pro: 1000.0,
+            max: 2000.0,
+        };
+        assert_eq!(GlmPlan::from_quota(1e8, &custom), GlmPlan::Max);
+        assert_eq!(GlmPlan::from_quota(1500.0, &custom), GlmPlan::Pro);
+    }
+
+    #[test]
+    fn plan_from_name_matches_case_insensitively() {
+        assert_eq!(GlmPlan::from_name("GLM Coding Max"), Some(GlmPlan::Max));
+        assert_eq!(GlmPlan::from_name("GLM Coding Pro"), Some(GlmPlan::Pro));
+        assert_eq!(GlmPlan::from_name("lite"), Some(GlmPlan::Lite));
+        assert_eq!(GlmPlan::from_name("LITE"), Some(GlmPlan::Lite));
+        assert_eq!(GlmPlan::from_name("3"), None);
+        assert_eq!(GlmPlan::from_name(""), None);
+    }
+
+    #[test]
+    fn detect_plan_prefers_api_name_over_quota() {
+        let thresholds = PlanThresholds::default();
+        let quota = |data: serde_json::Value| extract_quota_limit_data(&data).unwrap();
+
+        let named = quota(serde_json::json!({
+            "planName": "GLM Coding Pro",
+            "limits": [{ "type": "TOKENS_LIMIT", "percentage": 1.0, "usage": 40_000_000 }]
+        }));
+        assert_eq!(
+            named.detect_plan(&thresholds),
+            (GlmPlan::Pro, PlanSource::Api)
+        );
+
+        // 无法识别的等级字段回退到配额推断
+        let by_quota = quota(serde_json::json!({
+            "level": 3,
+            "limits": [{ "type": "TOKENS_LIMIT", "percentage": 1.0, "usage": 600_000_000 }]
+        }));
+        assert_eq!(by_quota.plan_name.as_deref(), Some("3"));
+        assert_eq!(
+            by_quota.detect_plan(&thresholds),
+            (GlmPlan::Max, PlanSource::Quota)
+        );
+
+        let unknown = quota(serde_json::json!({
+            "limits": [{ "type": "TIME_LIMIT", "percentage": 1.0, "usage": 100 }]
+        }));
+        assert_eq!(
+            unknown.detect_plan(&thresholds),
+            (GlmPlan::Unknown, PlanSource::Unknown)
+        );
+    }
+}
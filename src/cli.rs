@@ -1,4 +1,4 @@
-use clap::Parser;
+use clap::{Args, Parser, Subcommand, ValueEnum};
 
 #[derive(Parser, Debug)]
 #[command(name = "glmcode")]
@@ -28,9 +28,73 @@ pub struct Cli {
     #[arg(short = 'u', long = "update")]
     pub update: bool,
 
+    /// Refresh cached network data once and exit (spawned in the background)
+    #[arg(long = "refresh")]
+    pub refresh: bool,
+
+    /// Keep refreshing cached network data in the foreground
+    #[arg(long = "daemon")]
+    pub daemon: bool,
+
+    /// Diagnose a render: reads sample input JSON from stdin and prints
+    /// per-segment timings, fallbacks, HTTP statuses and resolved files
+    #[arg(long = "doctor", visible_alias = "trace")]
+    pub doctor: bool,
+
     /// Patch Claude Code cli.js to disable context warnings
     #[arg(long = "patch")]
     pub patch: Option<String>,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Print a full GLM usage report (plan, quotas, model and tool usage)
+    Usage(UsageArgs),
+    /// Summarize the local usage history by day, project or session
+    History(HistoryArgs),
+}
+
+#[derive(Args, Debug)]
+pub struct UsageArgs {
+    /// Time window for model and tool usage, in hours
+    #[arg(long = "hours", default_value_t = 24)]
+    pub hours: i64,
+
+    /// Print the report as JSON
+    #[arg(long = "json")]
+    pub json: bool,
+}
+
+#[derive(Args, Debug)]
+pub struct HistoryArgs {
+    /// How to group the history
+    #[arg(long = "by", value_enum, default_value_t = HistoryGroup::Day)]
+    pub by: HistoryGroup,
+
+    /// Only include the last N days (0 for everything)
+    #[arg(long = "days", default_value_t = 30)]
+    pub days: u32,
+
+    /// Output format
+    #[arg(long = "format", value_enum, default_value_t = HistoryFormat::Table)]
+    pub format: HistoryFormat,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
+pub enum HistoryGroup {
+    Day,
+    Project,
+    Session,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
+pub enum HistoryFormat {
+    Table,
+    Csv,
+    Json,
 }
 
 impl Cli {
@@ -1,4 +1,4 @@
-use super::types::Config;
+use super::types::{Config, SegmentId};
 use std::fs;
 use std::path::{Path, PathBuf};
 
@@ -143,7 +143,7 @@ impl Config {
     }
 
     /// Get the default config file path (~/.claude/glmcode/config.toml)
-    fn get_config_path() -> PathBuf {
+    pub fn get_config_path() -> PathBuf {
         if let Some(home) = dirs::home_dir() {
             home.join(".claude").join("glmcode").join("config.toml")
         } else {
@@ -180,11 +180,105 @@ impl Config {
             return Err("No segments configured".into());
         }
 
-        // Validate segment IDs are unique
-        let mut seen_ids = std::collections::HashSet::new();
+        // Validate segment instances are unique; repeated segment types need a key
+        let mut seen_keys = std::collections::HashSet::new();
         for segment in &self.segments {
-            if !seen_ids.insert(segment.id) {
-                return Err(format!("Duplicate segment ID: {:?}", segment.id).into());
+            if segment.id == SegmentId::Custom
+                && !segment
+                    .options
+                    .get("command")
+                    .is_some_and(|v| v.is_string())
+            {
+                return Err("Custom segment needs a command option".into());
+            }
+            let custom_timeout = (segment.id == SegmentId::Custom)
+                .then(|| segment.options.get("timeout").and_then(|v| v.as_f64()))
+                .flatten();
+            if let Some(timeout) = custom_timeout {
+                let deadline = self.render_deadline_ms as f64 / 1000.0;
+                if !timeout.is_finite()
+                    || timeout < 0.0
+                    || (self.render_deadline_ms > 0 && timeout > deadline)
+                {
+                    return Err(format!(
+                        "Invalid timeout for segment {}: must be between 0 and the {}s render deadline",
+                        segment.instance_key(),
+                        deadline
+                    )
+                    .into());
+                }
+            }
+            let key = segment.instance_key();
+            if !seen_keys.insert(key.clone()) {
+                return Err(match segment.key {
+                    Some(_) => format!("Duplicate segment key: {}", key),
+                    None => format!(
+                        "Duplicate segment: {} (give each instance a unique key)",
+                        key
+                    ),
+                }
+                .into());
+            }
+        }
+
+        // Validate rows place configured segments at most once
+        let mut placed = std::collections::HashSet::new();
+        for key in self
+            .rows
+            .iter()
+            .flat_map(|row| row.segments.iter().chain(&row.right))
+        {
+            if !seen_keys.contains(key) {
+                return Err(
+                    format!("Row refers to segment {}, which is not configured", key).into(),
+                );
+            }
+            if !placed.insert(key) {
+                return Err(format!("Segment {} is placed in more than one row", key).into());
+            }
+        }
+
+        // Validate options against each segment type's schema
+        let problems: Vec<String> = self
+            .segments
+            .iter()
+            .flat_map(|segment| {
+                crate::core::segments::validate_options(&segment.options, segment.id)
+                    .into_iter()
+                    .map(move |problem| format!("segment {}: {}", segment.instance_key(), problem))
+            })
+            .collect();
+        if !problems.is_empty() {
+            return Err(format!("Invalid segment options: {}", problems.join("; ")).into());
+        }
+
+        // Validate color rules parse
+        for segment in &self.segments {
+            segment.color_rules().map_err(|e| {
+                format!(
+                    "Invalid color_rules for segment {}: {}",
+                    segment.instance_key(),
+                    e
+                )
+            })?;
+        }
+
+        // Validate alert rules point at configured segments
+        for rule in &self.alerts {
+            if rule.metric.is_empty() {
+                return Err(format!("Alert {} has no metric", rule.key()).into());
+            }
+            if !self.segments.iter().any(|segment| rule.targets(segment)) {
+                return Err(format!(
+                    "Alert {} refers to segment {:?}{}, which is not configured",
+                    rule.key(),
+                    rule.segment,
+                    rule.instance
+                        .as_ref()
+                        .map(|instance| format!(" [{}]", instance))
+                        .unwrap_or_default()
+                )
+                .into());
             }
         }
 
@@ -198,3 +292,107 @@ impl Config {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{RowConfig, SegmentConfig};
+    use serde_json::json;
+
+    fn segment(id: SegmentId, key: Option<&str>, options: serde_json::Value) -> SegmentConfig {
+        let mut segment = Config::default().segments[0].clone();
+        segment.id = id;
+        segment.key = key.map(str::to_string);
+        segment.options = serde_json::from_value(options).unwrap();
+        segment
+    }
+
+    fn config(segments: Vec<SegmentConfig>) -> Config {
+        Config {
+            segments,
+            rows: Vec::new(),
+            alerts: Vec::new(),
+            ..Config::default()
+        }
+    }
+
+    fn error(config: &Config) -> String {
+        config.check().unwrap_err().to_string()
+    }
+
+    #[test]
+    fn default_config_is_valid() {
+        assert!(Config::default().check().is_ok());
+    }
+
+    #[test]
+    fn rejects_duplicate_instance_keys() {
+        let repeated = config(vec![
+            segment(SegmentId::Git, None, json!({})),
+            segment(SegmentId::Git, None, json!({})),
+        ]);
+        assert_eq!(
+            error(&repeated),
+            "Duplicate segment: git (give each instance a unique key)"
+        );
+
+        let same_key = config(vec![
+            segment(SegmentId::Git, Some("repo"), json!({})),
+            segment(SegmentId::Directory, Some("repo"), json!({})),
+        ]);
+        assert_eq!(error(&same_key), "Duplicate segment key: repo");
+
+        let keyed = config(vec![
+            segment(SegmentId::Git, Some("repo"), json!({})),
+            segment(SegmentId::Git, None, json!({})),
+        ]);
+        assert!(keyed.check().is_ok());
+    }
+
+    #[test]
+    fn rejects_rows_with_missing_or_repeated_keys() {
+        let mut missing = config(vec![segment(SegmentId::Git, None, json!({}))]);
+        missing.rows = vec![RowConfig {
+            segments: vec!["git".to_string(), "model".to_string()],
+            right: Vec::new(),
+            separator: None,
+        }];
+        assert_eq!(
+            error(&missing),
+            "Row refers to segment model, which is not configured"
+        );
+
+        let mut repeated = config(vec![segment(SegmentId::Git, None, json!({}))]);
+        repeated.rows = vec![RowConfig {
+            segments: vec!["git".to_string()],
+            right: vec!["git".to_string()],
+            separator: None,
+        }];
+        assert_eq!(
+            error(&repeated),
+            "Segment git is placed in more than one row"
+        );
+    }
+
+    #[test]
+    fn custom_segments_need_a_command_and_a_timeout_within_the_deadline() {
+        let no_command = config(vec![segment(
+            SegmentId::Custom,
+            None,
+            json!({ "name": "k8s" }),
+        )]);
+        assert_eq!(error(&no_command), "Custom segment needs a command option");
+
+        let mut slow = config(vec![segment(
+            SegmentId::Custom,
+            None,
+            json!({ "name": "k8s", "command": "true", "timeout": 2 }),
+        )]);
+        slow.render_deadline_ms = 1000;
+        assert!(error(&slow).starts_with("Invalid timeout for segment custom:k8s"));
+
+        // Without a deadline any finite timeout is fine
+        slow.render_deadline_ms = 0;
+        assert!(slow.check().is_ok());
+    }
+}
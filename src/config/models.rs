@@ -1,6 +1,6 @@
 use serde::{Deserialize, Serialize};
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ModelConfig {
@@ -13,6 +13,47 @@ pub struct ModelEntry {
     pub pattern: String,
     pub display_name: String,
     pub context_limit: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pricing: Option<ModelPricing>,
+}
+
+/// Prices per million tokens, used to estimate spend from transcript usage
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ModelPricing {
+    #[serde(default)]
+    pub input: f64,
+    #[serde(default)]
+    pub output: f64,
+    #[serde(default)]
+    pub cache_read: f64,
+    #[serde(default)]
+    pub cache_write: f64,
+    #[serde(default)]
+    pub currency: Currency,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum Currency {
+    #[default]
+    Usd,
+    Cny,
+}
+
+impl Currency {
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            Self::Usd => "$",
+            Self::Cny => "¥",
+        }
+    }
+
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::Usd => "USD",
+            Self::Cny => "CNY",
+        }
+    }
 }
 
 impl ModelConfig {
@@ -36,19 +77,19 @@ impl ModelConfig {
         }
 
         // Try loading from user config directory first, then local
-        let config_paths = [
-            dirs::home_dir().map(|d| d.join(".claude").join("glmcode").join("models.toml")),
-            Some(Path::new("models.toml").to_path_buf()),
-        ];
-
-        for path in config_paths.iter().flatten() {
+        for path in Self::config_paths() {
             if path.exists() {
-                if let Ok(config) = Self::load_from_file(path) {
-                    // Prepend external models to built-in ones for priority
-                    let mut merged_entries = config.model_entries;
-                    merged_entries.extend(model_config.model_entries);
-                    model_config.model_entries = merged_entries;
-                    return model_config;
+                match Self::load_from_file(&path) {
+                    Ok(config) => {
+                        // Prepend external models to built-in ones for priority
+                        let mut merged_entries = config.model_entries;
+                        merged_entries.extend(model_config.model_entries);
+                        model_config.model_entries = merged_entries;
+                        return model_config;
+                    }
+                    Err(e) => {
+                        crate::trace::record(format!("failed to parse {}: {}", path.display(), e))
+                    }
                 }
             }
         }
@@ -57,6 +98,17 @@ impl ModelConfig {
         model_config
     }
 
+    /// Candidate models.toml locations, in priority order
+    pub fn config_paths() -> Vec<PathBuf> {
+        [
+            dirs::home_dir().map(|d| d.join(".claude").join("glmcode").join("models.toml")),
+            Some(Path::new("models.toml").to_path_buf()),
+        ]
+        .into_iter()
+        .flatten()
+        .collect()
+    }
+
     /// Get context limit for a model based on ID pattern matching
     /// Checks external config first, then falls back to built-in config
     pub fn get_context_limit(&self, model_id: &str) -> u32 {
@@ -88,6 +140,23 @@ impl ModelConfig {
         None
     }
 
+    /// Get pricing for a model: the first matching entry that has prices
+    pub fn get_pricing(&self, model_id: &str) -> Option<&ModelPricing> {
+        let model_lower = model_id.to_lowercase();
+
+        self.model_entries
+            .iter()
+            .filter(|entry| model_lower.contains(&entry.pattern.to_lowercase()))
+            .find_map(|entry| entry.pricing.as_ref())
+    }
+
+    /// Whether any entry has prices, i.e. whether spend can be estimated at all
+    pub fn has_pricing(&self) -> bool {
+        self.model_entries
+            .iter()
+            .any(|entry| entry.pricing.is_some())
+    }
+
     /// Create default model configuration file with minimal template
     pub fn create_default_file<P: AsRef<Path>>(path: P) -> Result<(), Box<dyn std::error::Error>> {
         // Create a minimal template config (not the full fallback config)
@@ -113,7 +182,10 @@ impl ModelConfig {
              # [[models]]\n\
              # pattern = \"glm-4.5\"\n\
              # display_name = \"GLM-4.5\"\n\
-             # context_limit = 128000\n",
+             # context_limit = 128000\n\
+             #\n\
+             # Optional prices per million tokens, for the estimated_cost segment:\n\
+             # pricing = {{ input = 2.0, output = 8.0, cache_read = 0.4, cache_write = 0.0, currency = \"CNY\" }}\n",
             toml_content.trim()
         );
 
@@ -136,6 +208,7 @@ impl Default for ModelConfig {
                     pattern: "[1m]".to_string(),
                     display_name: "Sonnet 4.5 1M".to_string(),
                     context_limit: 1_000_000,
+                    pricing: None,
                 },
                 // ModelEntry {
                 //     pattern: "claude-sonnet-4-5".to_string(),
@@ -166,27 +239,32 @@ impl Default for ModelConfig {
                     pattern: "claude-3-7-sonnet".to_string(),
                     display_name: "Sonnet 3.7".to_string(),
                     context_limit: 200_000,
+                    pricing: None,
                 },
                 // Third-party models
                 ModelEntry {
                     pattern: "glm-4.5".to_string(),
                     display_name: "GLM-4.5".to_string(),
                     context_limit: 128_000,
+                    pricing: None,
                 },
                 ModelEntry {
                     pattern: "kimi-k2-turbo".to_string(),
                     display_name: "Kimi K2 Turbo".to_string(),
                     context_limit: 128_000,
+                    pricing: None,
                 },
                 ModelEntry {
                     pattern: "kimi-k2".to_string(),
                     display_name: "Kimi K2".to_string(),
                     context_limit: 128_000,
+                    pricing: None,
                 },
                 ModelEntry {
                     pattern: "qwen3-coder".to_string(),
                     display_name: "Qwen Coder".to_string(),
                     context_limit: 256_000,
+                    pricing: None,
                 },
             ],
         }
@@ -1,5 +1,7 @@
+use crate::api::{GlmPlatform, PlanThresholds};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::path::PathBuf;
 
 // Main config structure
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -7,10 +9,163 @@ pub struct Config {
     pub style: StyleConfig,
     pub segments: Vec<SegmentConfig>,
     pub theme: String,
+    /// Global render deadline in milliseconds (0 waits for every segment)
+    #[serde(default = "default_render_deadline_ms")]
+    pub render_deadline_ms: u64,
+    /// Widest a statusline row may be; `COLUMNS` is used when unset, but
+    /// Claude Code does not export it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_width: Option<usize>,
+    #[serde(default, skip_serializing_if = "GlmConfig::is_empty")]
+    pub glm: GlmConfig,
+    #[serde(default, skip_serializing_if = "HistoryConfig::is_default")]
+    pub history: HistoryConfig,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub alerts: Vec<AlertRule>,
+    /// Multi-line layout; when empty every segment goes on one line
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub rows: Vec<RowConfig>,
+}
+
+pub const DEFAULT_RENDER_DEADLINE_MS: u64 = 1000;
+
+fn default_render_deadline_ms() -> u64 {
+    DEFAULT_RENDER_DEADLINE_MS
 }
 
 // Default implementation moved to ui/themes/presets.rs
 
+/// GLM endpoint resolution (`[glm]` section)
+///
+/// Every field is optional; unset fields fall back to `ANTHROPIC_BASE_URL` /
+/// `ANTHROPIC_AUTH_TOKEN` from the process environment, then settings.json.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GlmConfig {
+    /// Root of the monitor API, e.g. `https://glm-proxy.example.com`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub monitor_base_url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub platform: Option<GlmPlatform>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub token: Option<TokenSource>,
+    /// Minimum 5-hour token quota for each plan, used when the API reports no plan
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub plan_thresholds: Option<PlanThresholds>,
+}
+
+impl GlmConfig {
+    pub fn is_empty(&self) -> bool {
+        self.monitor_base_url.is_none()
+            && self.platform.is_none()
+            && self.token.is_none()
+            && self.plan_thresholds.is_none()
+    }
+}
+
+/// Local usage history (`[history]` section)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HistoryConfig {
+    /// Append a snapshot to the history file while rendering (opt-in)
+    pub enabled: bool,
+    /// Minimum seconds between two snapshots of a session whose cost, API
+    /// time and line counts did not change
+    pub interval: u64,
+    /// History file (defaults to ~/.claude/glmcode/history.jsonl)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path: Option<PathBuf>,
+}
+
+pub const DEFAULT_HISTORY_INTERVAL: u64 = 300;
+
+impl Default for HistoryConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            interval: DEFAULT_HISTORY_INTERVAL,
+            path: None,
+        }
+    }
+}
+
+impl HistoryConfig {
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// One line of a multi-line statusline (`[[rows]]`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RowConfig {
+    /// Segment keys in display order (the `key`, or the segment id when unset)
+    #[serde(default)]
+    pub segments: Vec<String>,
+    /// Segment keys aligned to the right edge of the row
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub right: Vec<String>,
+    /// Overrides `style.separator` for this row
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub separator: Option<String>,
+}
+
+/// Threshold alert on a segment metadata value (`[[alerts]]`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AlertRule {
+    /// Identifies the rule in the debounce state (defaults to `segment.metric`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    pub segment: SegmentId,
+    /// Key of the segment instance to watch when the type is configured more than once
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub instance: Option<String>,
+    /// Metadata key, e.g. `tokens_percentage` or `percentage`
+    pub metric: String,
+    /// Trips when the value reaches this threshold
+    pub above: f64,
+    /// Shell command run once each time the rule trips
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub command: Option<String>,
+    /// Colors applied to the segment while the rule is tripped
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub colors: Option<ColorConfig>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub text_bold: Option<bool>,
+}
+
+impl AlertRule {
+    pub fn key(&self) -> String {
+        match &self.name {
+            Some(name) => name.clone(),
+            None => match &self.instance {
+                Some(instance) => format!("{}.{}", instance, self.metric),
+                None => format!("{:?}.{}", self.segment, self.metric),
+            },
+        }
+    }
+
+    /// Whether the rule watches this segment
+    pub fn targets(&self, segment: &SegmentConfig) -> bool {
+        segment.id == self.segment
+            && self
+                .instance
+                .as_ref()
+                .is_none_or(|instance| segment.key.as_ref() == Some(instance))
+    }
+}
+
+/// Where to read the GLM auth token from
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TokenSource {
+    /// Process environment variable
+    Env(String),
+    /// Key in the `env` section of ~/.claude/settings.json
+    SettingsKey(String),
+    /// File whose trimmed contents are the token (`~/` is expanded)
+    File(PathBuf),
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct StyleConfig {
     pub mode: StyleMode,
@@ -28,6 +183,9 @@ pub enum StyleMode {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SegmentConfig {
     pub id: SegmentId,
+    /// Unique name for this instance, needed when a segment type is used more than once
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key: Option<String>,
     pub enabled: bool,
     pub icon: IconConfig,
     pub colors: ColorConfig,
@@ -35,6 +193,104 @@ pub struct SegmentConfig {
     pub options: HashMap<String, serde_json::Value>,
 }
 
+impl SegmentConfig {
+    /// Identifies this segment among its siblings: `key` when set, otherwise
+    /// the segment id (custom segments fall back to their `name` option)
+    pub fn instance_key(&self) -> String {
+        if let Some(key) = &self.key {
+            return key.clone();
+        }
+        let id = serde_json::to_value(self.id)
+            .ok()
+            .and_then(|v| v.as_str().map(str::to_string))
+            .unwrap_or_default();
+        if self.id != SegmentId::Custom {
+            return id;
+        }
+        let name = self.options.get("name").and_then(|v| v.as_str());
+        format!("{}:{}", id, name.unwrap_or("custom"))
+    }
+
+    /// `options.priority`: when a row is too wide, lower priorities are dropped first
+    pub fn priority(&self) -> u64 {
+        self.options
+            .get("priority")
+            .and_then(|v| v.as_u64())
+            .unwrap_or(0)
+    }
+
+    /// `options.min_width`: columns the text may be shortened to when a row is
+    /// too wide. Branch and directory names default to 12; other segments keep
+    /// their text unless it is set.
+    pub fn min_width(&self) -> Option<usize> {
+        match self.options.get("min_width").and_then(|v| v.as_u64()) {
+            Some(min_width) => Some((min_width as usize).max(1)),
+            None if matches!(self.id, SegmentId::Git | SegmentId::Directory) => Some(12),
+            None => None,
+        }
+    }
+
+    /// Parse `options.color_rules`
+    pub fn color_rules(&self) -> Result<Vec<ColorRule>, serde_json::Error> {
+        match self.options.get("color_rules") {
+            Some(rules) => serde_json::from_value(rules.clone()),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    /// Colors after applying the first color rule matching `metadata`
+    pub fn resolved_colors(&self, metadata: &HashMap<String, String>) -> ColorConfig {
+        let mut colors = self.colors.clone();
+        let rule = self
+            .color_rules()
+            .unwrap_or_default()
+            .into_iter()
+            .find(|rule| {
+                metadata
+                    .get(&rule.metric)
+                    .and_then(|v| v.parse::<f64>().ok())
+                    .is_some_and(|value| rule.matches(value))
+            });
+        if let Some(rule) = rule {
+            if rule.icon.is_some() {
+                colors.icon = rule.icon;
+            }
+            if rule.text.is_some() {
+                colors.text = rule.text;
+            }
+            if rule.background.is_some() {
+                colors.background = rule.background;
+            }
+        }
+        colors
+    }
+}
+
+/// Colors for a range of a segment metadata value (`options.color_rules`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ColorRule {
+    /// Metadata key, e.g. `percentage` or `tokens_percentage`
+    pub metric: String,
+    /// Inclusive lower bound
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min: Option<f64>,
+    /// Exclusive upper bound
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub icon: Option<AnsiColor>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub text: Option<AnsiColor>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub background: Option<AnsiColor>,
+}
+
+impl ColorRule {
+    pub fn matches(&self, value: f64) -> bool {
+        self.min.is_none_or(|min| value >= min) && self.max.is_none_or(|max| value < max)
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct IconConfig {
     pub plain: String,
@@ -74,6 +330,12 @@ pub enum SegmentId {
     Update,
     GlmUsage,
     DecodeSpeed,
+    GlmModelUsage,
+    GlmToolUsage,
+    SessionTokens,
+    EstimatedCost,
+    Cost,
+    Custom,
 }
 
 // Legacy compatibility structure
@@ -86,18 +348,18 @@ pub struct SegmentsConfig {
 }
 
 // Data structures compatible with existing main.rs
-#[derive(Deserialize)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Model {
     pub id: String,
     pub display_name: String,
 }
 
-#[derive(Deserialize)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Workspace {
     pub current_dir: String,
 }
 
-#[derive(Deserialize)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Cost {
     pub total_cost_usd: Option<f64>,
     pub total_duration_ms: Option<u64>,
@@ -106,12 +368,12 @@ pub struct Cost {
     pub total_lines_removed: Option<u32>,
 }
 
-#[derive(Deserialize)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct OutputStyle {
     pub name: String,
 }
 
-#[derive(Deserialize)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct InputData {
     pub model: Model,
     pub workspace: Workspace,
@@ -401,6 +663,8 @@ pub type Usage = RawUsage;
 
 #[derive(Deserialize)]
 pub struct Message {
+    pub id: Option<String>,
+    pub model: Option<String>,
     pub usage: Option<Usage>,
 }
 
@@ -414,4 +678,84 @@ pub struct TranscriptEntry {
     #[serde(rename = "parentUuid")]
     pub parent_uuid: Option<String>,
     pub summary: Option<String>,
+    pub timestamp: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn segment(color_rules: serde_json::Value) -> SegmentConfig {
+        let mut segment = Config::default().segments[0].clone();
+        segment.colors.text = Some(AnsiColor::Color256 { c256: 1 });
+        segment.options = HashMap::from([("color_rules".to_string(), color_rules)]);
+        segment
+    }
+
+    fn metadata(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    fn text_color(colors: &ColorConfig) -> serde_json::Value {
+        serde_json::to_value(&colors.text).unwrap()
+    }
+
+    #[test]
+    fn color_rule_range_is_min_inclusive_max_exclusive() {
+        let rule: ColorRule =
+            serde_json::from_value(json!({ "metric": "percentage", "min": 50, "max": 80 }))
+                .unwrap();
+        assert!(!rule.matches(49.9));
+        assert!(rule.matches(50.0));
+        assert!(rule.matches(79.9));
+        assert!(!rule.matches(80.0));
+
+        let open: ColorRule = serde_json::from_value(json!({ "metric": "percentage" })).unwrap();
+        assert!(open.matches(-1.0));
+        assert!(open.matches(1e9));
+    }
+
+    #[test]
+    fn first_matching_color_rule_wins() {
+        let segment = segment(json!([
+            { "metric": "percentage", "min": 80, "text": { "c256": 167 } },
+            { "metric": "percentage", "min": 50, "text": { "c256": 179 } },
+            { "metric": "percentage", "text": { "c256": 114 } },
+        ]));
+
+        let colors = segment.resolved_colors(&metadata(&[("percentage", "90")]));
+        assert_eq!(text_color(&colors), json!({ "c256": 167 }));
+        let colors = segment.resolved_colors(&metadata(&[("percentage", "60")]));
+        assert_eq!(text_color(&colors), json!({ "c256": 179 }));
+        let colors = segment.resolved_colors(&metadata(&[("percentage", "10")]));
+        assert_eq!(text_color(&colors), json!({ "c256": 114 }));
+    }
+
+    #[test]
+    fn missing_or_unparsable_metrics_keep_theme_colors() {
+        let segment = segment(json!([
+            { "metric": "exhaust_in", "max": 3600, "text": { "c256": 167 } },
+            { "metric": "percentage", "min": 50, "background": { "c256": 179 } },
+        ]));
+
+        // No metric at all: the theme colors are kept
+        let colors = segment.resolved_colors(&HashMap::new());
+        assert_eq!(text_color(&colors), json!({ "c256": 1 }));
+
+        // A rule whose metric is missing is skipped, a later one still applies
+        // and only overrides the colors it sets
+        let colors = segment.resolved_colors(&metadata(&[("percentage", "60")]));
+        assert_eq!(text_color(&colors), json!({ "c256": 1 }));
+        assert_eq!(
+            serde_json::to_value(&colors.background).unwrap(),
+            json!({ "c256": 179 })
+        );
+
+        let colors = segment.resolved_colors(&metadata(&[("exhaust_in", "n/a")]));
+        assert_eq!(text_color(&colors), json!({ "c256": 1 }));
+    }
 }
@@ -1,4 +1,6 @@
 pub mod segments;
 pub mod statusline;
+/// Segment `format` templates
+pub mod template;
 
 pub use statusline::{collect_all_segments, StatusLineGenerator};
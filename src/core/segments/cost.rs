@@ -0,0 +1,327 @@
+use super::{OptionKind, Segment, SegmentData, SessionSegment};
+use crate::config::{InputData, SegmentId};
+use crate::utils::StateFile;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+/// Options accepted in the segment's `options` table
+pub const OPTIONS: &[(&str, OptionKind)] = &[
+    ("daily_budget", OptionKind::Number),
+    ("monthly_budget", OptionKind::Number),
+];
+
+/// Spend ledger, kept in ~/.claude/glmcode
+const LEDGER_FILE: &str = "cost_ledger.json";
+
+/// Days of spend kept; sessions left without any are forgotten
+const LEDGER_RETENTION_DAYS: i64 = 62;
+
+/// Spend of one session, as reported by Claude Code
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct SessionSpend {
+    /// Last `total_cost_usd` seen
+    total_usd: f64,
+    /// Local day ("YYYY-MM-DD") -> spend added that day
+    days: BTreeMap<String, f64>,
+}
+
+/// Spend across sessions, keyed by transcript path
+///
+/// Claude Code reports a running total per session, so each render adds the
+/// increase since the last one to today's bucket. Writes happen under a lock;
+/// an increase that finds the ledger locked is picked up by the next render.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct CostLedger {
+    sessions: HashMap<String, SessionSpend>,
+}
+
+impl CostLedger {
+    /// Record a session's running total and return whether anything changed
+    fn record(&mut self, transcript_path: &str, total_usd: f64, today: &str) -> bool {
+        let session = self
+            .sessions
+            .entry(transcript_path.to_string())
+            .or_default();
+        // A lower total means the session's counter started over
+        let added = if total_usd < session.total_usd {
+            total_usd
+        } else {
+            total_usd - session.total_usd
+        };
+        session.total_usd = total_usd;
+        if added <= 0.0 {
+            return false;
+        }
+        *session.days.entry(today.to_string()).or_default() += added;
+        true
+    }
+
+    fn spend_since(&self, first_day: &str) -> f64 {
+        self.sessions
+            .values()
+            .flat_map(|session| session.days.range(first_day.to_string()..))
+            .map(|(_, spend)| spend)
+            .sum()
+    }
+
+    fn forget_before(&mut self, first_day: &str) {
+        for session in self.sessions.values_mut() {
+            session.days.retain(|day, _| day.as_str() >= first_day);
+        }
+        self.sessions.retain(|_, session| !session.days.is_empty());
+    }
+}
+
+/// Session cost reported by Claude Code, API vs wall time, and progress
+/// against daily and monthly budgets (USD)
+#[derive(Default)]
+pub struct CostSegment {
+    daily_budget: Option<f64>,
+    monthly_budget: Option<f64>,
+}
+
+impl CostSegment {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_daily_budget(mut self, daily_budget: f64) -> Self {
+        self.daily_budget = Some(daily_budget);
+        self
+    }
+
+    pub fn with_monthly_budget(mut self, monthly_budget: f64) -> Self {
+        self.monthly_budget = Some(monthly_budget);
+        self
+    }
+
+    /// Read daily and monthly budgets from segment options
+    pub fn with_options(mut self, options: &HashMap<String, serde_json::Value>) -> Self {
+        if let Some(daily_budget) = options.get("daily_budget").and_then(|v| v.as_f64()) {
+            self.daily_budget = Some(daily_budget).filter(|budget| *budget > 0.0);
+        }
+        if let Some(monthly_budget) = options.get("monthly_budget").and_then(|v| v.as_f64()) {
+            self.monthly_budget = Some(monthly_budget).filter(|budget| *budget > 0.0);
+        }
+        self
+    }
+}
+
+/// Add the session's running total to the ledger and return (today, this month)
+fn update_ledger(
+    state: &StateFile,
+    transcript_path: &str,
+    total_usd: f64,
+    now: chrono::NaiveDate,
+) -> (f64, f64) {
+    let today = now.format("%Y-%m-%d").to_string();
+    let month_start = now.format("%Y-%m-01").to_string();
+    let retention_start = (now - chrono::Duration::days(LEDGER_RETENTION_DAYS))
+        .format("%Y-%m-%d")
+        .to_string();
+
+    let mut ledger: CostLedger = state.load().unwrap_or_default();
+    if ledger.record(transcript_path, total_usd, &today) {
+        match state.lock() {
+            Some(_lock) => {
+                // Record again on the latest ledger so other sessions' spend is kept
+                ledger = state.load().unwrap_or_default();
+                ledger.record(transcript_path, total_usd, &today);
+                ledger.forget_before(&retention_start);
+                if let Err(e) = state.store(&ledger) {
+                    crate::trace::record(format!(
+                        "failed to write {}: {}",
+                        state.path().display(),
+                        e
+                    ));
+                }
+            }
+            None => crate::trace::record("cost ledger is locked, recording on a later render"),
+        }
+    }
+    (ledger.spend_since(&today), ledger.spend_since(&month_start))
+}
+
+impl Segment for CostSegment {
+    fn collect(&self, input: &InputData) -> Option<SegmentData> {
+        let Some(cost_data) = input.cost.as_ref() else {
+            crate::trace::record("input has no cost data");
+            return None;
+        };
+        let Some(total_usd) = cost_data.total_cost_usd else {
+            crate::trace::record("input has no cost.total_cost_usd");
+            return None;
+        };
+
+        let (today_usd, month_usd) = match StateFile::new(LEDGER_FILE) {
+            Some(state) => update_ledger(
+                &state,
+                &input.transcript_path,
+                total_usd,
+                chrono::Local::now().date_naive(),
+            ),
+            None => (0.0, 0.0),
+        };
+
+        let mut metadata = HashMap::new();
+        metadata.insert("cost_usd".to_string(), format!("{:.4}", total_usd));
+        metadata.insert("today_cost".to_string(), format!("{:.4}", today_usd));
+        metadata.insert("month_cost".to_string(), format!("{:.4}", month_usd));
+
+        let mut details = Vec::new();
+        if let (Some(api_ms), Some(wall_ms)) =
+            (cost_data.total_api_duration_ms, cost_data.total_duration_ms)
+        {
+            details.push(format!(
+                "API {}/{}",
+                SessionSegment::format_duration(api_ms),
+                SessionSegment::format_duration(wall_ms)
+            ));
+            metadata.insert("api_duration_ms".to_string(), api_ms.to_string());
+            metadata.insert("duration_ms".to_string(), wall_ms.to_string());
+            if wall_ms > 0 {
+                metadata.insert(
+                    "api_percentage".to_string(),
+                    (api_ms as f64 / wall_ms as f64 * 100.0).to_string(),
+                );
+            }
+        }
+        for (label, key, spent, budget) in [
+            ("日", "daily", today_usd, self.daily_budget),
+            ("月", "monthly", month_usd, self.monthly_budget),
+        ] {
+            let Some(budget) = budget else {
+                continue;
+            };
+            let percentage = spent / budget * 100.0;
+            details.push(format!("{} {:.0}%", label, percentage));
+            metadata.insert(format!("{}_budget", key), budget.to_string());
+            metadata.insert(format!("{}_percentage", key), percentage.to_string());
+        }
+
+        Some(SegmentData {
+            primary: format!("${:.2}", total_usd),
+            secondary: details.join(" · "),
+            metadata,
+        })
+    }
+
+    fn id(&self) -> SegmentId {
+        SegmentId::Cost
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+    use std::path::PathBuf;
+
+    fn state(name: &str) -> (StateFile, PathBuf) {
+        let dir =
+            std::env::temp_dir().join(format!("glmcode-cost-test-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        (StateFile::at(dir.join(LEDGER_FILE)), dir)
+    }
+
+    fn day(date: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(date, "%Y-%m-%d").unwrap()
+    }
+
+    #[test]
+    fn record_adds_the_increase_to_today() {
+        let mut ledger = CostLedger::default();
+        assert!(ledger.record("a.jsonl", 1.5, "2026-03-01"));
+        assert!(ledger.record("a.jsonl", 2.0, "2026-03-02"));
+        // An unchanged total records nothing
+        assert!(!ledger.record("a.jsonl", 2.0, "2026-03-02"));
+        // A lower total means the counter restarted: all of it is new spend
+        assert!(ledger.record("a.jsonl", 0.25, "2026-03-02"));
+
+        let days = &ledger.sessions["a.jsonl"].days;
+        assert_eq!(days["2026-03-01"], 1.5);
+        assert_eq!(days["2026-03-02"], 0.75);
+    }
+
+    #[test]
+    fn spend_since_sums_every_session_from_a_day() {
+        let mut ledger = CostLedger::default();
+        ledger.record("a.jsonl", 1.0, "2026-02-27");
+        ledger.record("a.jsonl", 3.0, "2026-03-01");
+        ledger.record("b.jsonl", 0.5, "2026-03-02");
+
+        assert_eq!(ledger.spend_since("2026-03-02"), 0.5);
+        assert_eq!(ledger.spend_since("2026-03-01"), 2.5);
+        assert_eq!(ledger.spend_since("2026-01-01"), 3.5);
+        assert_eq!(ledger.spend_since("2026-04-01"), 0.0);
+    }
+
+    #[test]
+    fn forget_before_drops_old_days_and_empty_sessions() {
+        let mut ledger = CostLedger::default();
+        ledger.record("old.jsonl", 1.0, "2026-01-01");
+        ledger.record("a.jsonl", 1.0, "2026-01-01");
+        ledger.record("a.jsonl", 2.0, "2026-03-01");
+
+        ledger.forget_before("2026-02-01");
+        assert!(!ledger.sessions.contains_key("old.jsonl"));
+        assert_eq!(ledger.sessions["a.jsonl"].days.len(), 1);
+        assert_eq!(ledger.spend_since("2026-01-01"), 1.0);
+    }
+
+    #[test]
+    fn update_ledger_keeps_other_sessions_and_month_totals() {
+        let (state, dir) = state("update");
+        assert_eq!(
+            update_ledger(&state, "a.jsonl", 1.0, day("2026-03-01")),
+            (1.0, 1.0)
+        );
+        assert_eq!(
+            update_ledger(&state, "b.jsonl", 2.0, day("2026-03-02")),
+            (2.0, 3.0)
+        );
+        assert_eq!(
+            update_ledger(&state, "a.jsonl", 1.5, day("2026-03-02")),
+            (2.5, 3.5)
+        );
+        // A new month starts from zero but the ledger still has March
+        assert_eq!(
+            update_ledger(&state, "a.jsonl", 1.5, day("2026-04-01")),
+            (0.0, 0.0)
+        );
+        let ledger: CostLedger = state.load().unwrap();
+        assert_eq!(ledger.spend_since("2026-03-01"), 3.5);
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn locked_ledger_is_not_written() {
+        let (state, dir) = state("locked");
+        update_ledger(&state, "a.jsonl", 1.0, day("2026-03-01"));
+
+        let lock = state.lock().unwrap();
+        // The increase still shows, and is recorded once the lock is gone
+        assert_eq!(
+            update_ledger(&state, "a.jsonl", 3.0, day("2026-03-01")),
+            (3.0, 3.0)
+        );
+        assert_eq!(
+            state
+                .load::<CostLedger>()
+                .unwrap()
+                .spend_since("2026-03-01"),
+            1.0
+        );
+        drop(lock);
+
+        update_ledger(&state, "a.jsonl", 3.0, day("2026-03-01"));
+        assert_eq!(
+            state
+                .load::<CostLedger>()
+                .unwrap()
+                .spend_since("2026-03-01"),
+            3.0
+        );
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
@@ -0,0 +1,315 @@
+use super::{OptionKind, Segment, SegmentData};
+use crate::api::ApiCache;
+use crate::config::{InputData, SegmentId};
+use crate::utils::shell_command;
+use std::collections::HashMap;
+use std::io::{Read, Write};
+use std::process::{Child, Stdio};
+use std::sync::mpsc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Options accepted in the segment's `options` table
+pub const OPTIONS: &[(&str, OptionKind)] = &[
+    ("name", OptionKind::String),
+    ("command", OptionKind::String),
+    ("timeout", OptionKind::Number),
+    ("cache_duration", OptionKind::Integer),
+];
+
+/// Default command timeout in seconds, well inside the default render deadline
+const DEFAULT_TIMEOUT: f64 = 0.3;
+
+/// Runs a user-configured shell command and shows its output
+///
+/// The command receives the statusline input JSON on stdin. The first line of
+/// stdout becomes the primary text and the second line the secondary text;
+/// empty output hides the segment.
+pub struct CustomSegment {
+    name: String,
+    command: Option<String>,
+    timeout: Duration,
+    /// Upper bound for `timeout`, the render deadline when there is one
+    max_timeout: Option<Duration>,
+    cache_duration: u64,
+}
+
+impl Default for CustomSegment {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CustomSegment {
+    pub fn new() -> Self {
+        Self {
+            name: "custom".to_string(),
+            command: None,
+            timeout: Duration::from_secs_f64(DEFAULT_TIMEOUT),
+            max_timeout: None,
+            cache_duration: 0,
+        }
+    }
+
+    pub fn with_name(mut self, name: impl Into<String>) -> Self {
+        self.name = name.into();
+        self
+    }
+
+    pub fn with_command(mut self, command: impl Into<String>) -> Self {
+        self.command = Some(command.into());
+        self
+    }
+
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = self.max_timeout.map_or(timeout, |max| timeout.min(max));
+        self
+    }
+
+    /// Never wait for the command past the render deadline (0 means no deadline)
+    pub fn with_render_deadline_ms(mut self, render_deadline_ms: u64) -> Self {
+        if render_deadline_ms > 0 {
+            let max = Duration::from_millis(render_deadline_ms);
+            self.max_timeout = Some(max);
+            self.timeout = self.timeout.min(max);
+        }
+        self
+    }
+
+    pub fn with_cache_duration(mut self, cache_duration: u64) -> Self {
+        self.cache_duration = cache_duration;
+        self
+    }
+
+    /// Read name, command, timeout (seconds) and cache duration from segment options
+    pub fn with_options(mut self, options: &HashMap<String, serde_json::Value>) -> Self {
+        if let Some(name) = options.get("name").and_then(|v| v.as_str()) {
+            self.name = name.to_string();
+        }
+        if let Some(command) = options.get("command").and_then(|v| v.as_str()) {
+            self.command = Some(command.to_string());
+        }
+        if let Some(timeout) = options
+            .get("timeout")
+            .and_then(|v| v.as_f64())
+            .and_then(|secs| Duration::try_from_secs_f64(secs.max(0.0)).ok())
+        {
+            self = self.with_timeout(timeout);
+        }
+        if let Some(cache_duration) = options.get("cache_duration").and_then(|v| v.as_u64()) {
+            self.cache_duration = cache_duration;
+        }
+        self
+    }
+
+    /// Output is cached per command and working directory
+    fn cache(&self, command: &str, input: &InputData) -> ApiCache {
+        use std::collections::hash_map::DefaultHasher;
+        use std::hash::{Hash, Hasher};
+
+        let mut hasher = DefaultHasher::new();
+        command.hash(&mut hasher);
+        input.workspace.current_dir.hash(&mut hasher);
+        let name: String = self
+            .name
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+            .collect();
+        ApiCache::new(
+            format!("custom_{}_{:016x}", name, hasher.finish()),
+            Duration::from_secs(self.cache_duration),
+        )
+    }
+
+    /// Run the command with the input JSON on stdin, killing it after the timeout
+    fn run(&self, command: &str, input: &InputData) -> Result<String, String> {
+        let stdin_json = serde_json::to_vec(input).map_err(|e| e.to_string())?;
+        let mut child = ChildGuard(
+            shell_command(command)
+                .stdin(Stdio::piped())
+                .stdout(Stdio::piped())
+                .stderr(Stdio::null())
+                .spawn()
+                .map_err(|e| format!("failed to start: {}", e))?,
+        );
+
+        // Feed stdin and drain stdout on their own threads so a chatty or
+        // non-reading command cannot block us past the timeout
+        if let Some(mut stdin) = child.0.stdin.take() {
+            thread::spawn(move || {
+                let _ = stdin.write_all(&stdin_json);
+            });
+        }
+        let (tx, rx) = mpsc::channel();
+        if let Some(mut stdout) = child.0.stdout.take() {
+            thread::spawn(move || {
+                let mut output = String::new();
+                let _ = stdout.read_to_string(&mut output);
+                let _ = tx.send(output);
+            });
+        }
+
+        let deadline = Instant::now() + self.timeout;
+        let status = loop {
+            match child.0.try_wait() {
+                Ok(Some(status)) => break status,
+                Ok(None) if Instant::now() < deadline => thread::sleep(Duration::from_millis(5)),
+                Ok(None) => return Err(format!("timed out after {:?}", self.timeout)),
+                Err(e) => return Err(e.to_string()),
+            }
+        };
+        if !status.success() {
+            return Err(format!("exited with {}", status));
+        }
+
+        rx.recv_timeout(deadline.saturating_duration_since(Instant::now()))
+            .map_err(|_| "output not closed before the timeout".to_string())
+    }
+
+    fn render(&self, output: &str) -> Option<SegmentData> {
+        let mut lines = output
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty());
+        let primary = lines.next()?.to_string();
+        let secondary = lines.next().unwrap_or_default().to_string();
+
+        let mut metadata = HashMap::new();
+        metadata.insert("name".to_string(), self.name.clone());
+        metadata.insert("output".to_string(), primary.clone());
+
+        Some(SegmentData {
+            primary,
+            secondary,
+            metadata,
+        })
+    }
+}
+
+/// Kills and reaps the command however `run` returns
+struct ChildGuard(Child);
+
+impl Drop for ChildGuard {
+    fn drop(&mut self) {
+        let _ = self.0.kill();
+        let _ = self.0.wait();
+    }
+}
+
+impl Segment for CustomSegment {
+    fn collect(&self, input: &InputData) -> Option<SegmentData> {
+        let Some(command) = &self.command else {
+            crate::trace::record(format!("custom segment {} has no command", self.name));
+            return None;
+        };
+
+        let cache = self.cache(command, input);
+        let cached = (self.cache_duration > 0)
+            .then(|| cache.load::<String>())
+            .flatten();
+        if let Some((output, true)) = &cached {
+            crate::trace::record(format!("custom {}: cached output", self.name));
+            return self.render(output);
+        }
+
+        match self.run(command, input) {
+            Ok(output) => {
+                if self.cache_duration > 0 {
+                    cache.store(&output);
+                }
+                self.render(&output)
+            }
+            Err(e) => {
+                crate::trace::record(format!("custom {}: command {}", self.name, e));
+                // Keep showing the last good output
+                let (output, _) = cached?;
+                let mut data = self.render(&output)?;
+                data.metadata
+                    .insert("stale".to_string(), "true".to_string());
+                Some(data)
+            }
+        }
+    }
+
+    fn id(&self) -> SegmentId {
+        SegmentId::Custom
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn input() -> InputData {
+        serde_json::from_value(json!({
+            "model": { "id": "glm-4.6", "display_name": "GLM-4.6" },
+            "workspace": { "current_dir": "/tmp/custom-project" },
+            "transcript_path": "/tmp/custom-project/transcript.jsonl"
+        }))
+        .unwrap()
+    }
+
+    fn options(value: serde_json::Value) -> HashMap<String, serde_json::Value> {
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn output_lines_become_primary_and_secondary() {
+        let data = CustomSegment::new()
+            .with_name("two-lines")
+            .with_command("printf 'first\\n\\nsecond\\nthird\\n'")
+            .collect(&input())
+            .unwrap();
+        assert_eq!(data.primary, "first");
+        assert_eq!(data.secondary, "second");
+        assert_eq!(data.metadata.get("name").unwrap(), "two-lines");
+    }
+
+    #[test]
+    fn command_reads_the_input_json_on_stdin() {
+        let output = CustomSegment::new().run("cat", &input()).unwrap();
+        let echoed: serde_json::Value = serde_json::from_str(&output).unwrap();
+        assert_eq!(echoed["workspace"]["current_dir"], "/tmp/custom-project");
+        assert_eq!(echoed["model"]["id"], "glm-4.6");
+    }
+
+    #[test]
+    fn slow_commands_are_killed_at_the_timeout() {
+        let segment = CustomSegment::new().with_timeout(Duration::from_millis(100));
+        let start = Instant::now();
+        let result = segment.run("sleep 5", &input());
+        assert!(result.unwrap_err().starts_with("timed out"));
+        assert!(start.elapsed() < Duration::from_secs(2));
+    }
+
+    #[test]
+    fn non_zero_exit_hides_the_segment() {
+        let segment = CustomSegment::new().with_command("echo partial; exit 3");
+        assert!(segment
+            .run("echo partial; exit 3", &input())
+            .unwrap_err()
+            .contains('3'));
+        assert!(segment.collect(&input()).is_none());
+    }
+
+    #[test]
+    fn timeout_is_clamped_to_the_render_deadline() {
+        let segment = CustomSegment::new()
+            .with_render_deadline_ms(100)
+            .with_options(&options(json!({ "timeout": 5 })));
+        assert_eq!(segment.timeout, Duration::from_millis(100));
+
+        // Out-of-range values keep the default instead of panicking
+        let segment = CustomSegment::new().with_options(&options(json!({ "timeout": 1e300 })));
+        assert_eq!(segment.timeout, Duration::from_secs_f64(DEFAULT_TIMEOUT));
+        let segment = CustomSegment::new().with_options(&options(json!({ "timeout": -1 })));
+        assert_eq!(segment.timeout, Duration::ZERO);
+
+        // Without a deadline the configured timeout is kept
+        let segment = CustomSegment::new()
+            .with_render_deadline_ms(0)
+            .with_options(&options(json!({ "timeout": 5 })));
+        assert_eq!(segment.timeout, Duration::from_secs(5));
+    }
+}
@@ -1,10 +1,30 @@
-use crate::api::{fetch_glm_model_performance, fetch_glm_quota_limit, get_glm_api_config, GlmPlan};
-use crate::config::InputData;
-use crate::core::segments::SegmentData;
+use crate::api::{
+    cached_glm_model_performance, cached_glm_quota_limit, refresh_glm_model_performance,
+    refresh_glm_quota_limit, resolve_glm_api_config, GlmApiConfig, GlmApiError, GlmPlan,
+};
+use crate::config::{GlmConfig, InputData};
+use crate::core::segments::glm_usage::{indicator_segment, mark_stale, parse_error_indicators};
+use crate::core::segments::glm_usage::{parse_plan_override, resolve_plan};
+use crate::core::segments::{OptionKind, SegmentData};
 use std::collections::HashMap;
+use std::time::Duration;
 
-/// ANSI 重置代码
-const RESET: &str = "\x1b[0m";
+/// 段配置可用的 options
+pub const OPTIONS: &[(&str, OptionKind)] = &[
+    ("cache_duration", OptionKind::Integer),
+    ("timeout", OptionKind::Integer),
+    ("plan", OptionKind::String),
+    ("error_indicators", OptionKind::StringTable),
+];
+
+/// 默认缓存时长（秒）
+const DEFAULT_CACHE_DURATION: u64 = 300;
+
+/// 默认请求超时（秒）
+const DEFAULT_TIMEOUT: u64 = 3;
+
+/// 模型性能查询窗口（小时）
+const PERFORMANCE_HOURS: i64 = 5;
 
 /// 生成速度曲线 sparkline
 fn generate_sparkline(speeds: &[f64], _width: usize) -> String {
@@ -33,62 +53,132 @@ fn generate_sparkline(speeds: &[f64], _width: usize) -> String {
     bars.join("")
 }
 
-pub struct DecodeSpeedSegment;
+pub struct DecodeSpeedSegment {
+    /// 按 [glm] 配置解析出的接口配置，未配置时为 None
+    api_config: Option<GlmApiConfig>,
+    cache_duration: u64,
+    timeout: u64,
+    plan: Option<GlmPlan>,
+    error_indicators: HashMap<String, String>,
+}
+
+impl Default for DecodeSpeedSegment {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
 impl DecodeSpeedSegment {
     pub fn new() -> Self {
-        Self
+        Self {
+            api_config: None,
+            cache_duration: DEFAULT_CACHE_DURATION,
+            timeout: DEFAULT_TIMEOUT,
+            plan: None,
+            error_indicators: HashMap::new(),
+        }
+    }
+
+    /// 按 [glm] 配置解析接口地址与 Token
+    pub fn with_glm_config(mut self, glm: &GlmConfig) -> Self {
+        self.api_config = resolve_glm_api_config(glm);
+        self
+    }
+
+    pub fn with_cache_duration(mut self, cache_duration: u64) -> Self {
+        self.cache_duration = cache_duration;
+        self
+    }
+
+    pub fn with_timeout(mut self, timeout: u64) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// 手动指定套餐，跳过自动识别
+    pub fn with_plan(mut self, plan: GlmPlan) -> Self {
+        self.plan = Some(plan);
+        self
+    }
+
+    pub fn with_error_indicators(mut self, error_indicators: HashMap<String, String>) -> Self {
+        self.error_indicators = error_indicators;
+        self
+    }
+
+    /// 从段配置的 options 读取缓存时长、超时与状态提示
+    pub fn with_options(mut self, options: &HashMap<String, serde_json::Value>) -> Self {
+        if let Some(cache_duration) = options.get("cache_duration").and_then(|v| v.as_u64()) {
+            self.cache_duration = cache_duration;
+        }
+        if let Some(timeout) = options.get("timeout").and_then(|v| v.as_u64()) {
+            self.timeout = timeout;
+        }
+        if let Some(plan) = parse_plan_override(options) {
+            self.plan = Some(plan);
+        }
+        if let Some(error_indicators) = parse_error_indicators(options) {
+            self.error_indicators = error_indicators;
+        }
+        self
+    }
+
+    /// 刷新 quota 与模型性能缓存（由后台刷新进程调用）
+    pub fn refresh(&self) -> Result<(), GlmApiError> {
+        let api_config = self
+            .api_config
+            .clone()
+            .ok_or(GlmApiError::NotConfigured)?
+            .with_timeout(Duration::from_secs(self.timeout));
+        let cache_ttl = Duration::from_secs(self.cache_duration);
+
+        // 套餐识别失败不影响模型性能数据的刷新
+        let quota_result = refresh_glm_quota_limit(&api_config, cache_ttl);
+        refresh_glm_model_performance(&api_config, PERFORMANCE_HOURS, cache_ttl)?;
+        quota_result
     }
 }
 
 impl super::Segment for DecodeSpeedSegment {
     fn collect(&self, _input: &InputData) -> Option<SegmentData> {
         // 尝试从 Claude settings 获取 API 配置
-        let api_config = match get_glm_api_config() {
-            Some(config) => config,
-            None => {
-                let metadata = HashMap::new();
-                return Some(SegmentData {
-                    primary: "未配置 GLM".to_string(),
-                    secondary: String::new(),
-                    metadata,
-                });
-            }
+        let Some(api_config) = self.api_config.clone() else {
+            return Some(indicator_segment(
+                &self.error_indicators,
+                Some(&GlmApiError::NotConfigured),
+            ));
         };
 
-        // 获取套餐类型
-        let plan = match fetch_glm_quota_limit(&api_config) {
-            Ok(quota_data) => {
-                // 查找 TOKENS_LIMIT 类型的配额
-                quota_data.limits.iter().find_map(|limit| {
-                    if limit.limit_type == "TOKENS_LIMIT" || limit.limit_type.contains("TOKENS") {
-                        limit.usage.map(|usage| GlmPlan::from_token_usage(usage))
-                    } else {
-                        None
-                    }
-                }).unwrap_or(GlmPlan::Unknown)
-            }
-            Err(_) => GlmPlan::Unknown,
+        let cache_ttl = Duration::from_secs(self.cache_duration);
+
+        // 获取套餐类型（与 GLM 用量段共享同一份 quota 缓存）
+        let quota_data = match self.plan {
+            Some(_) => None,
+            None => cached_glm_quota_limit(&api_config, cache_ttl).data,
         };
+        let (plan, plan_source) =
+            resolve_plan(self.plan, quota_data.as_ref(), &api_config.plan_thresholds);
 
         // 获取模型性能数据（最近5小时）
-        let performance_data = match fetch_glm_model_performance(&api_config, 5) {
-            Ok(data) => data,
-            Err(_) => {
-                let metadata = HashMap::new();
-                return Some(SegmentData {
-                    primary: "⏳ 获取中...".to_string(),
-                    secondary: String::new(),
-                    metadata,
-                });
+        let cached = cached_glm_model_performance(&api_config, PERFORMANCE_HOURS, cache_ttl);
+        let (performance_data, stale_error) = match (cached.data, cached.error) {
+            (Some(data), Some(error)) if error.is_transient() => (data, Some(error)),
+            (_, Some(error)) => {
+                return Some(indicator_segment(&self.error_indicators, Some(&error)))
             }
+            (Some(data), None) => (data, None),
+            (None, None) => return Some(indicator_segment(&self.error_indicators, None)),
         };
 
         // 根据套餐类型选择对应的解码速度
         let speeds = match plan {
             GlmPlan::Lite => &performance_data.lite_decode_speed,
             GlmPlan::Pro | GlmPlan::Max => &performance_data.pro_max_decode_speed,
-            GlmPlan::Unknown => &performance_data.pro_max_decode_speed, // 默认使用 Pro/Max
+            GlmPlan::Unknown => {
+                // 无法识别套餐时默认使用 Pro/Max 速度，可通过 plan 选项指定
+                crate::trace::record("plan unknown, showing Pro/Max decode speed");
+                &performance_data.pro_max_decode_speed
+            }
         };
 
         // 只取最近5个数据点（如果有的话）
@@ -110,11 +200,11 @@ impl super::Segment for DecodeSpeedSegment {
             let first = *recent_speeds_rev.first().unwrap();
             let last = *recent_speeds_rev.last().unwrap();
             if last > first * 1.05 {
-                "↗"  // 上升超过 5%
+                "↗" // 上升超过 5%
             } else if last < first * 0.95 {
-                "↘"  // 下降超过 5%
+                "↘" // 下降超过 5%
             } else {
-                "→"  // 平稳
+                "→" // 平稳
             }
         } else {
             "→"
@@ -122,26 +212,30 @@ impl super::Segment for DecodeSpeedSegment {
 
         let mut metadata = HashMap::new();
         metadata.insert("plan".to_string(), plan.name().to_string());
+        metadata.insert("plan_source".to_string(), plan_source.name().to_string());
         metadata.insert("current_speed".to_string(), format!("{:.1}", current_speed));
         metadata.insert("sparkline".to_string(), sparkline.clone());
 
         // 构建显示文本：套餐 趋势 速度t/s 曲线
         let primary = format!(
-            "{} {}{}t/s {}{}",
+            "{} {}{}t/s {}",
             plan.name(),
             trend,
             format_speed(current_speed),
-            "\x1b[38;5;214m",  // 橙色
             sparkline
         );
 
         let secondary = String::new();
 
-        Some(SegmentData {
-            primary: format!("{}{}", primary, RESET),
+        let mut data = SegmentData {
+            primary,
             secondary,
             metadata,
-        })
+        };
+        if let Some(error) = stale_error {
+            mark_stale(&mut data, &self.error_indicators, &error);
+        }
+        Some(data)
     }
 
     fn id(&self) -> crate::config::SegmentId {
@@ -0,0 +1,258 @@
+use super::{OptionKind, Segment, SegmentData};
+use crate::config::{Currency, InputData, ModelConfig, ModelPricing, SegmentId};
+use crate::transcript::{ModelUsage, SessionTotals};
+use std::collections::HashMap;
+
+/// Options accepted in the segment's `options` table
+pub const OPTIONS: &[(&str, OptionKind)] = &[
+    ("currency", OptionKind::String),
+    ("usd_to_cny", OptionKind::Number),
+];
+
+/// Default exchange rate, in CNY per USD
+const DEFAULT_USD_TO_CNY: f64 = 7.2;
+
+/// Spend estimated from transcript usage and the prices in models.toml
+///
+/// Claude Code only reports cost for Anthropic billing, so this prices each
+/// assistant message by its model instead. Usage of models without pricing
+/// is left out and counted in `unpriced_turns`.
+pub struct EstimatedCostSegment {
+    models: ModelConfig,
+    currency: Option<Currency>,
+    usd_to_cny: f64,
+}
+
+impl Default for EstimatedCostSegment {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl EstimatedCostSegment {
+    pub fn new() -> Self {
+        Self {
+            models: ModelConfig::default(),
+            currency: None,
+            usd_to_cny: DEFAULT_USD_TO_CNY,
+        }
+    }
+
+    /// Prices from models.toml, loaded once by the caller
+    pub fn with_model_config(mut self, models: &ModelConfig) -> Self {
+        self.models = models.clone();
+        self
+    }
+
+    pub fn with_currency(mut self, currency: Currency) -> Self {
+        self.currency = Some(currency);
+        self
+    }
+
+    pub fn with_usd_to_cny(mut self, usd_to_cny: f64) -> Self {
+        self.usd_to_cny = usd_to_cny;
+        self
+    }
+
+    /// Read the display currency and exchange rate from segment options
+    pub fn with_options(mut self, options: &HashMap<String, serde_json::Value>) -> Self {
+        if let Some(currency) = options
+            .get("currency")
+            .and_then(|v| serde_json::from_value(v.clone()).ok())
+        {
+            self.currency = Some(currency);
+        }
+        if let Some(usd_to_cny) = options.get("usd_to_cny").and_then(|v| v.as_f64()) {
+            if usd_to_cny > 0.0 {
+                self.usd_to_cny = usd_to_cny;
+            }
+        }
+        self
+    }
+
+    /// Convert an amount between currencies
+    fn convert(&self, amount: f64, from: Currency, to: Currency) -> f64 {
+        match (from, to) {
+            (Currency::Usd, Currency::Cny) => amount * self.usd_to_cny,
+            (Currency::Cny, Currency::Usd) => amount / self.usd_to_cny,
+            _ => amount,
+        }
+    }
+
+    /// Price usage by model; models without an entry in the transcript are
+    /// priced as `current_model`. Returns the spend and the unpriced turns.
+    fn price(&self, usage: &ModelUsage, current_model: &str, currency: Currency) -> (f64, u32) {
+        let mut spend = 0.0;
+        let mut unpriced_turns = 0;
+        for (model, totals) in usage {
+            let model = if model.is_empty() {
+                current_model
+            } else {
+                model
+            };
+            match self.models.get_pricing(model) {
+                Some(pricing) => {
+                    spend += self.convert(cost(pricing, totals), pricing.currency, currency)
+                }
+                None => unpriced_turns += totals.turns,
+            }
+        }
+        (spend, unpriced_turns)
+    }
+}
+
+/// Cost of some usage at the given per-million prices
+fn cost(pricing: &ModelPricing, totals: &SessionTotals) -> f64 {
+    (totals.input_tokens as f64 * pricing.input
+        + totals.output_tokens as f64 * pricing.output
+        + totals.cache_read_tokens as f64 * pricing.cache_read
+        + totals.cache_creation_tokens as f64 * pricing.cache_write)
+        / 1_000_000.0
+}
+
+fn format_amount(currency: Currency, amount: f64) -> String {
+    format!("{}{:.2}", currency.symbol(), amount)
+}
+
+impl Segment for EstimatedCostSegment {
+    fn collect(&self, input: &InputData) -> Option<SegmentData> {
+        // Without any prices there is nothing to add up, so skip the transcripts
+        if !self.models.has_pricing() {
+            crate::trace::record("no pricing configured in models.toml".to_string());
+            return None;
+        }
+        let current_pricing = self.models.get_pricing(&input.model.id);
+        let currency = self
+            .currency
+            .or(current_pricing.map(|pricing| pricing.currency))
+            .unwrap_or_default();
+
+        let breakdown = crate::transcript::usage_breakdown(&input.transcript_path)?;
+        let (session, unpriced_turns) = self.price(&breakdown.session, &input.model.id, currency);
+        let (today, _) = self.price(&breakdown.today, &input.model.id, currency);
+        let (project, _) = self.price(&breakdown.project, &input.model.id, currency);
+
+        if current_pricing.is_none() && session == 0.0 && today == 0.0 {
+            crate::trace::record(format!("no pricing configured for {}", input.model.id));
+            return None;
+        }
+
+        let mut metadata = HashMap::new();
+        metadata.insert("session_cost".to_string(), format!("{:.4}", session));
+        metadata.insert("today_cost".to_string(), format!("{:.4}", today));
+        metadata.insert("project_cost".to_string(), format!("{:.4}", project));
+        metadata.insert("currency".to_string(), currency.code().to_string());
+        metadata.insert("symbol".to_string(), currency.symbol().to_string());
+        metadata.insert("unpriced_turns".to_string(), unpriced_turns.to_string());
+
+        Some(SegmentData {
+            primary: format_amount(currency, session),
+            secondary: format!(
+                "今日 {} · 项目 {}",
+                format_amount(currency, today),
+                format_amount(currency, project)
+            ),
+            metadata,
+        })
+    }
+
+    fn id(&self) -> SegmentId {
+        SegmentId::EstimatedCost
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ModelEntry;
+    use serde_json::json;
+
+    fn entry(pattern: &str, pricing: Option<ModelPricing>) -> ModelEntry {
+        ModelEntry {
+            pattern: pattern.to_string(),
+            display_name: pattern.to_string(),
+            context_limit: 128_000,
+            pricing,
+        }
+    }
+
+    fn pricing(input: f64, output: f64, currency: Currency) -> ModelPricing {
+        ModelPricing {
+            input,
+            output,
+            cache_read: input / 10.0,
+            cache_write: 0.0,
+            currency,
+        }
+    }
+
+    fn models() -> ModelConfig {
+        ModelConfig {
+            model_entries: vec![
+                entry("glm-4.6-air", None),
+                entry("glm-4.6", Some(pricing(2.0, 8.0, Currency::Cny))),
+                entry("claude-sonnet", Some(pricing(3.0, 15.0, Currency::Usd))),
+            ],
+        }
+    }
+
+    fn totals(turns: u32, input_tokens: u64, output_tokens: u64) -> SessionTotals {
+        SessionTotals {
+            turns,
+            input_tokens,
+            output_tokens,
+            ..SessionTotals::default()
+        }
+    }
+
+    #[test]
+    fn pricing_uses_the_first_matching_entry_with_prices() {
+        let models = models();
+        assert!(models.has_pricing());
+        assert_eq!(models.get_pricing("GLM-4.6").unwrap().input, 2.0);
+        // The air entry has no prices, so the broader glm-4.6 entry applies
+        assert_eq!(models.get_pricing("glm-4.6-air").unwrap().input, 2.0);
+        assert!(models.get_pricing("kimi-k2").is_none());
+        assert!(!ModelConfig::default().has_pricing());
+    }
+
+    #[test]
+    fn price_converts_currencies_and_counts_unknown_models() {
+        let segment = EstimatedCostSegment::new()
+            .with_model_config(&models())
+            .with_options(&serde_json::from_value(json!({ "usd_to_cny": 7.0 })).unwrap());
+        let usage = ModelUsage::from([
+            ("glm-4.6".to_string(), totals(2, 1_000_000, 100_000)),
+            ("claude-sonnet-4".to_string(), totals(1, 1_000_000, 0)),
+            ("mystery-model".to_string(), totals(3, 500_000, 500_000)),
+        ]);
+
+        // ¥2 + ¥0.8 for glm-4.6, plus $3 at 7 CNY per USD
+        let (spend, unpriced_turns) = segment.price(&usage, "glm-4.6", Currency::Cny);
+        assert!((spend - 23.8).abs() < 1e-9);
+        assert_eq!(unpriced_turns, 3);
+
+        let (spend, _) = segment.price(&usage, "glm-4.6", Currency::Usd);
+        assert!((spend - (2.8 / 7.0 + 3.0)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn usage_without_a_model_is_priced_as_the_current_one() {
+        let segment = EstimatedCostSegment::new().with_model_config(&models());
+        let usage = ModelUsage::from([(String::new(), totals(1, 1_000_000, 0))]);
+
+        assert_eq!(segment.price(&usage, "glm-4.6", Currency::Cny), (2.0, 0));
+        assert_eq!(segment.price(&usage, "kimi-k2", Currency::Cny), (0.0, 1));
+    }
+
+    #[test]
+    fn nothing_is_collected_without_any_pricing() {
+        let input: InputData = serde_json::from_value(json!({
+            "model": { "id": "glm-4.6", "display_name": "GLM-4.6" },
+            "workspace": { "current_dir": "/tmp/project" },
+            "transcript_path": "/nonexistent/project/session.jsonl"
+        }))
+        .unwrap();
+        assert!(EstimatedCostSegment::new().collect(&input).is_none());
+    }
+}
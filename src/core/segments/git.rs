@@ -1,8 +1,11 @@
-use super::{Segment, SegmentData};
+use super::{OptionKind, Segment, SegmentData, SegmentOptions};
 use crate::config::{InputData, SegmentId};
 use std::collections::HashMap;
 use std::process::Command;
 
+/// Options accepted in the segment's `options` table
+pub const OPTIONS: &[(&str, OptionKind)] = &[("show_sha", OptionKind::Bool)];
+
 #[derive(Debug)]
 pub struct GitInfo {
     pub branch: String,
@@ -39,8 +42,17 @@ impl GitSegment {
         self
     }
 
+    /// Read show_sha from segment options
+    pub fn with_options(mut self, options: &SegmentOptions) -> Self {
+        if let Some(show_sha) = options.get("show_sha").and_then(|v| v.as_bool()) {
+            self.show_sha = show_sha;
+        }
+        self
+    }
+
     fn get_git_info(&self, working_dir: &str) -> Option<GitInfo> {
         if !self.is_git_repository(working_dir) {
+            crate::trace::record(format!("{} is not a git repository", working_dir));
             return None;
         }
 
@@ -0,0 +1,199 @@
+use crate::api::{
+    cached_glm_model_usage, refresh_glm_model_usage, resolve_glm_api_config, GlmApiConfig,
+    GlmApiError, GlmModelUsage,
+};
+use crate::config::{GlmConfig, InputData};
+use crate::core::segments::glm_usage::{indicator_segment, mark_stale, parse_error_indicators};
+use crate::core::segments::{OptionKind, SegmentData};
+use std::collections::HashMap;
+use std::time::Duration;
+
+/// 段配置可用的 options
+pub const OPTIONS: &[(&str, OptionKind)] = &[
+    ("cache_duration", OptionKind::Integer),
+    ("timeout", OptionKind::Integer),
+    ("hours", OptionKind::Integer),
+    ("max_models", OptionKind::Integer),
+    ("error_indicators", OptionKind::StringTable),
+];
+
+/// 默认缓存时长（秒）
+const DEFAULT_CACHE_DURATION: u64 = 300;
+
+/// 默认请求超时（秒）
+const DEFAULT_TIMEOUT: u64 = 3;
+
+/// 默认统计窗口（小时）
+const DEFAULT_HOURS: i64 = 24;
+
+/// 默认显示的模型数量
+const DEFAULT_MAX_MODELS: usize = 2;
+
+pub struct GlmModelUsageSegment {
+    /// 按 [glm] 配置解析出的接口配置，未配置时为 None
+    api_config: Option<GlmApiConfig>,
+    cache_duration: u64,
+    timeout: u64,
+    hours: i64,
+    max_models: usize,
+    error_indicators: HashMap<String, String>,
+}
+
+impl Default for GlmModelUsageSegment {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl GlmModelUsageSegment {
+    pub fn new() -> Self {
+        Self {
+            api_config: None,
+            cache_duration: DEFAULT_CACHE_DURATION,
+            timeout: DEFAULT_TIMEOUT,
+            hours: DEFAULT_HOURS,
+            max_models: DEFAULT_MAX_MODELS,
+            error_indicators: HashMap::new(),
+        }
+    }
+
+    /// 按 [glm] 配置解析接口地址与 Token
+    pub fn with_glm_config(mut self, glm: &GlmConfig) -> Self {
+        self.api_config = resolve_glm_api_config(glm);
+        self
+    }
+
+    pub fn with_cache_duration(mut self, cache_duration: u64) -> Self {
+        self.cache_duration = cache_duration;
+        self
+    }
+
+    pub fn with_timeout(mut self, timeout: u64) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    pub fn with_hours(mut self, hours: i64) -> Self {
+        self.hours = hours;
+        self
+    }
+
+    pub fn with_max_models(mut self, max_models: usize) -> Self {
+        self.max_models = max_models;
+        self
+    }
+
+    /// 从段配置的 options 读取缓存时长、超时、统计窗口与状态提示
+    pub fn with_options(mut self, options: &HashMap<String, serde_json::Value>) -> Self {
+        if let Some(cache_duration) = options.get("cache_duration").and_then(|v| v.as_u64()) {
+            self.cache_duration = cache_duration;
+        }
+        if let Some(timeout) = options.get("timeout").and_then(|v| v.as_u64()) {
+            self.timeout = timeout;
+        }
+        if let Some(hours) = options.get("hours").and_then(|v| v.as_i64()) {
+            self.hours = hours.max(1);
+        }
+        if let Some(max_models) = options.get("max_models").and_then(|v| v.as_u64()) {
+            self.max_models = max_models as usize;
+        }
+        if let Some(error_indicators) = parse_error_indicators(options) {
+            self.error_indicators = error_indicators;
+        }
+        self
+    }
+
+    /// 刷新模型用量缓存（由后台刷新进程调用）
+    pub fn refresh(&self) -> Result<(), GlmApiError> {
+        let api_config = self
+            .api_config
+            .clone()
+            .ok_or(GlmApiError::NotConfigured)?
+            .with_timeout(Duration::from_secs(self.timeout));
+        refresh_glm_model_usage(
+            &api_config,
+            self.hours,
+            Duration::from_secs(self.cache_duration),
+        )
+    }
+
+    /// 渲染各模型 Token 用量
+    fn render(&self, usage: &GlmModelUsage) -> SegmentData {
+        let mut metadata = HashMap::new();
+        metadata.insert("hours".to_string(), self.hours.to_string());
+        metadata.insert(
+            "total_tokens".to_string(),
+            format!("{:.0}", usage.total_tokens),
+        );
+        metadata.insert(
+            "total_calls".to_string(),
+            format!("{:.0}", usage.total_calls),
+        );
+        metadata.insert("model_count".to_string(), usage.models.len().to_string());
+
+        // 只有汇总数据时显示总量
+        let primary = if usage.models.is_empty() || self.max_models == 0 {
+            format!("{} tok", format_tokens(usage.total_tokens))
+        } else {
+            let mut parts: Vec<String> = usage
+                .models
+                .iter()
+                .take(self.max_models)
+                .map(|m| format!("{} {}", m.model, format_tokens(m.tokens)))
+                .collect();
+            let hidden = usage.models.len().saturating_sub(self.max_models);
+            if hidden > 0 {
+                parts.push(format!("+{}", hidden));
+            }
+            parts.join(" · ")
+        };
+
+        SegmentData {
+            primary,
+            secondary: format!("{}h", self.hours),
+            metadata,
+        }
+    }
+}
+
+impl super::Segment for GlmModelUsageSegment {
+    fn collect(&self, _input: &InputData) -> Option<SegmentData> {
+        let Some(api_config) = self.api_config.clone() else {
+            return Some(indicator_segment(
+                &self.error_indicators,
+                Some(&GlmApiError::NotConfigured),
+            ));
+        };
+
+        let cached = cached_glm_model_usage(
+            &api_config,
+            self.hours,
+            Duration::from_secs(self.cache_duration),
+        );
+        match (cached.data, cached.error) {
+            (Some(usage), Some(error)) if error.is_transient() => {
+                let mut data = self.render(&usage);
+                mark_stale(&mut data, &self.error_indicators, &error);
+                Some(data)
+            }
+            (_, Some(error)) => Some(indicator_segment(&self.error_indicators, Some(&error))),
+            (Some(usage), None) => Some(self.render(&usage)),
+            (None, None) => Some(indicator_segment(&self.error_indicators, None)),
+        }
+    }
+
+    fn id(&self) -> crate::config::SegmentId {
+        crate::config::SegmentId::GlmModelUsage
+    }
+}
+
+/// 格式化 Token 数量（如 "850", "12.3k", "4.5M"）
+pub(crate) fn format_tokens(tokens: f64) -> String {
+    if tokens >= 1_000_000.0 {
+        format!("{:.1}M", tokens / 1_000_000.0)
+    } else if tokens >= 1000.0 {
+        format!("{:.1}k", tokens / 1000.0)
+    } else {
+        format!("{:.0}", tokens)
+    }
+}
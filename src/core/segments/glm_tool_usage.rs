@@ -0,0 +1,173 @@
+use crate::api::{
+    cached_glm_tool_usage, refresh_glm_tool_usage, resolve_glm_api_config, GlmApiConfig,
+    GlmApiError, GlmToolUsage,
+};
+use crate::config::{GlmConfig, InputData};
+use crate::core::segments::glm_usage::{indicator_segment, mark_stale, parse_error_indicators};
+use crate::core::segments::{OptionKind, SegmentData};
+use std::collections::HashMap;
+use std::time::Duration;
+
+/// 段配置可用的 options
+pub const OPTIONS: &[(&str, OptionKind)] = &[
+    ("cache_duration", OptionKind::Integer),
+    ("timeout", OptionKind::Integer),
+    ("hours", OptionKind::Integer),
+    ("error_indicators", OptionKind::StringTable),
+];
+
+/// 默认缓存时长（秒）
+const DEFAULT_CACHE_DURATION: u64 = 300;
+
+/// 默认请求超时（秒）
+const DEFAULT_TIMEOUT: u64 = 3;
+
+/// 默认统计窗口（小时）
+const DEFAULT_HOURS: i64 = 24;
+
+/// 常见 MCP 工具的简短名称
+fn tool_label(tool: &str) -> String {
+    match tool.to_ascii_lowercase().as_str() {
+        "networksearch" | "websearch" | "search" => "搜索".to_string(),
+        "webread" | "reader" | "read" => "读取".to_string(),
+        "vision" | "image" | "imageunderstanding" => "视觉".to_string(),
+        "zread" => "zread".to_string(),
+        _ => tool.to_string(),
+    }
+}
+
+pub struct GlmToolUsageSegment {
+    /// 按 [glm] 配置解析出的接口配置，未配置时为 None
+    api_config: Option<GlmApiConfig>,
+    cache_duration: u64,
+    timeout: u64,
+    hours: i64,
+    error_indicators: HashMap<String, String>,
+}
+
+impl Default for GlmToolUsageSegment {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl GlmToolUsageSegment {
+    pub fn new() -> Self {
+        Self {
+            api_config: None,
+            cache_duration: DEFAULT_CACHE_DURATION,
+            timeout: DEFAULT_TIMEOUT,
+            hours: DEFAULT_HOURS,
+            error_indicators: HashMap::new(),
+        }
+    }
+
+    /// 按 [glm] 配置解析接口地址与 Token
+    pub fn with_glm_config(mut self, glm: &GlmConfig) -> Self {
+        self.api_config = resolve_glm_api_config(glm);
+        self
+    }
+
+    pub fn with_cache_duration(mut self, cache_duration: u64) -> Self {
+        self.cache_duration = cache_duration;
+        self
+    }
+
+    pub fn with_timeout(mut self, timeout: u64) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    pub fn with_hours(mut self, hours: i64) -> Self {
+        self.hours = hours;
+        self
+    }
+
+    /// 从段配置的 options 读取缓存时长、超时、统计窗口与状态提示
+    pub fn with_options(mut self, options: &HashMap<String, serde_json::Value>) -> Self {
+        if let Some(cache_duration) = options.get("cache_duration").and_then(|v| v.as_u64()) {
+            self.cache_duration = cache_duration;
+        }
+        if let Some(timeout) = options.get("timeout").and_then(|v| v.as_u64()) {
+            self.timeout = timeout;
+        }
+        if let Some(hours) = options.get("hours").and_then(|v| v.as_i64()) {
+            self.hours = hours.max(1);
+        }
+        if let Some(error_indicators) = parse_error_indicators(options) {
+            self.error_indicators = error_indicators;
+        }
+        self
+    }
+
+    /// 刷新工具用量缓存（由后台刷新进程调用）
+    pub fn refresh(&self) -> Result<(), GlmApiError> {
+        let api_config = self
+            .api_config
+            .clone()
+            .ok_or(GlmApiError::NotConfigured)?
+            .with_timeout(Duration::from_secs(self.timeout));
+        refresh_glm_tool_usage(
+            &api_config,
+            self.hours,
+            Duration::from_secs(self.cache_duration),
+        )
+    }
+
+    /// 渲染各工具调用次数
+    fn render(&self, usage: &GlmToolUsage) -> SegmentData {
+        let mut metadata = HashMap::new();
+        metadata.insert("hours".to_string(), self.hours.to_string());
+        for item in &usage.tools {
+            metadata.insert(format!("{}_count", item.tool), format!("{:.0}", item.count));
+        }
+
+        let primary = if usage.tools.is_empty() {
+            "MCP 0".to_string()
+        } else {
+            usage
+                .tools
+                .iter()
+                .map(|item| format!("{} {:.0}", tool_label(&item.tool), item.count))
+                .collect::<Vec<_>>()
+                .join(" · ")
+        };
+
+        SegmentData {
+            primary,
+            secondary: format!("{}h", self.hours),
+            metadata,
+        }
+    }
+}
+
+impl super::Segment for GlmToolUsageSegment {
+    fn collect(&self, _input: &InputData) -> Option<SegmentData> {
+        let Some(api_config) = self.api_config.clone() else {
+            return Some(indicator_segment(
+                &self.error_indicators,
+                Some(&GlmApiError::NotConfigured),
+            ));
+        };
+
+        let cached = cached_glm_tool_usage(
+            &api_config,
+            self.hours,
+            Duration::from_secs(self.cache_duration),
+        );
+        match (cached.data, cached.error) {
+            (Some(usage), Some(error)) if error.is_transient() => {
+                let mut data = self.render(&usage);
+                mark_stale(&mut data, &self.error_indicators, &error);
+                Some(data)
+            }
+            (_, Some(error)) => Some(indicator_segment(&self.error_indicators, Some(&error))),
+            (Some(usage), None) => Some(self.render(&usage)),
+            (None, None) => Some(indicator_segment(&self.error_indicators, None)),
+        }
+    }
+
+    fn id(&self) -> crate::config::SegmentId {
+        crate::config::SegmentId::GlmToolUsage
+    }
+}
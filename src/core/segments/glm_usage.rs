@@ -1,170 +1,513 @@
-use crate::api::{fetch_glm_quota_limit, format_reset_time, get_glm_api_config, GlmPlan, GlmPlatform};
-use crate::config::InputData;
-use crate::core::segments::SegmentData;
+use crate::api::{
+    cached_glm_quota_limit, format_reset_time, refresh_glm_quota_limit, resolve_glm_api_config,
+    GlmApiConfig, GlmApiError, GlmPlan, GlmPlatform, GlmQuotaLimit, PlanSource, PlanThresholds,
+    QuotaLimitItem,
+};
+use crate::config::{GlmConfig, InputData};
+use crate::core::segments::{OptionKind, SegmentData};
+use crate::forecast::{track_quota, Forecast};
 use std::collections::HashMap;
+use std::time::Duration;
 
-/// ANSI 重置代码
-const RESET: &str = "\x1b[0m";
+/// 段配置可用的 options
+pub const OPTIONS: &[(&str, OptionKind)] = &[
+    ("cache_duration", OptionKind::Integer),
+    ("timeout", OptionKind::Integer),
+    ("plan", OptionKind::String),
+    ("limits", OptionKind::StringList),
+    ("labels", OptionKind::StringTable),
+    ("bar_length", OptionKind::Integer),
+    ("show_reset", OptionKind::Bool),
+    ("show_details", OptionKind::Bool),
+    ("forecast", OptionKind::Bool),
+    ("forecast_window", OptionKind::Integer),
+    ("error_indicators", OptionKind::StringTable),
+];
 
-/// 根据百分比获取状态色（柔和色调）
-fn get_status_color(percentage: f64) -> &'static str {
-    if percentage <= 50.0 {
-        "\x1b[38;5;114m" // 柔和绿 (256色 #114)
-    } else if percentage <= 80.0 {
-        "\x1b[38;5;179m" // 柔和黄/橙 (256色 #179)
-    } else {
-        "\x1b[38;5;167m" // 柔和红 (256色 #167)
+/// 默认缓存时长（秒）
+const DEFAULT_CACHE_DURATION: u64 = 60;
+
+/// 默认请求超时（秒）
+const DEFAULT_TIMEOUT: u64 = 3;
+
+/// 默认进度条格数
+const DEFAULT_BAR_LENGTH: usize = 10;
+
+/// 默认消耗速度窗口（分钟）
+const DEFAULT_FORECAST_WINDOW: u64 = 60;
+
+/// 数据尚未缓存时的默认提示
+const LOADING_INDICATOR: &str = "⏳ 获取中...";
+
+/// 读取 options 中的 error_indicators 表（按错误类别或 loading 覆盖状态提示）
+pub(crate) fn parse_error_indicators(
+    options: &HashMap<String, serde_json::Value>,
+) -> Option<HashMap<String, String>> {
+    let table = options.get("error_indicators")?.as_object()?;
+    Some(
+        table
+            .iter()
+            .filter_map(|(kind, text)| Some((kind.clone(), text.as_str()?.to_string())))
+            .collect(),
+    )
+}
+
+/// 只显示状态提示的段数据（错误或加载中）
+pub(crate) fn indicator_segment(
+    indicators: &HashMap<String, String>,
+    error: Option<&GlmApiError>,
+) -> SegmentData {
+    let mut metadata = HashMap::new();
+    let primary = match error {
+        Some(error) => {
+            metadata.insert("error_kind".to_string(), error.kind().to_string());
+            error.indicator(indicators)
+        }
+        None => indicators
+            .get("loading")
+            .cloned()
+            .unwrap_or_else(|| LOADING_INDICATOR.to_string()),
+    };
+    SegmentData {
+        primary,
+        secondary: String::new(),
+        metadata,
     }
 }
 
-pub struct GlmUsageSegment;
+/// 读取 options 中的 plan 覆盖项（lite / pro / max）
+pub(crate) fn parse_plan_override(options: &HashMap<String, serde_json::Value>) -> Option<GlmPlan> {
+    options
+        .get("plan")
+        .and_then(|v| v.as_str())
+        .and_then(GlmPlan::from_name)
+}
+
+/// 确定套餐：段配置覆盖优先，其次接口字段与配额阈值
+pub(crate) fn resolve_plan(
+    plan_override: Option<GlmPlan>,
+    quota_data: Option<&GlmQuotaLimit>,
+    thresholds: &PlanThresholds,
+) -> (GlmPlan, PlanSource) {
+    if let Some(plan) = plan_override {
+        return (plan, PlanSource::Override);
+    }
+    let (plan, source) = quota_data
+        .map(|quota| quota.detect_plan(thresholds))
+        .unwrap_or((GlmPlan::Unknown, PlanSource::Unknown));
+    crate::trace::record(format!("plan {} (from {})", plan.name(), source.name()));
+    (plan, source)
+}
+
+/// 临时错误时继续显示旧数据，并在 secondary 附加状态提示
+pub(crate) fn mark_stale(
+    data: &mut SegmentData,
+    indicators: &HashMap<String, String>,
+    error: &GlmApiError,
+) {
+    data.metadata
+        .insert("error_kind".to_string(), error.kind().to_string());
+    let indicator = error.indicator(indicators);
+    data.secondary = if data.secondary.is_empty() {
+        indicator
+    } else {
+        format!("{} {}", data.secondary, indicator)
+    };
+}
+
+pub struct GlmUsageSegment {
+    /// 按 [glm] 配置解析出的接口配置，未配置时为 None
+    api_config: Option<GlmApiConfig>,
+    cache_duration: u64,
+    timeout: u64,
+    plan: Option<GlmPlan>,
+    /// 按类型筛选并排序要显示的配额（为空时显示全部）
+    limits: Vec<String>,
+    /// 按类型覆盖配额标签
+    labels: HashMap<String, String>,
+    bar_length: usize,
+    show_reset: bool,
+    show_details: bool,
+    /// 是否根据消耗速度预测 Token 配额耗尽时间
+    forecast: bool,
+    /// 计算消耗速度的时间窗口（分钟）
+    forecast_window: u64,
+    error_indicators: HashMap<String, String>,
+}
+
+impl Default for GlmUsageSegment {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
 impl GlmUsageSegment {
     pub fn new() -> Self {
-        Self
+        Self {
+            api_config: None,
+            cache_duration: DEFAULT_CACHE_DURATION,
+            timeout: DEFAULT_TIMEOUT,
+            plan: None,
+            limits: Vec::new(),
+            labels: HashMap::new(),
+            bar_length: DEFAULT_BAR_LENGTH,
+            show_reset: true,
+            show_details: false,
+            forecast: false,
+            forecast_window: DEFAULT_FORECAST_WINDOW,
+            error_indicators: HashMap::new(),
+        }
     }
-}
 
-impl super::Segment for GlmUsageSegment {
-    fn collect(&self, _input: &InputData) -> Option<SegmentData> {
-        // 尝试从 Claude settings 获取 API 配置
-        let api_config = match get_glm_api_config() {
-            Some(config) => config,
-            None => {
-                let metadata = HashMap::new();
-                return Some(SegmentData {
-                    primary: "未配置 GLM".to_string(),
-                    secondary: String::new(),
-                    metadata,
-                });
-            }
-        };
+    /// 按 [glm] 配置解析接口地址与 Token
+    pub fn with_glm_config(mut self, glm: &GlmConfig) -> Self {
+        self.api_config = resolve_glm_api_config(glm);
+        self
+    }
 
-        let platform = api_config.platform();
-        let platform_name = match platform {
-            GlmPlatform::ZAI => "Z.ai",
-            GlmPlatform::ZHIPU => "智谱",
+    pub fn with_cache_duration(mut self, cache_duration: u64) -> Self {
+        self.cache_duration = cache_duration;
+        self
+    }
+
+    pub fn with_timeout(mut self, timeout: u64) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// 手动指定套餐，跳过自动识别
+    pub fn with_plan(mut self, plan: GlmPlan) -> Self {
+        self.plan = Some(plan);
+        self
+    }
+
+    /// 只显示指定类型的配额，并按给定顺序排列
+    pub fn with_limits(mut self, limits: Vec<String>) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    pub fn with_labels(mut self, labels: HashMap<String, String>) -> Self {
+        self.labels = labels;
+        self
+    }
+
+    /// 进度条格数（0 表示不显示进度条）
+    pub fn with_bar_length(mut self, bar_length: usize) -> Self {
+        self.bar_length = bar_length;
+        self
+    }
+
+    pub fn with_reset(mut self, show_reset: bool) -> Self {
+        self.show_reset = show_reset;
+        self
+    }
+
+    pub fn with_details(mut self, show_details: bool) -> Self {
+        self.show_details = show_details;
+        self
+    }
+
+    pub fn with_forecast(mut self, forecast: bool) -> Self {
+        self.forecast = forecast;
+        self
+    }
+
+    pub fn with_forecast_window(mut self, minutes: u64) -> Self {
+        self.forecast_window = minutes;
+        self
+    }
+
+    pub fn with_error_indicators(mut self, error_indicators: HashMap<String, String>) -> Self {
+        self.error_indicators = error_indicators;
+        self
+    }
+
+    /// 从段配置的 options 读取缓存时长、超时、配额显示与状态提示
+    pub fn with_options(mut self, options: &HashMap<String, serde_json::Value>) -> Self {
+        if let Some(cache_duration) = options.get("cache_duration").and_then(|v| v.as_u64()) {
+            self.cache_duration = cache_duration;
+        }
+        if let Some(timeout) = options.get("timeout").and_then(|v| v.as_u64()) {
+            self.timeout = timeout;
+        }
+        if let Some(plan) = parse_plan_override(options) {
+            self.plan = Some(plan);
+        }
+        if let Some(limits) = options.get("limits").and_then(|v| v.as_array()) {
+            self.limits = limits
+                .iter()
+                .filter_map(|v| v.as_str().map(str::to_string))
+                .collect();
+        }
+        if let Some(labels) = options.get("labels").and_then(|v| v.as_object()) {
+            self.labels = labels
+                .iter()
+                .filter_map(|(limit_type, label)| {
+                    Some((limit_type.clone(), label.as_str()?.to_string()))
+                })
+                .collect();
+        }
+        if let Some(bar_length) = options.get("bar_length").and_then(|v| v.as_u64()) {
+            self.bar_length = bar_length as usize;
+        }
+        if let Some(show_reset) = options.get("show_reset").and_then(|v| v.as_bool()) {
+            self.show_reset = show_reset;
+        }
+        if let Some(show_details) = options.get("show_details").and_then(|v| v.as_bool()) {
+            self.show_details = show_details;
+        }
+        if let Some(forecast) = options.get("forecast").and_then(|v| v.as_bool()) {
+            self.forecast = forecast;
+        }
+        if let Some(minutes) = options.get("forecast_window").and_then(|v| v.as_u64()) {
+            self.forecast_window = minutes.max(1);
+        }
+        if let Some(error_indicators) = parse_error_indicators(options) {
+            self.error_indicators = error_indicators;
+        }
+        self
+    }
+
+    /// 记录 Token 配额（优先 5 小时窗口）的使用率并预测耗尽时间
+    fn track_forecast<'a>(
+        &self,
+        api_config: &GlmApiConfig,
+        quota_data: &'a GlmQuotaLimit,
+    ) -> Option<(&'a QuotaLimitItem, Forecast)> {
+        if !self.forecast {
+            return None;
+        }
+        let tokens = quota_data
+            .limits
+            .iter()
+            .filter(|limit| limit.limit_type.contains("TOKENS"));
+        let limit = tokens
+            .clone()
+            .find(|limit| limit.unit == Some(3))
+            .or_else(|| tokens.clone().next())?;
+        let window = Duration::from_secs(self.forecast_window * 60);
+        Some((limit, track_quota(api_config, limit, window)))
+    }
+
+    /// 按 limits 选项筛选并排序配额
+    fn select_limits<'a>(&self, quota_data: &'a GlmQuotaLimit) -> Vec<&'a QuotaLimitItem> {
+        if self.limits.is_empty() {
+            return quota_data.limits.iter().collect();
+        }
+        self.limits
+            .iter()
+            .flat_map(|wanted| {
+                let wanted = wanted.to_ascii_uppercase();
+                quota_data
+                    .limits
+                    .iter()
+                    .filter(move |limit| limit.limit_type.to_ascii_uppercase().contains(&wanted))
+            })
+            .fold(Vec::new(), |mut selected, limit| {
+                if !selected.iter().any(|s| std::ptr::eq(*s, limit)) {
+                    selected.push(limit);
+                }
+                selected
+            })
+    }
+
+    /// 配额标签：options.labels 优先，同类型有多个窗口时附加窗口长度
+    fn limit_label(&self, limit: &QuotaLimitItem, duplicated: bool) -> String {
+        let label = match self.labels.get(&limit.limit_type) {
+            Some(label) => label.clone(),
+            None => default_limit_label(&limit.limit_type),
         };
+        match limit.window_short().filter(|_| duplicated) {
+            Some(window) if label.is_empty() => window,
+            Some(window) => format!("{} {}", label, window),
+            None => label,
+        }
+    }
 
-        // 获取 quota limit 数据（GLM 主要关注配额百分比）
-        let quota_data = match fetch_glm_quota_limit(&api_config) {
-            Ok(data) => data,
-            Err(_) => {
-                let metadata = HashMap::new();
-                return Some(SegmentData {
-                    primary: "⏳ 获取中...".to_string(),
-                    secondary: String::new(),
-                    metadata,
-                });
+    /// 渲染单个配额：标签 进度条 百分比 重置时间 分项
+    fn render_limit(&self, limit: &QuotaLimitItem, duplicated: bool) -> String {
+        let mut parts = Vec::new();
+
+        let label = self.limit_label(limit, duplicated);
+        if !label.is_empty() {
+            parts.push(label);
+        }
+
+        if self.bar_length > 0 {
+            let filled = ((limit.percentage.clamp(0.0, 100.0) / 100.0) * self.bar_length as f64)
+                .round() as usize;
+            let empty = self.bar_length - filled;
+            parts.push(format!("{}{}", "▓".repeat(filled), "░".repeat(empty)));
+        }
+
+        parts.push(format_percentage(limit.percentage));
+
+        if self.show_reset {
+            if let Some(reset_ts) = limit.next_reset_time {
+                parts.push(format!("🔄 {}", format_reset_time(reset_ts)));
             }
-        };
+        }
 
-        // 查找 TOKENS_LIMIT 类型的配额（5小时窗口）
-        let tokens_limit = quota_data.limits.iter().find(|limit| {
-            limit.limit_type == "TOKENS_LIMIT" || limit.limit_type.contains("TOKENS")
-        });
+        if self.show_details {
+            let details = limit.details();
+            if !details.is_empty() {
+                let details: Vec<String> = details
+                    .iter()
+                    .map(|(name, value)| format!("{} {:.0}", name, value))
+                    .collect();
+                parts.push(format!("({})", details.join(", ")));
+            }
+        }
 
-        // 查找 TIME_LIMIT 类型的配额（1个月窗口，MCP usage）
-        let time_limit = quota_data.limits.iter().find(|limit| {
-            limit.limit_type == "TIME_LIMIT" || limit.limit_type.contains("TIME")
-        });
+        parts.join(" ")
+    }
 
+    /// 渲染耗尽预测，并写入消耗速度等 metadata
+    fn render_forecast(
+        &self,
+        forecast: Forecast,
+        metadata: &mut HashMap<String, String>,
+    ) -> Option<String> {
+        match forecast {
+            Forecast::Unknown => None,
+            Forecast::Safe { rate_per_hour } => {
+                metadata.insert("burn_rate".to_string(), format!("{:.1}", rate_per_hour));
+                metadata.insert("forecast".to_string(), "safe".to_string());
+                Some("✓ 重置前够用".to_string())
+            }
+            Forecast::Exhaust {
+                rate_per_hour,
+                in_ms,
+            } => {
+                metadata.insert("burn_rate".to_string(), format!("{:.1}", rate_per_hour));
+                metadata.insert("forecast".to_string(), "exhaust".to_string());
+                metadata.insert("exhaust_in".to_string(), (in_ms / 1000).to_string());
+                let now_ms = chrono::Utc::now().timestamp_millis();
+                Some(format!("⚠ ~{}后耗尽", format_reset_time(now_ms + in_ms)))
+            }
+        }
+    }
+
+    /// 渲染配额：第一项显示在 primary，其余显示在 secondary
+    fn render_quota(
+        &self,
+        quota_data: &GlmQuotaLimit,
+        platform_name: &str,
+        (plan, plan_source): (GlmPlan, PlanSource),
+        forecast: Option<(&QuotaLimitItem, Forecast)>,
+    ) -> SegmentData {
         let mut metadata = HashMap::new();
         metadata.insert("platform".to_string(), platform_name.to_string());
 
-        // 优先显示 TOKENS_LIMIT（主要使用指标）
-        if let Some(tokens) = tokens_limit {
-            let percentage = tokens.percentage;
-            metadata.insert("tokens_percentage".to_string(), format!("{:.1}", percentage));
+        // 识别套餐类型（仅记录在 metadata 中）
+        if plan != GlmPlan::Unknown {
+            metadata.insert("plan".to_string(), plan.name().to_string());
+            metadata.insert("plan_source".to_string(), plan_source.name().to_string());
+        }
 
-            // 识别套餐类型（仅记录在 metadata 中）
-            if let Some(usage) = tokens.usage {
-                let detected_plan = GlmPlan::from_token_usage(usage);
-                metadata.insert("plan".to_string(), detected_plan.name().to_string());
-            }
+        let selected = self.select_limits(quota_data);
+        metadata.insert("limit_count".to_string(), selected.len().to_string());
 
-            // 生成进度条（10格）
-            let bar_length = 10;
-            let filled = ((percentage / 100.0) * bar_length as f64).round() as usize;
-            let empty = bar_length - filled;
-
-            let status_color = get_status_color(percentage);
-            let progress_bar = format!(
-                "{}{}{}{}",
-                status_color,
-                "▓".repeat(filled),
-                "░".repeat(empty),
-                RESET
-            );
-
-            // 格式化重置时间（如果存在）
-            let reset_time_str = if let Some(reset_ts) = tokens.next_reset_time {
-                metadata.insert("next_reset_time".to_string(), reset_ts.to_string());
-                let formatted = format_reset_time(reset_ts);
-                format!(" 🔄 {}", formatted)
-            } else {
-                String::new()
-            };
+        let mut rendered = Vec::new();
+        for limit in &selected {
+            let key = limit.limit_type.to_ascii_lowercase();
+            metadata
+                .entry(format!("{}_percentage", key))
+                .or_insert_with(|| format!("{:.1}", limit.percentage));
 
-            // 构建显示文本：进度条 百分比 重置时间
-            let primary = format!(
-                "{} {}{}",
-                progress_bar,
-                format_percentage(percentage),
-                reset_time_str
-            );
-
-            // 如果有 TIME_LIMIT，显示在 secondary
-            let secondary = if let Some(time) = time_limit {
-                metadata.insert("time_percentage".to_string(), format!("{:.1}", time.percentage));
-                format!("MCP {}", format_percentage(time.percentage))
+            // 兼容旧的 metadata 键
+            let legacy = if limit.limit_type.contains("TOKENS") {
+                Some("tokens_percentage")
+            } else if limit.limit_type.contains("TIME") {
+                Some("time_percentage")
             } else {
-                String::new()
+                None
             };
+            if let Some(legacy) = legacy {
+                metadata
+                    .entry(legacy.to_string())
+                    .or_insert_with(|| format!("{:.1}", limit.percentage));
+            }
+            if let Some(reset_ts) = limit.next_reset_time {
+                metadata
+                    .entry("next_reset_time".to_string())
+                    .or_insert_with(|| reset_ts.to_string());
+            }
+
+            let duplicated = selected
+                .iter()
+                .filter(|other| other.limit_type == limit.limit_type)
+                .count()
+                > 1;
+            let mut text = self.render_limit(limit, duplicated);
+            if let Some((_, forecast)) = forecast.filter(|(item, _)| std::ptr::eq(*item, *limit)) {
+                if let Some(projection) = self.render_forecast(forecast, &mut metadata) {
+                    text = format!("{} {}", text, projection);
+                }
+            }
+            rendered.push(text);
+        }
 
-            return Some(SegmentData {
+        let mut rendered = rendered.into_iter();
+        match rendered.next() {
+            Some(primary) => SegmentData {
                 primary,
-                secondary,
+                secondary: rendered.collect::<Vec<_>>().join(" · "),
                 metadata,
-            });
-        }
-
-        // 如果没有 TOKENS_LIMIT，但有 TIME_LIMIT
-        if let Some(time) = time_limit {
-            let percentage = time.percentage;
-            metadata.insert("time_percentage".to_string(), format!("{:.1}", percentage));
-
-            let bar_length = 10;
-            let filled = ((percentage / 100.0) * bar_length as f64).round() as usize;
-            let empty = bar_length - filled;
-
-            let status_color = get_status_color(percentage);
-            let progress_bar = format!(
-                "{}{}{}{}",
-                status_color,
-                "▓".repeat(filled),
-                "░".repeat(empty),
-                RESET
-            );
-
-            return Some(SegmentData {
-                primary: format!(
-                    "{} MCP {} {}",
-                    platform_name,
-                    progress_bar,
-                    format_percentage(percentage)
-                ),
+            },
+            // 没有任何配额数据
+            None => SegmentData {
+                primary: format!("{} 暂无数据", platform_name),
                 secondary: String::new(),
                 metadata,
-            });
+            },
         }
+    }
 
-        // 没有任何配额数据
-        Some(SegmentData {
-            primary: format!("{} 暂无数据", platform_name),
-            secondary: String::new(),
-            metadata,
-        })
+    /// 刷新 quota 缓存（由后台刷新进程调用）
+    pub fn refresh(&self) -> Result<(), GlmApiError> {
+        let api_config = self
+            .api_config
+            .clone()
+            .ok_or(GlmApiError::NotConfigured)?
+            .with_timeout(Duration::from_secs(self.timeout));
+        refresh_glm_quota_limit(&api_config, Duration::from_secs(self.cache_duration))
+    }
+}
+
+impl super::Segment for GlmUsageSegment {
+    fn collect(&self, _input: &InputData) -> Option<SegmentData> {
+        // 尝试从 Claude settings 获取 API 配置
+        let Some(api_config) = self.api_config.clone() else {
+            return Some(indicator_segment(
+                &self.error_indicators,
+                Some(&GlmApiError::NotConfigured),
+            ));
+        };
+
+        let platform_name = match api_config.platform() {
+            GlmPlatform::ZAI => "Z.ai",
+            GlmPlatform::ZHIPU => "智谱",
+        };
+
+        // 获取 quota limit 数据（GLM 主要关注配额百分比）
+        let cached = cached_glm_quota_limit(&api_config, Duration::from_secs(self.cache_duration));
+        let plan = resolve_plan(self.plan, cached.data.as_ref(), &api_config.plan_thresholds);
+        match (cached.data, cached.error) {
+            (Some(quota_data), Some(error)) if error.is_transient() => {
+                let forecast = self.track_forecast(&api_config, &quota_data);
+                let mut data = self.render_quota(&quota_data, platform_name, plan, forecast);
+                mark_stale(&mut data, &self.error_indicators, &error);
+                Some(data)
+            }
+            (_, Some(error)) => Some(indicator_segment(&self.error_indicators, Some(&error))),
+            (Some(quota_data), None) => {
+                let forecast = self.track_forecast(&api_config, &quota_data);
+                Some(self.render_quota(&quota_data, platform_name, plan, forecast))
+            }
+            (None, None) => Some(indicator_segment(&self.error_indicators, None)),
+        }
     }
 
     fn id(&self) -> crate::config::SegmentId {
@@ -172,7 +515,160 @@ impl super::Segment for GlmUsageSegment {
     }
 }
 
+/// 默认配额标签：Token 配额不加标签，MCP 配额显示 "MCP"，其余取类型名
+fn default_limit_label(limit_type: &str) -> String {
+    if limit_type.contains("TOKENS") {
+        String::new()
+    } else if limit_type.contains("TIME") {
+        "MCP".to_string()
+    } else {
+        limit_type.trim_end_matches("_LIMIT").to_string()
+    }
+}
+
 /// 格式化百分比
 fn format_percentage(percentage: f64) -> String {
     format!("{:.0}%", percentage)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options(value: serde_json::Value) -> HashMap<String, serde_json::Value> {
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn error_indicators_option_overrides_per_kind() {
+        let indicators = parse_error_indicators(&options(serde_json::json!({
+            "error_indicators": { "timeout": "slow", "loading": "...", "http": 5 }
+        })))
+        .unwrap();
+
+        let timeout = indicator_segment(&indicators, Some(&GlmApiError::Timeout));
+        assert_eq!(timeout.primary, "slow");
+        assert_eq!(timeout.metadata.get("error_kind").unwrap(), "timeout");
+
+        // 非字符串项被忽略，回退到默认提示
+        let http = GlmApiError::Http { status: 500 };
+        assert_eq!(
+            indicator_segment(&indicators, Some(&http)).primary,
+            http.default_indicator()
+        );
+        assert_eq!(indicator_segment(&indicators, None).primary, "...");
+        assert!(parse_error_indicators(&HashMap::new()).is_none());
+    }
+
+    fn quota_with_usage(usage: f64) -> GlmQuotaLimit {
+        serde_json::from_value(serde_json::json!({
+            "limits": [{ "type": "TOKENS_LIMIT", "percentage": 10.0, "usage": usage }]
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn plan_override_option_accepts_plan_names() {
+        let plan = |value: serde_json::Value| parse_plan_override(&options(value));
+        assert_eq!(
+            plan(serde_json::json!({ "plan": "max" })),
+            Some(GlmPlan::Max)
+        );
+        assert_eq!(
+            plan(serde_json::json!({ "plan": "Pro" })),
+            Some(GlmPlan::Pro)
+        );
+        assert_eq!(plan(serde_json::json!({ "plan": "enterprise" })), None);
+        assert_eq!(plan(serde_json::json!({ "plan": 2 })), None);
+        assert_eq!(plan(serde_json::json!({})), None);
+    }
+
+    #[test]
+    fn resolve_plan_prefers_override_then_quota() {
+        let thresholds = PlanThresholds::default();
+        let quota = quota_with_usage(1e8);
+
+        assert_eq!(
+            resolve_plan(Some(GlmPlan::Lite), Some(&quota), &thresholds),
+            (GlmPlan::Lite, PlanSource::Override)
+        );
+        assert_eq!(
+            resolve_plan(None, Some(&quota), &thresholds),
+            (GlmPlan::Pro, PlanSource::Quota)
+        );
+        assert_eq!(
+            resolve_plan(None, Some(&quota_with_usage(5e8)), &thresholds),
+            (GlmPlan::Max, PlanSource::Quota)
+        );
+        assert_eq!(
+            resolve_plan(None, None, &thresholds),
+            (GlmPlan::Unknown, PlanSource::Unknown)
+        );
+    }
+
+    fn quota_limits() -> GlmQuotaLimit {
+        serde_json::from_value(serde_json::json!({
+            "limits": [
+                { "type": "TOKENS_LIMIT", "percentage": 10.0, "number": 5, "unit": 3 },
+                { "type": "TIME_LIMIT", "percentage": 20.0, "number": 1, "unit": 5 },
+                { "type": "TOKENS_LIMIT", "percentage": 30.0, "number": 7, "unit": 1 },
+                { "type": "SEARCH_LIMIT", "percentage": 40.0 }
+            ]
+        }))
+        .unwrap()
+    }
+
+    fn selected(segment: &GlmUsageSegment, quota: &GlmQuotaLimit) -> Vec<f64> {
+        segment
+            .select_limits(quota)
+            .iter()
+            .map(|limit| limit.percentage)
+            .collect()
+    }
+
+    #[test]
+    fn select_limits_keeps_api_order_without_option() {
+        let quota = quota_limits();
+        assert_eq!(
+            selected(&GlmUsageSegment::new(), &quota),
+            vec![10.0, 20.0, 30.0, 40.0]
+        );
+    }
+
+    #[test]
+    fn select_limits_filters_and_orders_by_option() {
+        let quota = quota_limits();
+        let segment = GlmUsageSegment::new().with_options(&options(serde_json::json!({
+            "limits": ["time", "TOKENS_LIMIT", "tokens"]
+        })));
+        // 未列出的类型被排除，重复匹配只保留一次
+        assert_eq!(selected(&segment, &quota), vec![20.0, 10.0, 30.0]);
+
+        let segment = GlmUsageSegment::new().with_options(&options(serde_json::json!({
+            "limits": ["VIDEO_LIMIT"]
+        })));
+        assert!(selected(&segment, &quota).is_empty());
+    }
+
+    #[test]
+    fn limit_label_defaults_overrides_and_windows() {
+        let quota = quota_limits();
+        let segment = GlmUsageSegment::new();
+        let [tokens, time, _, search] = &quota.limits[..] else {
+            unreachable!()
+        };
+
+        assert_eq!(segment.limit_label(tokens, false), "");
+        assert_eq!(segment.limit_label(tokens, true), "5h");
+        assert_eq!(segment.limit_label(time, false), "MCP");
+        assert_eq!(segment.limit_label(time, true), "MCP 1mo");
+        // 未知类型去掉 _LIMIT 后缀，没有窗口长度时不附加
+        assert_eq!(segment.limit_label(search, true), "SEARCH");
+
+        let segment = GlmUsageSegment::new().with_options(&options(serde_json::json!({
+            "labels": { "TOKENS_LIMIT": "Tok", "SEARCH_LIMIT": "" }
+        })));
+        assert_eq!(segment.limit_label(tokens, true), "Tok 5h");
+        assert_eq!(segment.limit_label(search, false), "");
+    }
+}
@@ -1,24 +1,218 @@
 pub mod context_window;
+pub mod cost;
+pub mod custom;
 pub mod decode_speed;
 pub mod directory;
+pub mod estimated_cost;
 pub mod git;
+pub mod glm_model_usage;
+pub mod glm_tool_usage;
 pub mod glm_usage;
 pub mod model;
 pub mod output_style;
 pub mod session;
+pub mod session_tokens;
 pub mod update;
 pub mod usage;
 
-use crate::config::{InputData, SegmentId};
+use crate::config::{GlmConfig, InputData, ModelConfig, SegmentConfig, SegmentId};
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
+/// A segment's `options` table
+pub type SegmentOptions = HashMap<String, serde_json::Value>;
+
 // New Segment trait for data collection only
 pub trait Segment {
     fn collect(&self, input: &InputData) -> Option<SegmentData>;
     fn id(&self) -> SegmentId;
 }
 
-#[derive(Debug, Clone)]
+/// Expected type of an option value
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OptionKind {
+    Bool,
+    /// Non-negative integer
+    Integer,
+    Number,
+    String,
+    StringList,
+    /// Array of tables
+    TableList,
+    /// Table of strings
+    StringTable,
+}
+
+impl OptionKind {
+    pub fn matches(&self, value: &serde_json::Value) -> bool {
+        match self {
+            Self::Bool => value.is_boolean(),
+            Self::Integer => value.is_u64(),
+            Self::Number => value.is_number(),
+            Self::String => value.is_string(),
+            Self::StringList => value
+                .as_array()
+                .is_some_and(|items| items.iter().all(|v| v.is_string())),
+            Self::TableList => value
+                .as_array()
+                .is_some_and(|items| items.iter().all(|v| v.is_object())),
+            Self::StringTable => value
+                .as_object()
+                .is_some_and(|table| table.values().all(|v| v.is_string())),
+        }
+    }
+
+    pub fn describe(&self) -> &'static str {
+        match self {
+            Self::Bool => "a boolean",
+            Self::Integer => "a non-negative integer",
+            Self::Number => "a number",
+            Self::String => "a string",
+            Self::StringList => "an array of strings",
+            Self::TableList => "an array of tables",
+            Self::StringTable => "a table of strings",
+        }
+    }
+}
+
+/// Options every segment accepts; the statusline generator applies them
+pub const COMMON_OPTIONS: &[(&str, OptionKind)] = &[
+    ("format", OptionKind::String),
+    ("color_rules", OptionKind::TableList),
+    ("priority", OptionKind::Integer),
+    ("min_width", OptionKind::Integer),
+];
+
+/// Options a segment type reads from its `options` table
+pub fn option_schema(id: SegmentId) -> &'static [(&'static str, OptionKind)] {
+    match id {
+        SegmentId::Git => git::OPTIONS,
+        SegmentId::Usage => usage::OPTIONS,
+        SegmentId::GlmUsage => glm_usage::OPTIONS,
+        SegmentId::DecodeSpeed => decode_speed::OPTIONS,
+        SegmentId::GlmModelUsage => glm_model_usage::OPTIONS,
+        SegmentId::GlmToolUsage => glm_tool_usage::OPTIONS,
+        SegmentId::EstimatedCost => estimated_cost::OPTIONS,
+        SegmentId::Cost => cost::OPTIONS,
+        SegmentId::Custom => custom::OPTIONS,
+        SegmentId::Model
+        | SegmentId::Directory
+        | SegmentId::ContextWindow
+        | SegmentId::Session
+        | SegmentId::SessionTokens
+        | SegmentId::OutputStyle
+        | SegmentId::Update => &[],
+    }
+}
+
+/// Unknown keys and mistyped values in a segment's options
+pub fn validate_options(options: &SegmentOptions, id: SegmentId) -> Vec<String> {
+    let schema = option_schema(id);
+    let mut keys: Vec<_> = options.keys().collect();
+    keys.sort();
+
+    keys.into_iter()
+        .filter_map(|key| {
+            let kind = schema
+                .iter()
+                .chain(COMMON_OPTIONS)
+                .find(|(name, _)| name == key)
+                .map(|(_, kind)| kind);
+            match kind {
+                None => Some(format!("unknown option `{}`", key)),
+                Some(kind) if !kind.matches(&options[key]) => {
+                    Some(format!("option `{}` must be {}", key, kind.describe()))
+                }
+                Some(_) => None,
+            }
+        })
+        .collect()
+}
+
+/// Build the segment for a config entry with its options, `[glm]` and the
+/// models.toml pricing applied
+pub fn build(
+    segment_config: &SegmentConfig,
+    glm: &GlmConfig,
+    models: &ModelConfig,
+    render_deadline_ms: u64,
+) -> Box<dyn Segment> {
+    let options = &segment_config.options;
+    match segment_config.id {
+        SegmentId::Model => Box::new(ModelSegment::new()),
+        SegmentId::Directory => Box::new(DirectorySegment::new()),
+        SegmentId::Git => Box::new(GitSegment::new().with_options(options)),
+        SegmentId::ContextWindow => Box::new(ContextWindowSegment::new()),
+        SegmentId::Usage => Box::new(UsageSegment::new().with_options(options)),
+        SegmentId::Session => Box::new(SessionSegment::new()),
+        SegmentId::OutputStyle => Box::new(OutputStyleSegment::new()),
+        SegmentId::Update => Box::new(UpdateSegment::new()),
+        SegmentId::GlmUsage => Box::new(
+            GlmUsageSegment::new()
+                .with_glm_config(glm)
+                .with_options(options),
+        ),
+        SegmentId::DecodeSpeed => Box::new(
+            DecodeSpeedSegment::new()
+                .with_glm_config(glm)
+                .with_options(options),
+        ),
+        SegmentId::GlmModelUsage => Box::new(
+            GlmModelUsageSegment::new()
+                .with_glm_config(glm)
+                .with_options(options),
+        ),
+        SegmentId::GlmToolUsage => Box::new(
+            GlmToolUsageSegment::new()
+                .with_glm_config(glm)
+                .with_options(options),
+        ),
+        SegmentId::SessionTokens => Box::new(SessionTokensSegment::new()),
+        SegmentId::EstimatedCost => Box::new(
+            EstimatedCostSegment::new()
+                .with_model_config(models)
+                .with_options(options),
+        ),
+        SegmentId::Cost => Box::new(CostSegment::new().with_options(options)),
+        SegmentId::Custom => Box::new(
+            CustomSegment::new()
+                .with_render_deadline_ms(render_deadline_ms)
+                .with_options(options),
+        ),
+    }
+}
+
+/// Refresh the cached network data behind a config entry, built the same way
+pub fn refresh(
+    segment_config: &SegmentConfig,
+    glm: &GlmConfig,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let options = &segment_config.options;
+    match segment_config.id {
+        SegmentId::Usage => UsageSegment::new().with_options(options).refresh(),
+        SegmentId::GlmUsage => Ok(GlmUsageSegment::new()
+            .with_glm_config(glm)
+            .with_options(options)
+            .refresh()?),
+        SegmentId::DecodeSpeed => Ok(DecodeSpeedSegment::new()
+            .with_glm_config(glm)
+            .with_options(options)
+            .refresh()?),
+        SegmentId::GlmModelUsage => Ok(GlmModelUsageSegment::new()
+            .with_glm_config(glm)
+            .with_options(options)
+            .refresh()?),
+        SegmentId::GlmToolUsage => Ok(GlmToolUsageSegment::new()
+            .with_glm_config(glm)
+            .with_options(options)
+            .refresh()?),
+        #[cfg(feature = "self-update")]
+        SegmentId::Update => crate::updater::UpdateState::refresh(),
+        _ => Ok(()),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct SegmentData {
     pub primary: String,
     pub secondary: String,
@@ -27,12 +221,87 @@ pub struct SegmentData {
 
 // Re-export all segment types
 pub use context_window::ContextWindowSegment;
+pub use cost::CostSegment;
+pub use custom::CustomSegment;
 pub use decode_speed::DecodeSpeedSegment;
 pub use directory::DirectorySegment;
+pub use estimated_cost::EstimatedCostSegment;
 pub use git::GitSegment;
+pub use glm_model_usage::GlmModelUsageSegment;
+pub use glm_tool_usage::GlmToolUsageSegment;
 pub use glm_usage::GlmUsageSegment;
 pub use model::ModelSegment;
 pub use output_style::OutputStyleSegment;
 pub use session::SessionSegment;
+pub use session_tokens::SessionTokensSegment;
 pub use update::UpdateSegment;
 pub use usage::UsageSegment;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn options(value: serde_json::Value) -> SegmentOptions {
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn option_kinds_match_their_values() {
+        assert!(OptionKind::Integer.matches(&json!(3)));
+        assert!(!OptionKind::Integer.matches(&json!(-3)));
+        assert!(!OptionKind::Integer.matches(&json!(0.5)));
+        assert!(OptionKind::Number.matches(&json!(0.5)));
+        assert!(OptionKind::StringList.matches(&json!(["a", "b"])));
+        assert!(!OptionKind::StringList.matches(&json!(["a", 1])));
+        assert!(OptionKind::TableList.matches(&json!([{ "metric": "x" }])));
+        assert!(OptionKind::StringTable.matches(&json!({ "auth": "!" })));
+        assert!(!OptionKind::StringTable.matches(&json!({ "auth": true })));
+    }
+
+    #[test]
+    fn reports_unknown_options_and_wrong_kinds_in_key_order() {
+        let problems = validate_options(
+            &options(json!({ "show_sha": "yes", "colour": 1, "branch": true })),
+            SegmentId::Git,
+        );
+        assert_eq!(
+            problems,
+            vec![
+                "unknown option `branch`",
+                "unknown option `colour`",
+                "option `show_sha` must be a boolean",
+            ]
+        );
+    }
+
+    #[test]
+    fn common_options_are_accepted_by_every_segment() {
+        let common = options(json!({
+            "format": "{primary}",
+            "color_rules": [{ "metric": "percentage", "min": 80 }],
+            "priority": 2,
+            "min_width": 8,
+        }));
+        assert!(validate_options(&common, SegmentId::Model).is_empty());
+        assert!(validate_options(&common, SegmentId::GlmUsage).is_empty());
+
+        let wrong = options(json!({ "priority": -1 }));
+        assert_eq!(
+            validate_options(&wrong, SegmentId::Model),
+            vec!["option `priority` must be a non-negative integer"]
+        );
+    }
+
+    #[test]
+    fn theme_presets_only_use_known_options() {
+        for segment in crate::config::Config::default().segments {
+            let problems = validate_options(&segment.options, segment.id);
+            assert!(problems.is_empty(), "{:?}: {:?}", segment.id, problems);
+        }
+        assert!(option_schema(SegmentId::Model).is_empty());
+        assert!(option_schema(SegmentId::Custom)
+            .iter()
+            .any(|(name, kind)| *name == "command" && *kind == OptionKind::String));
+    }
+}
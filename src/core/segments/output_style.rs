@@ -13,7 +13,10 @@ impl OutputStyleSegment {
 
 impl Segment for OutputStyleSegment {
     fn collect(&self, input: &InputData) -> Option<SegmentData> {
-        let output_style = input.output_style.as_ref()?;
+        let Some(output_style) = input.output_style.as_ref() else {
+            crate::trace::record("input has no output_style");
+            return None;
+        };
 
         // Primary display: style name
         let primary = output_style.name.clone();
@@ -10,7 +10,7 @@ impl SessionSegment {
         Self
     }
 
-    fn format_duration(ms: u64) -> String {
+    pub(crate) fn format_duration(ms: u64) -> String {
         if ms < 1000 {
             format!("{}ms", ms)
         } else if ms < 60_000 {
@@ -38,12 +38,16 @@ impl SessionSegment {
 
 impl Segment for SessionSegment {
     fn collect(&self, input: &InputData) -> Option<SegmentData> {
-        let cost_data = input.cost.as_ref()?;
+        let Some(cost_data) = input.cost.as_ref() else {
+            crate::trace::record("input has no cost data");
+            return None;
+        };
 
         // Primary display: total duration
         let primary = if let Some(duration) = cost_data.total_duration_ms {
             Self::format_duration(duration)
         } else {
+            crate::trace::record("input has no cost.total_duration_ms");
             return None;
         };
 
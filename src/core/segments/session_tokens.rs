@@ -0,0 +1,70 @@
+use super::glm_model_usage::format_tokens;
+use super::{Segment, SegmentData};
+use crate::config::{InputData, SegmentId};
+use std::collections::HashMap;
+
+/// Token totals of the whole session, summed from the transcript
+#[derive(Default)]
+pub struct SessionTokensSegment;
+
+impl SessionTokensSegment {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Segment for SessionTokensSegment {
+    fn collect(&self, input: &InputData) -> Option<SegmentData> {
+        let totals = crate::transcript::session_totals(&input.transcript_path)?;
+        if totals.turns == 0 {
+            crate::trace::record(format!(
+                "no assistant usage found in {}",
+                input.transcript_path
+            ));
+            return None;
+        }
+        let cache_hit_ratio = totals.cache_hit_ratio().unwrap_or(0.0);
+
+        let primary = format!(
+            "↑{} ↓{} 缓存 {}",
+            format_tokens(totals.input_tokens as f64),
+            format_tokens(totals.output_tokens as f64),
+            format_tokens(totals.cache_read_tokens as f64)
+        );
+        let secondary = format!("命中 {:.0}% · {} 轮", cache_hit_ratio, totals.turns);
+
+        let mut metadata = HashMap::new();
+        metadata.insert("input_tokens".to_string(), totals.input_tokens.to_string());
+        metadata.insert(
+            "output_tokens".to_string(),
+            totals.output_tokens.to_string(),
+        );
+        metadata.insert(
+            "cache_read_tokens".to_string(),
+            totals.cache_read_tokens.to_string(),
+        );
+        metadata.insert(
+            "cache_creation_tokens".to_string(),
+            totals.cache_creation_tokens.to_string(),
+        );
+        metadata.insert(
+            "total_tokens".to_string(),
+            (totals.prompt_tokens() + totals.output_tokens).to_string(),
+        );
+        metadata.insert(
+            "cache_hit_ratio".to_string(),
+            format!("{:.1}", cache_hit_ratio),
+        );
+        metadata.insert("turns".to_string(), totals.turns.to_string());
+
+        Some(SegmentData {
+            primary,
+            secondary,
+            metadata,
+        })
+    }
+
+    fn id(&self) -> SegmentId {
+        SegmentId::SessionTokens
+    }
+}
@@ -16,6 +16,10 @@ impl Segment for UpdateSegment {
         // Load update state and check for update status
         let update_state = UpdateState::load();
 
+        if update_state.status_text().is_none() {
+            crate::trace::record(format!("no update to show ({:?})", update_state.status));
+        }
+
         update_state.status_text().map(|status_text| SegmentData {
             primary: status_text,
             secondary: String::new(),
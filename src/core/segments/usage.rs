@@ -1,7 +1,8 @@
-use super::{Segment, SegmentData};
+use super::{OptionKind, Segment, SegmentData};
+use crate::api::ApiCache;
 use crate::config::{InputData, SegmentId};
 use crate::utils::credentials;
-use chrono::{DateTime, Datelike, Duration, Local, Timelike, Utc};
+use chrono::{DateTime, Datelike, Duration, Local, Timelike};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
@@ -22,15 +23,80 @@ struct ApiUsageCache {
     five_hour_utilization: f64,
     seven_day_utilization: f64,
     resets_at: Option<String>,
+}
+
+/// Format of the pre-ApiCache `~/.claude/glmcode/.api_usage_cache.json`
+#[derive(Debug, Deserialize)]
+struct LegacyUsageCache {
+    #[serde(flatten)]
+    usage: ApiUsageCache,
     cached_at: String,
 }
 
-#[derive(Default)]
-pub struct UsageSegment;
+impl From<ApiUsageResponse> for ApiUsageCache {
+    fn from(response: ApiUsageResponse) -> Self {
+        Self {
+            five_hour_utilization: response.five_hour.utilization,
+            seven_day_utilization: response.seven_day.utilization,
+            resets_at: response.seven_day.resets_at,
+        }
+    }
+}
+
+/// Options accepted in the segment's `options` table
+pub const OPTIONS: &[(&str, OptionKind)] = &[
+    ("api_base_url", OptionKind::String),
+    ("cache_duration", OptionKind::Integer),
+    ("timeout", OptionKind::Integer),
+];
+
+const DEFAULT_API_BASE_URL: &str = "https://api.anthropic.com";
+
+struct UsageOptions {
+    api_base_url: String,
+    cache_duration: u64,
+    timeout: u64,
+}
+
+impl Default for UsageOptions {
+    fn default() -> Self {
+        Self {
+            api_base_url: DEFAULT_API_BASE_URL.to_string(),
+            cache_duration: 300,
+            timeout: 2,
+        }
+    }
+}
+
+pub struct UsageSegment {
+    options: UsageOptions,
+}
+
+impl Default for UsageSegment {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
 impl UsageSegment {
     pub fn new() -> Self {
-        Self
+        Self {
+            options: UsageOptions::default(),
+        }
+    }
+
+    /// Read api_base_url, cache_duration and timeout from segment options
+    pub fn with_options(mut self, options: &HashMap<String, serde_json::Value>) -> Self {
+        if let Some(api_base_url) = options.get("api_base_url").and_then(|v| v.as_str()) {
+            self.options.api_base_url = api_base_url.to_string();
+        }
+        if let Some(cache_duration) = options.get("cache_duration").and_then(|v| v.as_u64()) {
+            self.options.cache_duration = cache_duration;
+        }
+        if let Some(timeout) = options.get("timeout").and_then(|v| v.as_u64()) {
+            self.options.timeout = timeout;
+        }
+        self
     }
 
     fn get_circle_icon(utilization: f64) -> String {
@@ -65,7 +131,22 @@ impl UsageSegment {
         "?".to_string()
     }
 
-    fn get_cache_path() -> Option<std::path::PathBuf> {
+    /// Instances pointing at another API base get their own cache
+    fn cache(options: &UsageOptions) -> ApiCache {
+        use std::collections::hash_map::DefaultHasher;
+        use std::hash::{Hash, Hasher};
+
+        let key = if options.api_base_url == DEFAULT_API_BASE_URL {
+            "anthropic_usage".to_string()
+        } else {
+            let mut hasher = DefaultHasher::new();
+            options.api_base_url.hash(&mut hasher);
+            format!("anthropic_usage_{:016x}", hasher.finish())
+        };
+        ApiCache::new(key, std::time::Duration::from_secs(options.cache_duration))
+    }
+
+    fn legacy_cache_path() -> Option<std::path::PathBuf> {
         let home = dirs::home_dir()?;
         Some(
             home.join(".claude")
@@ -74,35 +155,33 @@ impl UsageSegment {
         )
     }
 
-    fn load_cache(&self) -> Option<ApiUsageCache> {
-        let cache_path = Self::get_cache_path()?;
-        if !cache_path.exists() {
-            return None;
-        }
-
-        let content = std::fs::read_to_string(&cache_path).ok()?;
-        serde_json::from_str(&content).ok()
-    }
-
-    fn save_cache(&self, cache: &ApiUsageCache) {
-        if let Some(cache_path) = Self::get_cache_path() {
-            if let Some(parent) = cache_path.parent() {
-                let _ = std::fs::create_dir_all(parent);
-            }
-            if let Ok(json) = serde_json::to_string_pretty(cache) {
-                let _ = std::fs::write(&cache_path, json);
+    /// Move the old standalone cache file into the shared cache, keeping its timestamp
+    fn migrate_legacy_cache(cache: &ApiCache, legacy_path: &std::path::Path) {
+        let Ok(content) = std::fs::read_to_string(legacy_path) else {
+            return;
+        };
+        if cache.load::<ApiUsageCache>().is_none() {
+            if let Ok(legacy) = serde_json::from_str::<LegacyUsageCache>(&content) {
+                if let Ok(cached_at) = DateTime::parse_from_rfc3339(&legacy.cached_at) {
+                    cache.store_at(&legacy.usage, cached_at.with_timezone(&chrono::Utc));
+                }
             }
         }
+        let _ = std::fs::remove_file(legacy_path);
     }
 
-    fn is_cache_valid(&self, cache: &ApiUsageCache, cache_duration: u64) -> bool {
-        if let Ok(cached_at) = DateTime::parse_from_rfc3339(&cache.cached_at) {
-            let now = Utc::now();
-            let elapsed = now.signed_duration_since(cached_at.with_timezone(&Utc));
-            elapsed.num_seconds() < cache_duration as i64
-        } else {
-            false
-        }
+    /// Refresh the usage cache (called by the background refresher)
+    pub fn refresh(&self) -> Result<(), Box<dyn std::error::Error>> {
+        let token = credentials::get_oauth_token().ok_or("No OAuth token available")?;
+        let options = &self.options;
+
+        Self::cache(options)
+            .refresh(|| {
+                self.fetch_api_usage(&options.api_base_url, &token, options.timeout)
+                    .map(ApiUsageCache::from)
+                    .ok_or_else(|| "Usage API request failed".to_string())
+            })
+            .map_err(Into::into)
     }
 
     fn get_claude_code_version() -> String {
@@ -163,17 +242,31 @@ impl UsageSegment {
 
         let agent = agent_builder.build();
 
-        let response = agent
+        let response = match agent
             .get(&url)
             .set("Authorization", &format!("Bearer {}", token))
             .set("anthropic-beta", "oauth-2025-04-20")
             .set("User-Agent", &user_agent)
             .timeout(std::time::Duration::from_secs(timeout_secs))
             .call()
-            .ok()?;
+        {
+            Ok(response) => response,
+            Err(ureq::Error::Status(code, _)) => {
+                crate::trace::record(format!("GET {} -> {}", url, code));
+                return None;
+            }
+            Err(e) => {
+                crate::trace::record(format!("GET {} failed: {}", url, e));
+                return None;
+            }
+        };
+        crate::trace::record(format!("GET {} -> {}", url, response.status()));
 
         if response.status() == 200 {
-            response.into_json().ok()
+            response
+                .into_json()
+                .inspect_err(|e| crate::trace::record(format!("invalid usage response: {}", e)))
+                .ok()
         } else {
             None
         }
@@ -182,69 +275,21 @@ impl UsageSegment {
 
 impl Segment for UsageSegment {
     fn collect(&self, _input: &InputData) -> Option<SegmentData> {
-        let token = credentials::get_oauth_token()?;
-
-        // Load config from file to get segment options
-        let config = crate::config::Config::load().ok()?;
-        let segment_config = config.segments.iter().find(|s| s.id == SegmentId::Usage);
+        if credentials::get_oauth_token().is_none() {
+            crate::trace::record("no Claude OAuth token found");
+            return None;
+        }
 
-        let api_base_url = segment_config
-            .and_then(|sc| sc.options.get("api_base_url"))
-            .and_then(|v| v.as_str())
-            .unwrap_or("https://api.anthropic.com");
-
-        let cache_duration = segment_config
-            .and_then(|sc| sc.options.get("cache_duration"))
-            .and_then(|v| v.as_u64())
-            .unwrap_or(300);
-
-        let timeout = segment_config
-            .and_then(|sc| sc.options.get("timeout"))
-            .and_then(|v| v.as_u64())
-            .unwrap_or(2);
-
-        let cached_data = self.load_cache();
-        let use_cached = cached_data
-            .as_ref()
-            .map(|cache| self.is_cache_valid(cache, cache_duration))
-            .unwrap_or(false);
-
-        let (five_hour_util, seven_day_util, resets_at) = if use_cached {
-            let cache = cached_data.unwrap();
-            (
-                cache.five_hour_utilization,
-                cache.seven_day_utilization,
-                cache.resets_at,
-            )
-        } else {
-            match self.fetch_api_usage(api_base_url, &token, timeout) {
-                Some(response) => {
-                    let cache = ApiUsageCache {
-                        five_hour_utilization: response.five_hour.utilization,
-                        seven_day_utilization: response.seven_day.utilization,
-                        resets_at: response.seven_day.resets_at.clone(),
-                        cached_at: Utc::now().to_rfc3339(),
-                    };
-                    self.save_cache(&cache);
-                    (
-                        response.five_hour.utilization,
-                        response.seven_day.utilization,
-                        response.seven_day.resets_at,
-                    )
-                }
-                None => {
-                    if let Some(cache) = cached_data {
-                        (
-                            cache.five_hour_utilization,
-                            cache.seven_day_utilization,
-                            cache.resets_at,
-                        )
-                    } else {
-                        return None;
-                    }
-                }
+        let cache = Self::cache(&self.options);
+        if self.options.api_base_url == DEFAULT_API_BASE_URL {
+            if let Some(legacy_path) = Self::legacy_cache_path() {
+                Self::migrate_legacy_cache(&cache, &legacy_path);
             }
-        };
+        }
+        let cache: ApiUsageCache = cache.read()?;
+        let five_hour_util = cache.five_hour_utilization;
+        let seven_day_util = cache.seven_day_utilization;
+        let resets_at = cache.resets_at;
 
         let dynamic_icon = Self::get_circle_icon(seven_day_util / 100.0);
         let five_hour_percent = five_hour_util.round() as u8;
@@ -273,3 +318,32 @@ impl Segment for UsageSegment {
         SegmentId::Usage
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn legacy_cache_is_migrated_with_its_timestamp() {
+        let dir = std::env::temp_dir().join(format!("glmcode-usage-test-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let legacy_path = dir.join(".api_usage_cache.json");
+        std::fs::write(
+            &legacy_path,
+            r#"{"five_hour_utilization":12.0,"seven_day_utilization":34.0,"resets_at":null,"cached_at":"2020-01-01T00:00:00Z"}"#,
+        )
+        .unwrap();
+
+        let cache = ApiCache::new("anthropic_usage", std::time::Duration::from_secs(300))
+            .with_dir(dir.join("cache"));
+        UsageSegment::migrate_legacy_cache(&cache, &legacy_path);
+
+        let (usage, fresh) = cache.load::<ApiUsageCache>().unwrap();
+        assert_eq!(usage.five_hour_utilization, 12.0);
+        assert_eq!(usage.seven_day_utilization, 34.0);
+        assert!(!fresh);
+        assert!(!legacy_path.exists());
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
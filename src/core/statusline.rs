@@ -1,5 +1,14 @@
-use crate::config::{AnsiColor, Config, SegmentConfig, StyleMode};
+use crate::api::ApiCache;
+use crate::config::{AnsiColor, Config, ModelConfig, SegmentConfig, SegmentId, StyleMode};
 use crate::core::segments::SegmentData;
+use crate::core::template;
+use crate::trace::{self, SegmentOutcome, SegmentTrace};
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::sync::{mpsc, Arc};
+use std::thread;
+use std::time::{Duration, Instant};
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
 
 /// Strip ANSI escape sequences and return visible text length
 fn visible_width(text: &str) -> usize {
@@ -26,7 +35,86 @@ fn visible_width(text: &str) -> usize {
         }
     }
 
-    visible.chars().count()
+    visible.width()
+}
+
+/// Shorten text to `width` columns ending in an ellipsis. ANSI escape
+/// sequences are kept, and colors are reset after the cut.
+fn truncate_to_width(text: &str, width: usize) -> String {
+    if visible_width(text) <= width {
+        return text.to_string();
+    }
+
+    let mut output = String::new();
+    let mut used = 0;
+    let mut styled = false;
+    let mut chars = text.chars();
+    while let Some(ch) = chars.next() {
+        if ch == '\x1b' {
+            // Copy the escape sequence through its final letter
+            styled = true;
+            output.push(ch);
+            for next in chars.by_ref() {
+                output.push(next);
+                if next.is_alphabetic() {
+                    break;
+                }
+            }
+            continue;
+        }
+        let ch_width = ch.width().unwrap_or(0);
+        if used + ch_width + 1 > width {
+            break;
+        }
+        output.push(ch);
+        used += ch_width;
+    }
+
+    output.push('…');
+    if styled {
+        output.push_str("\x1b[0m");
+    }
+    output
+}
+
+/// Visible width of a segment's primary and secondary text
+fn text_width(data: &SegmentData) -> usize {
+    match data.secondary.is_empty() {
+        true => visible_width(&data.primary),
+        false => visible_width(&data.primary) + 1 + visible_width(&data.secondary),
+    }
+}
+
+/// Shorten a segment's text to `width` columns. Secondary text is folded into
+/// the primary first; both are drawn with the same style and spacing.
+fn shorten_segment(data: &mut SegmentData, width: usize) {
+    if !data.secondary.is_empty() {
+        data.primary = format!("{} {}", data.primary, std::mem::take(&mut data.secondary));
+    }
+    data.primary = truncate_to_width(&data.primary, width);
+}
+
+/// Apply the generic `format` and `color_rules` options. Colors are resolved
+/// into the config itself so powerline arrows pick up a new background too.
+fn apply_segment_options(
+    (mut config, mut data): (SegmentConfig, SegmentData),
+) -> (SegmentConfig, SegmentData) {
+    if let Some(format) = config.options.get("format").and_then(|v| v.as_str()) {
+        data.primary = template::render(format, &data);
+        data.secondary = String::new();
+    }
+    if config.options.contains_key("color_rules") {
+        config.colors = config.resolved_colors(&data.metadata);
+    }
+    (config, data)
+}
+
+/// One line of the statusline: left-aligned segments, then right-aligned ones
+struct Row {
+    separator: String,
+    segments: Vec<(SegmentConfig, SegmentData)>,
+    /// Index where the right-aligned group starts
+    split: usize,
 }
 
 pub struct StatusLineGenerator {
@@ -39,16 +127,187 @@ impl StatusLineGenerator {
     }
 
     pub fn generate(&self, segments: Vec<(SegmentConfig, SegmentData)>) -> String {
-        let mut output = Vec::new();
         let enabled_segments: Vec<_> = segments
             .into_iter()
             .filter(|(config, _)| config.enabled)
+            .map(apply_segment_options)
             .collect();
 
-        for (config, data) in enabled_segments.iter() {
+        let max_width = self.max_width();
+        self.layout_rows(enabled_segments)
+            .into_iter()
+            .map(|row| {
+                let row = match max_width {
+                    Some(max_width) => self.fit_row(row, max_width),
+                    None => row,
+                };
+                self.render_line(&row, max_width)
+            })
+            .filter(|line| !line.is_empty())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// Split segments into the configured rows. Segments not listed in any
+    /// row go on the left of the last one.
+    fn layout_rows(&self, segments: Vec<(SegmentConfig, SegmentData)>) -> Vec<Row> {
+        let default_separator = &self.config.style.separator;
+        if self.config.rows.is_empty() {
+            return vec![Row {
+                separator: default_separator.clone(),
+                split: segments.len(),
+                segments,
+            }];
+        }
+
+        let mut remaining: Vec<_> = segments.into_iter().map(Some).collect();
+        let mut place = |keys: &[String]| -> Vec<_> {
+            keys.iter()
+                .filter_map(|key| {
+                    remaining
+                        .iter_mut()
+                        .find(|slot| {
+                            slot.as_ref()
+                                .is_some_and(|(config, _)| config.instance_key() == *key)
+                        })?
+                        .take()
+                })
+                .collect()
+        };
+        let mut rows: Vec<_> = self
+            .config
+            .rows
+            .iter()
+            .map(|row| {
+                let separator = row.separator.as_ref().unwrap_or(default_separator);
+                (separator.clone(), place(&row.segments), place(&row.right))
+            })
+            .collect();
+
+        if let Some((_, left, _)) = rows.last_mut() {
+            left.extend(remaining.into_iter().flatten());
+        }
+        rows.into_iter()
+            .map(|(separator, mut left, right)| {
+                let split = left.len();
+                left.extend(right);
+                Row {
+                    separator,
+                    segments: left,
+                    split,
+                }
+            })
+            .collect()
+    }
+
+    /// Row width limit: `max_width` from the config, else the `COLUMNS` variable
+    fn max_width(&self) -> Option<usize> {
+        self.config
+            .max_width
+            .or_else(|| std::env::var("COLUMNS").ok()?.trim().parse().ok())
+            .filter(|width| *width > 0)
+    }
+
+    /// Fit a row into `max_width` columns. The lowest-priority segments (the
+    /// rightmost among equals) are dropped until the rest could fit, then text
+    /// is shortened down to each segment's `min_width`, lowest priority first.
+    /// Segments without a `min_width` are never shortened and the last one is
+    /// never dropped, so the result can still be wider than `max_width`.
+    fn fit_row(&self, mut row: Row, max_width: usize) -> Row {
+        let row_width = |row: &Row| visible_width(&self.render_line(row, None));
+        if row_width(&row) <= max_width {
+            return row;
+        }
+
+        // Width of the row with every shortenable segment at its min_width
+        let narrowest_width = |row: &Row| {
+            let segments = row
+                .segments
+                .iter()
+                .cloned()
+                .map(|(config, mut data)| {
+                    if let Some(min_width) = config.min_width() {
+                        if text_width(&data) > min_width {
+                            shorten_segment(&mut data, min_width);
+                        }
+                    }
+                    (config, data)
+                })
+                .collect();
+            row_width(&Row {
+                separator: row.separator.clone(),
+                segments,
+                split: row.split,
+            })
+        };
+        while row.segments.len() > 1 && narrowest_width(&row) > max_width {
+            let lowest = row
+                .segments
+                .iter()
+                .enumerate()
+                .min_by_key(|(i, (config, _))| (config.priority(), Reverse(*i)))
+                .map(|(i, _)| i);
+            if let Some(lowest) = lowest {
+                row.segments.remove(lowest);
+                if lowest < row.split {
+                    row.split -= 1;
+                }
+            }
+        }
+
+        let mut order: Vec<usize> = (0..row.segments.len()).collect();
+        order.sort_by_key(|&i| (row.segments[i].0.priority(), Reverse(i)));
+        for i in order {
+            let excess = row_width(&row).saturating_sub(max_width);
+            if excess == 0 {
+                break;
+            }
+            let Some(min_width) = row.segments[i].0.min_width() else {
+                continue;
+            };
+            let current = text_width(&row.segments[i].1);
+            if current > min_width {
+                let target = current.saturating_sub(excess).max(min_width);
+                shorten_segment(&mut row.segments[i].1, target);
+            }
+        }
+        row
+    }
+
+    /// Render a row, padding between its left and right groups so the right
+    /// group ends at `width` (a single space when the width is unknown)
+    fn render_line(&self, row: &Row, width: Option<usize>) -> String {
+        let (left, right) = row.segments.split_at(row.split);
+        let left = self.render_row(left, &row.separator, false);
+        let right = self.render_row(right, &row.separator, true);
+        if right.is_empty() {
+            return left;
+        }
+
+        let used = visible_width(&left) + visible_width(&right);
+        let min_gap = usize::from(!left.is_empty());
+        let gap = width
+            .map(|width| width.saturating_sub(used))
+            .unwrap_or(0)
+            .max(min_gap);
+        format!("{}{}{}", left, " ".repeat(gap), right)
+    }
+
+    /// Render one group of segments joined by `separator`. `reverse` points
+    /// powerline arrows leftwards, for right-aligned groups.
+    fn render_row(
+        &self,
+        segments: &[(SegmentConfig, SegmentData)],
+        separator: &str,
+        reverse: bool,
+    ) -> String {
+        let mut output = Vec::new();
+        let mut rendered_configs = Vec::new();
+        for (config, data) in segments {
             let rendered = self.render_segment(config, data);
             if !rendered.is_empty() {
                 output.push(rendered);
+                rendered_configs.push(config);
             }
         }
 
@@ -57,11 +316,11 @@ impl StatusLineGenerator {
         }
 
         // Handle Powerline arrow separators with color transition
-        if self.config.style.separator == "\u{e0b0}" {
-            self.join_with_powerline_arrows(&output, &enabled_segments)
+        if separator == "\u{e0b0}" {
+            self.join_with_powerline_arrows(&output, &rendered_configs, reverse)
         } else {
             // For all other separators, use white color and simple join
-            self.join_with_white_separators(&output)
+            self.join_with_white_separators(&output, separator)
         }
     }
 
@@ -100,17 +359,84 @@ impl StatusLineGenerator {
         let enabled_segments: Vec<_> = segments
             .into_iter()
             .filter(|(config, _)| config.enabled)
+            .map(apply_segment_options)
             .collect();
 
         if enabled_segments.is_empty() {
             return Text::from(vec![Line::default()]);
         }
 
+        // Each row starts on a new line and wraps on its own
+        let lines: Vec<String> = self
+            .layout_rows(enabled_segments)
+            .into_iter()
+            .flat_map(|row| {
+                // The terminal width is unknown here, so only `max_width` applies
+                let row = match self.config.max_width.filter(|width| *width > 0) {
+                    Some(limit) => self.fit_row(row, limit),
+                    None => row,
+                };
+                self.wrap_row(&row, max_width as usize)
+            })
+            .collect();
+
+        // Convert string lines to ratatui Text
+        let mut tui_lines = Vec::new();
+        for line in lines {
+            if let Ok(text) = line.into_text() {
+                for tui_line in text.lines {
+                    tui_lines.push(tui_line);
+                }
+            } else {
+                tui_lines.push(Line::from(vec![Span::raw(line)]));
+            }
+        }
+
+        // Ensure we have at least one line
+        if tui_lines.is_empty() {
+            tui_lines.push(Line::default());
+        }
+
+        Text::from(tui_lines)
+    }
+
+    /// Render one row for the preview, wrapping its left group by segment at
+    /// `max_w` and aligning the right group to the preview's right edge
+    fn wrap_row(&self, row: &Row, max_w: usize) -> Vec<String> {
+        let (left, right) = row.segments.split_at(row.split);
+        let mut lines = self.wrap_segments(left, &row.separator, max_w);
+
+        let right = self.render_row(right, &row.separator, true);
+        if !right.is_empty() {
+            let right_width = visible_width(&right);
+            match lines.last_mut() {
+                Some(last) if visible_width(last) + 1 + right_width <= max_w => {
+                    let gap = max_w - visible_width(last) - right_width;
+                    last.push_str(&" ".repeat(gap));
+                    last.push_str(&right);
+                }
+                _ => lines.push(format!(
+                    "{}{}",
+                    " ".repeat(max_w.saturating_sub(right_width)),
+                    right
+                )),
+            }
+        }
+        lines
+    }
+
+    /// Render segments for the preview, wrapping them by segment at `max_w`
+    fn wrap_segments(
+        &self,
+        segments: &[(SegmentConfig, SegmentData)],
+        separator: &str,
+        max_w: usize,
+    ) -> Vec<String> {
         // Render each segment individually
         let mut rendered_segments = Vec::new();
         let mut segment_configs = Vec::new();
 
-        for (config, data) in &enabled_segments {
+        for (config, data) in segments {
             let rendered = self.render_segment(config, data);
             if !rendered.is_empty() {
                 rendered_segments.push(rendered);
@@ -119,13 +445,13 @@ impl StatusLineGenerator {
         }
 
         if rendered_segments.is_empty() {
-            return Text::from(vec![Line::default()]);
+            return Vec::new();
         }
 
         // Pre-calculate separators between segments
         let mut separators = Vec::new();
         for i in 0..rendered_segments.len().saturating_sub(1) {
-            let separator = if self.config.style.separator == "\u{e0b0}" {
+            let separator = if separator == "\u{e0b0}" {
                 // Powerline arrows with color transition
                 let prev_bg = segment_configs
                     .get(i)
@@ -133,10 +459,10 @@ impl StatusLineGenerator {
                 let curr_bg = segment_configs
                     .get(i + 1)
                     .and_then(|config| config.colors.background.as_ref());
-                self.create_powerline_arrow(prev_bg, curr_bg)
+                self.create_powerline_arrow(prev_bg, curr_bg, false)
             } else {
                 // Regular separators with white color
-                format!("\x1b[37m{}\x1b[0m", self.config.style.separator)
+                format!("\x1b[37m{}\x1b[0m", separator)
             };
             separators.push(separator);
         }
@@ -145,7 +471,6 @@ impl StatusLineGenerator {
         let mut lines: Vec<String> = Vec::new();
         let mut current_line = String::new();
         let mut current_width = 0usize;
-        let max_w = max_width as usize;
 
         for i in 0..rendered_segments.len() {
             let segment = &rendered_segments[i];
@@ -194,24 +519,7 @@ impl StatusLineGenerator {
             lines.push(current_line);
         }
 
-        // Convert string lines to ratatui Text
-        let mut tui_lines = Vec::new();
-        for line in lines {
-            if let Ok(text) = line.into_text() {
-                for tui_line in text.lines {
-                    tui_lines.push(tui_line);
-                }
-            } else {
-                tui_lines.push(Line::from(vec![Span::raw(line)]));
-            }
-        }
-
-        // Ensure we have at least one line
-        if tui_lines.is_empty() {
-            tui_lines.push(Line::default());
-        }
-
-        Text::from(tui_lines)
+        lines
     }
 
     fn render_segment(&self, config: &SegmentConfig, data: &SegmentData) -> String {
@@ -358,13 +666,13 @@ impl StatusLineGenerator {
     }
 
     /// Join segments with white separators (non-Powerline)
-    fn join_with_white_separators(&self, rendered_segments: &[String]) -> String {
+    fn join_with_white_separators(&self, rendered_segments: &[String], separator: &str) -> String {
         if rendered_segments.is_empty() {
             return String::new();
         }
 
         // Use white color for separator
-        let white_separator = format!("\x1b[37m{}\x1b[0m", self.config.style.separator);
+        let white_separator = format!("\x1b[37m{}\x1b[0m", separator);
         rendered_segments.join(&white_separator)
     }
 
@@ -372,7 +680,8 @@ impl StatusLineGenerator {
     fn join_with_powerline_arrows(
         &self,
         rendered_segments: &[String],
-        segment_configs: &[(SegmentConfig, SegmentData)],
+        segment_configs: &[&SegmentConfig],
+        reverse: bool,
     ) -> String {
         if rendered_segments.is_empty() {
             return String::new();
@@ -387,13 +696,13 @@ impl StatusLineGenerator {
         for (i, _) in rendered_segments.iter().enumerate().skip(1) {
             let prev_bg = segment_configs
                 .get(i - 1)
-                .and_then(|(config, _)| config.colors.background.as_ref());
+                .and_then(|config| config.colors.background.as_ref());
             let curr_bg = segment_configs
                 .get(i)
-                .and_then(|(config, _)| config.colors.background.as_ref());
+                .and_then(|config| config.colors.background.as_ref());
 
             // Create Powerline arrow with color transition
-            let arrow = self.create_powerline_arrow(prev_bg, curr_bg);
+            let arrow = self.create_powerline_arrow(prev_bg, curr_bg, reverse);
 
             result.push_str(&arrow);
             result.push_str(&rendered_segments[i]);
@@ -404,30 +713,36 @@ impl StatusLineGenerator {
         result
     }
 
-    /// Create a Powerline arrow with proper color transition
+    /// Create a Powerline arrow with proper color transition. A `reverse`
+    /// arrow points left and takes its color from the segment after it.
     fn create_powerline_arrow(
         &self,
         prev_bg: Option<&AnsiColor>,
         curr_bg: Option<&AnsiColor>,
+        reverse: bool,
     ) -> String {
-        let arrow_char = "\u{e0b0}";
-
-        match (prev_bg, curr_bg) {
-            (Some(prev), Some(curr)) => {
-                // Arrow foreground = previous segment's background
-                // Arrow background = current segment's background
-                let fg_code = self.color_to_foreground_code(prev);
-                let bg_code = self.apply_background_color(curr);
+        let (arrow_char, fg_bg, bg_bg) = if reverse {
+            ("\u{e0b2}", curr_bg, prev_bg)
+        } else {
+            ("\u{e0b0}", prev_bg, curr_bg)
+        };
+
+        match (fg_bg, bg_bg) {
+            (Some(fg), Some(bg)) => {
+                // Arrow foreground = background of the segment it points from
+                // Arrow background = background of the segment it points into
+                let fg_code = self.color_to_foreground_code(fg);
+                let bg_code = self.apply_background_color(bg);
                 format!("{}{}{}\x1b[0m", bg_code, fg_code, arrow_char)
             }
-            (Some(prev), None) => {
-                // Previous segment has background, current doesn't
-                let fg_code = self.color_to_foreground_code(prev);
+            (Some(fg), None) => {
+                // Only the segment it points from has a background
+                let fg_code = self.color_to_foreground_code(fg);
                 format!("{}{}\x1b[0m", fg_code, arrow_char)
             }
-            (None, Some(curr)) => {
-                // Current segment has background, previous doesn't
-                let bg_code = self.apply_background_color(curr);
+            (None, Some(bg)) => {
+                // Only the segment it points into has a background
+                let bg_code = self.apply_background_color(bg);
                 format!("{}{}\x1b[0m", bg_code, arrow_char)
             }
             (None, None) => {
@@ -454,72 +769,581 @@ impl StatusLineGenerator {
     }
 }
 
+/// A finished segment: its data, how long `collect` took and its trace events
+type CollectedSegment = (Option<SegmentData>, Duration, Vec<String>);
+
+/// Cache key for the last successfully collected data of every segment
+const LAST_SEGMENTS_CACHE_KEY: &str = "last_segments";
+
+/// Collect every enabled segment concurrently, bounded by `config.render_deadline_ms`
+///
+/// Segments that miss the deadline fall back to the data they produced on the
+/// last render, or a placeholder. Output order always follows `config.segments`.
 pub fn collect_all_segments(
     config: &Config,
     input: &crate::config::InputData,
 ) -> Vec<(SegmentConfig, SegmentData)> {
-    use crate::core::segments::*;
+    collect_all_segments_traced(config, input).0
+}
 
-    let mut results = Vec::new();
+/// Same as [`collect_all_segments`], also returning per-segment diagnostics
+pub fn collect_all_segments_traced(
+    config: &Config,
+    input: &crate::config::InputData,
+) -> (Vec<(SegmentConfig, SegmentData)>, Vec<SegmentTrace>) {
+    let last_cache = ApiCache::new(LAST_SEGMENTS_CACHE_KEY, Duration::MAX);
+    collect_with_fallback(config, input, &last_cache)
+}
 
-    for segment_config in &config.segments {
-        // Skip disabled segments to avoid unnecessary API requests
-        if !segment_config.enabled {
-            continue;
-        }
+fn collect_with_fallback(
+    config: &Config,
+    input: &crate::config::InputData,
+    last_cache: &ApiCache,
+) -> (Vec<(SegmentConfig, SegmentData)>, Vec<SegmentTrace>) {
+    // Skip disabled segments to avoid unnecessary API requests
+    let enabled: Vec<SegmentConfig> = config
+        .segments
+        .iter()
+        .filter(|segment_config| segment_config.enabled)
+        .cloned()
+        .collect();
+
+    let deadline = (config.render_deadline_ms > 0)
+        .then(|| Instant::now() + Duration::from_millis(config.render_deadline_ms));
+
+    // Threads are detached rather than scoped: a scope would join stragglers
+    // and block past the deadline, while detached ones die with the process.
+    // models.toml is only read when a segment prices usage
+    let models = Arc::new(
+        match enabled
+            .iter()
+            .any(|segment_config| segment_config.id == SegmentId::EstimatedCost)
+        {
+            true => ModelConfig::load(),
+            false => ModelConfig::default(),
+        },
+    );
+
+    let input = Arc::new(input.clone());
+    let (tx, rx) = mpsc::channel();
+    for (index, segment_config) in enabled.iter().enumerate() {
+        let tx = tx.clone();
+        let input = Arc::clone(&input);
+        let segment_config = segment_config.clone();
+        let glm = config.glm.clone();
+        let models = Arc::clone(&models);
+        let render_deadline_ms = config.render_deadline_ms;
+        thread::spawn(move || {
+            let start = Instant::now();
+            let data =
+                crate::core::segments::build(&segment_config, &glm, &models, render_deadline_ms)
+                    .collect(&input);
+            let _ = tx.send((index, data, start.elapsed(), trace::take()));
+        });
+    }
+    drop(tx);
+
+    let mut collected: Vec<Option<CollectedSegment>> = vec![None; enabled.len()];
+    let mut pending = enabled.len();
+    while pending > 0 {
+        let received = match deadline {
+            Some(deadline) => rx
+                .recv_timeout(deadline.saturating_duration_since(Instant::now()))
+                .ok(),
+            None => rx.recv().ok(),
+        };
+        let Some((index, data, elapsed, events)) = received else {
+            break;
+        };
+        collected[index] = Some((data, elapsed, events));
+        pending -= 1;
+    }
 
-        let segment_data = match segment_config.id {
-            crate::config::SegmentId::Model => {
-                let segment = ModelSegment::new();
-                segment.collect(input)
-            }
-            crate::config::SegmentId::Directory => {
-                let segment = DirectorySegment::new();
-                segment.collect(input)
-            }
-            crate::config::SegmentId::Git => {
-                let show_sha = segment_config
-                    .options
-                    .get("show_sha")
-                    .and_then(|v| v.as_bool())
-                    .unwrap_or(false);
-                let segment = GitSegment::new().with_sha(show_sha);
-                segment.collect(input)
-            }
-            crate::config::SegmentId::ContextWindow => {
-                let segment = ContextWindowSegment::new();
-                segment.collect(input)
-            }
-            crate::config::SegmentId::Usage => {
-                let segment = UsageSegment::new();
-                segment.collect(input)
-            }
-            crate::config::SegmentId::Session => {
-                let segment = SessionSegment::new();
-                segment.collect(input)
-            }
-            crate::config::SegmentId::OutputStyle => {
-                let segment = OutputStyleSegment::new();
-                segment.collect(input)
-            }
-            crate::config::SegmentId::Update => {
-                let segment = UpdateSegment::new();
-                segment.collect(input)
-            }
-            crate::config::SegmentId::GlmUsage => {
-                let segment = GlmUsageSegment::new();
-                segment.collect(input)
+    let mut last_segments: HashMap<String, SegmentData> = last_cache
+        .load()
+        .map(|(segments, _)| segments)
+        .unwrap_or_default();
+
+    let mut results = Vec::new();
+    let mut traces = Vec::new();
+    let mut updated = false;
+    for (segment_config, slot) in enabled.into_iter().zip(collected) {
+        let id = segment_config.id;
+        let key = segment_config.key.clone();
+        let instance = segment_config.instance_key();
+        let (outcome, duration, events) = match slot {
+            Some((Some(data), elapsed, events)) => {
+                // Only rewrite the file when something actually changed
+                if last_segments.get(&instance) != Some(&data) {
+                    last_segments.insert(instance, data.clone());
+                    updated = true;
+                }
+                results.push((segment_config, data));
+                (SegmentOutcome::Rendered, Some(elapsed), events)
             }
-            crate::config::SegmentId::DecodeSpeed => {
-                let segment = DecodeSpeedSegment::new();
-                segment.collect(input)
+            // Segment chose not to render
+            Some((None, elapsed, events)) => (SegmentOutcome::Hidden, Some(elapsed), events),
+            // Missed the deadline
+            None => {
+                let (data, outcome) = match last_segments.get(&instance) {
+                    Some(data) => {
+                        let mut data = data.clone();
+                        data.metadata
+                            .insert("fallback".to_string(), "cached".to_string());
+                        (data, SegmentOutcome::CachedFallback)
+                    }
+                    None => (placeholder_segment_data(), SegmentOutcome::Placeholder),
+                };
+                results.push((segment_config, data));
+                (outcome, None, Vec::new())
             }
         };
+        traces.push(SegmentTrace {
+            id,
+            key,
+            duration,
+            outcome,
+            events,
+        });
+    }
+
+    if updated {
+        last_cache.store(&last_segments);
+    }
+
+    (results, traces)
+}
+
+/// Placeholder shown for a segment that missed the deadline with nothing cached
+fn placeholder_segment_data() -> SegmentData {
+    let mut metadata = HashMap::new();
+    metadata.insert("fallback".to_string(), "placeholder".to_string());
+    SegmentData {
+        primary: "…".to_string(),
+        secondary: String::new(),
+        metadata,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{InputData, SegmentId};
+    use std::path::PathBuf;
+
+    fn input(current_dir: &str) -> InputData {
+        serde_json::from_value(serde_json::json!({
+            "model": { "id": "glm-4.6", "display_name": "GLM-4.6" },
+            "workspace": { "current_dir": current_dir },
+            "transcript_path": "",
+        }))
+        .unwrap()
+    }
+
+    fn segment(id: SegmentId, options: serde_json::Value) -> SegmentConfig {
+        let mut segment = Config::default().segments[0].clone();
+        segment.id = id;
+        segment.key = None;
+        segment.enabled = true;
+        segment.options = serde_json::from_value(options).unwrap();
+        segment
+    }
+
+    fn config(segments: Vec<SegmentConfig>, render_deadline_ms: u64) -> Config {
+        Config {
+            segments,
+            render_deadline_ms,
+            ..Config::default()
+        }
+    }
+
+    fn last_cache(name: &str) -> (ApiCache, PathBuf) {
+        let dir = std::env::temp_dir().join(format!(
+            "glmcode-statusline-test-{}-{}",
+            name,
+            std::process::id()
+        ));
+        let _ = std::fs::remove_dir_all(&dir);
+        let cache = ApiCache::new(LAST_SEGMENTS_CACHE_KEY, Duration::MAX).with_dir(dir.clone());
+        (cache, dir)
+    }
+
+    fn data(primary: &str) -> SegmentData {
+        SegmentData {
+            primary: primary.to_string(),
+            secondary: String::new(),
+            metadata: HashMap::new(),
+        }
+    }
 
-        if let Some(data) = segment_data {
-            results.push((segment_config.clone(), data));
+    /// Text with ANSI escape sequences removed
+    fn plain(text: &str) -> String {
+        let mut output = String::new();
+        let mut chars = text.chars();
+        while let Some(ch) = chars.next() {
+            if ch == '\x1b' {
+                chars.by_ref().find(|next| next.is_alphabetic());
+            } else {
+                output.push(ch);
+            }
         }
+        output
+    }
+
+    /// Uncolored segment keyed `key`, drawn as its upper-cased key then its text
+    fn keyed(key: &str, text: &str) -> (SegmentConfig, SegmentData) {
+        let mut config = segment(SegmentId::Model, serde_json::json!({}));
+        config.key = Some(key.to_string());
+        config.icon.plain = key.to_uppercase();
+        config.colors = crate::config::ColorConfig {
+            icon: None,
+            text: None,
+            background: None,
+        };
+        config.styles.text_bold = false;
+        (config, data(text))
+    }
+
+    fn generator(rows: serde_json::Value, max_width: Option<usize>) -> StatusLineGenerator {
+        StatusLineGenerator::new(Config {
+            style: crate::config::StyleConfig {
+                mode: crate::config::StyleMode::Plain,
+                separator: " | ".to_string(),
+            },
+            rows: serde_json::from_value(rows).unwrap(),
+            max_width,
+            ..Config::default()
+        })
+    }
+
+    fn row_keys(row: &Row) -> (Vec<String>, Vec<String>) {
+        let keys = |segments: &[(SegmentConfig, SegmentData)]| {
+            segments
+                .iter()
+                .map(|(config, _)| config.instance_key())
+                .collect()
+        };
+        let (left, right) = row.segments.split_at(row.split);
+        (keys(left), keys(right))
+    }
+
+    #[test]
+    fn segment_missing_the_deadline_falls_back_to_last_data() {
+        let (cache, dir) = last_cache("fallback");
+        let mut last = HashMap::new();
+        last.insert("custom:slow".to_string(), data("last time"));
+        cache.store(&last);
+
+        let config = config(
+            vec![
+                segment(SegmentId::Directory, serde_json::json!({})),
+                segment(
+                    SegmentId::Custom,
+                    serde_json::json!({ "name": "slow", "command": "sleep 2" }),
+                ),
+                segment(
+                    SegmentId::Custom,
+                    serde_json::json!({ "name": "never", "command": "sleep 2" }),
+                ),
+            ],
+            100,
+        );
+        let (results, traces) = collect_with_fallback(&config, &input("/tmp/project"), &cache);
+
+        assert_eq!(results.len(), 3);
+        assert_eq!(results[0].1.primary, "project");
+        assert_eq!(traces[0].outcome, SegmentOutcome::Rendered);
+
+        assert_eq!(results[1].1.primary, "last time");
+        assert_eq!(results[1].1.metadata.get("fallback").unwrap(), "cached");
+        assert_eq!(traces[1].outcome, SegmentOutcome::CachedFallback);
+
+        assert_eq!(results[2].1, placeholder_segment_data());
+        assert_eq!(traces[2].outcome, SegmentOutcome::Placeholder);
+
+        // The fallback entry is kept and the fresh one added
+        let (stored, _) = cache.load::<HashMap<String, SegmentData>>().unwrap();
+        assert_eq!(stored.get("custom:slow"), Some(&data("last time")));
+        assert_eq!(stored.get("directory").unwrap().primary, "project");
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn unchanged_segments_do_not_rewrite_last_data() {
+        let (cache, dir) = last_cache("unchanged");
+        let config = config(
+            vec![segment(SegmentId::Directory, serde_json::json!({}))],
+            0,
+        );
+        collect_with_fallback(&config, &input("/tmp/project"), &cache);
+
+        let path = dir.join(format!("{}.json", LAST_SEGMENTS_CACHE_KEY));
+        let old = std::time::SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000);
+        let mtime = |path: &PathBuf| std::fs::metadata(path).unwrap().modified().unwrap();
+        std::fs::File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_modified(old)
+            .unwrap();
+
+        collect_with_fallback(&config, &input("/tmp/project"), &cache);
+        assert_eq!(mtime(&path), old);
+
+        collect_with_fallback(&config, &input("/tmp/other"), &cache);
+        assert_ne!(mtime(&path), old);
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn rows_place_segments_by_key_and_leftovers_go_last() {
+        let generator = generator(
+            serde_json::json!([
+                { "segments": ["a", "b"], "right": ["c"] },
+                { "segments": ["d", "missing"], "separator": " / " },
+            ]),
+            None,
+        );
+        let segments = ["a", "b", "c", "d", "e"]
+            .iter()
+            .map(|key| keyed(key, key))
+            .collect();
+
+        let rows = generator.layout_rows(segments);
+        assert_eq!(rows.len(), 2);
+        assert_eq!(
+            row_keys(&rows[0]),
+            (vec!["a".into(), "b".into()], vec!["c".into()])
+        );
+        assert_eq!(rows[0].separator, " | ");
+        // Unknown keys are skipped and unplaced segments join the last row
+        assert_eq!(row_keys(&rows[1]), (vec!["d".into(), "e".into()], vec![]));
+        assert_eq!(rows[1].separator, " / ");
+    }
+
+    #[test]
+    fn each_row_renders_on_its_own_line_with_its_separator() {
+        let generator = generator(
+            serde_json::json!([
+                { "segments": ["a", "b"] },
+                { "segments": ["c", "d"], "separator": " / " },
+            ]),
+            None,
+        );
+        let segments = ["a", "b", "c", "d"]
+            .iter()
+            .map(|key| keyed(key, key))
+            .collect();
+
+        assert_eq!(plain(&generator.generate(segments)), "A a | B b\nC c / D d");
+    }
+
+    #[test]
+    fn empty_rows_are_left_out() {
+        let generator = generator(
+            serde_json::json!([
+                { "segments": ["missing"] },
+                { "segments": ["a"] },
+                { "segments": ["hidden"] },
+            ]),
+            None,
+        );
+        let mut hidden = keyed("hidden", "");
+        hidden.0.icon.plain = String::new();
+        hidden.0.enabled = false;
+
+        assert_eq!(
+            plain(&generator.generate(vec![keyed("a", "a"), hidden])),
+            "A a"
+        );
+    }
+
+    #[test]
+    fn without_rows_everything_goes_on_one_line() {
+        let generator = generator(serde_json::json!([]), None);
+        let rows = generator.layout_rows(vec![keyed("a", "a"), keyed("b", "b")]);
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].split, 2);
+        assert_eq!(plain(&generator.render_line(&rows[0], None)), "A a | B b");
     }
 
-    results
+    #[test]
+    fn preview_wraps_each_row_by_segment() {
+        let generator = generator(
+            serde_json::json!([{ "segments": ["a", "b", "c"], "right": ["d"] }]),
+            None,
+        );
+        let rows = generator.layout_rows(
+            ["a", "b", "c", "d"]
+                .iter()
+                .map(|key| keyed(key, "text"))
+                .collect(),
+        );
+
+        // Wide enough: the right group is aligned to the preview edge
+        let lines: Vec<_> = generator
+            .wrap_row(&rows[0], 40)
+            .iter()
+            .map(|line| plain(line))
+            .collect();
+        assert_eq!(
+            lines,
+            vec![format!("A text | B text | C text{}D text", " ".repeat(10))]
+        );
+
+        // Narrower: wrap between segments, the right group shares the last line
+        let lines: Vec<_> = generator
+            .wrap_row(&rows[0], 16)
+            .iter()
+            .map(|line| plain(line))
+            .collect();
+        assert_eq!(lines, vec!["A text | B text", "C text    D text"]);
+
+        // Too narrow for that: the right group gets its own line
+        let lines: Vec<_> = generator
+            .wrap_row(&rows[0], 12)
+            .iter()
+            .map(|line| plain(line))
+            .collect();
+        assert_eq!(lines, vec!["A text", "B text", "C text", "      D text"]);
+    }
+
+    fn with_options(
+        (mut config, data): (SegmentConfig, SegmentData),
+        options: serde_json::Value,
+    ) -> (SegmentConfig, SegmentData) {
+        config.options = serde_json::from_value(options).unwrap();
+        (config, data)
+    }
+
+    fn fitted(
+        generator: &StatusLineGenerator,
+        segments: Vec<(SegmentConfig, SegmentData)>,
+    ) -> String {
+        let max_width = generator.max_width().unwrap();
+        let row = generator.layout_rows(segments).remove(0);
+        plain(&generator.render_line(&generator.fit_row(row, max_width), None))
+    }
+
+    #[test]
+    fn lowest_priority_segments_are_dropped_rightmost_first() {
+        let generator = generator(serde_json::json!([]), Some(12));
+        let segments = vec![
+            keyed("a", "aaa"),
+            with_options(keyed("b", "bbb"), serde_json::json!({ "priority": 5 })),
+            keyed("c", "ccc"),
+        ];
+        // "A aaa | B bbb | C ccc" is 21 columns: c goes, then a
+        assert_eq!(fitted(&generator, segments.clone()), "B bbb");
+
+        let generator = self::generator(serde_json::json!([]), Some(13));
+        assert_eq!(fitted(&generator, segments), "A aaa | B bbb");
+    }
+
+    #[test]
+    fn text_shrinks_to_min_width_before_anything_is_dropped() {
+        let generator = generator(serde_json::json!([]), Some(20));
+        let segments = vec![
+            with_options(
+                keyed("a", "a long branch name"),
+                serde_json::json!({ "min_width": 6 }),
+            ),
+            keyed("b", "bbb"),
+        ];
+        // Shortened just enough to fit, ending in an ellipsis
+        assert_eq!(fitted(&generator, segments.clone()), "A a long br… | B bbb");
+
+        // Too wide even at min_width: b is dropped and a shrinks into the rest
+        let generator = self::generator(serde_json::json!([]), Some(12));
+        assert_eq!(fitted(&generator, segments), "A a long br…");
+    }
+
+    #[test]
+    fn segments_without_min_width_are_never_shortened() {
+        let generator = generator(serde_json::json!([]), Some(1));
+        let model = keyed("m", "GLM-4.6 with a long display name");
+        // Nothing fits: the last segment stays, at full width
+        assert_eq!(
+            fitted(&generator, vec![model.clone(), keyed("b", "b")]),
+            "M GLM-4.6 with a long display name"
+        );
+
+        let shortenable = with_options(model, serde_json::json!({ "min_width": 1 }));
+        assert_eq!(fitted(&generator, vec![shortenable]), "M …");
+    }
+
+    #[test]
+    fn truncation_counts_wide_characters_and_keeps_escapes() {
+        assert_eq!(truncate_to_width("short", 5), "short");
+        assert_eq!(truncate_to_width("漢字漢字", 5), "漢字…");
+        // A wide character never straddles the limit
+        assert_eq!(truncate_to_width("漢字漢字", 4), "漢…");
+
+        let colored = "\x1b[31mbranch-name\x1b[0m";
+        let truncated = truncate_to_width(colored, 4);
+        assert_eq!(truncated, "\x1b[31mbra…\x1b[0m");
+        assert_eq!(visible_width(&truncated), 4);
+    }
+
+    #[test]
+    fn right_group_is_padded_to_the_width() {
+        let generator = generator(
+            serde_json::json!([{ "segments": ["a"], "right": ["b"] }]),
+            None,
+        );
+        let row = generator
+            .layout_rows(vec![keyed("a", "a"), keyed("b", "b")])
+            .remove(0);
+
+        assert_eq!(
+            plain(&generator.render_line(&row, Some(20))),
+            format!("A a{}B b", " ".repeat(14))
+        );
+        // Unknown width, or too narrow: a single space between the groups
+        assert_eq!(plain(&generator.render_line(&row, None)), "A a B b");
+        assert_eq!(plain(&generator.render_line(&row, Some(4))), "A a B b");
+
+        let right_only = generator.layout_rows(vec![keyed("b", "b")]).remove(0);
+        assert_eq!(right_only.split, 0);
+        assert_eq!(
+            plain(&generator.render_line(&right_only, Some(10))),
+            "       B b"
+        );
+        assert_eq!(plain(&generator.render_line(&right_only, None)), "B b");
+    }
+
+    #[test]
+    fn max_width_pads_the_right_group_in_generate() {
+        let generator = generator(
+            serde_json::json!([{ "segments": ["a"], "right": ["b"] }]),
+            Some(12),
+        );
+        assert_eq!(
+            plain(&generator.generate(vec![keyed("a", "a"), keyed("b", "b")])),
+            "A a      B b"
+        );
+    }
+
+    #[test]
+    fn right_group_uses_reversed_powerline_arrows() {
+        let generator = generator(serde_json::json!([]), None);
+        let background = |(mut config, data): (SegmentConfig, SegmentData), c256| {
+            config.colors.background = Some(AnsiColor::Color256 { c256 });
+            (config, data)
+        };
+        let segments = vec![
+            background(keyed("a", "a"), 1),
+            background(keyed("b", "b"), 2),
+        ];
+
+        let left = generator.render_row(&segments, "\u{e0b0}", false);
+        assert!(left.contains("\x1b[48;5;2m\x1b[38;5;1m\u{e0b0}"));
+        assert!(!left.contains('\u{e0b2}'));
+
+        // Pointing left, the arrow takes the color of the segment after it
+        let right = generator.render_row(&segments, "\u{e0b0}", true);
+        assert!(right.contains("\x1b[48;5;1m\x1b[38;5;2m\u{e0b2}"));
+        assert!(!right.contains('\u{e0b0}'));
+        assert_eq!(plain(&right), " A a \u{e0b2} B b ");
+    }
 }
@@ -0,0 +1,145 @@
+use crate::core::segments::SegmentData;
+
+/// Render `template` against the segment's data
+///
+/// `{key}` reads metadata (or `primary` / `secondary`), `{key:.N}` rounds it,
+/// `{key_k}` / `{key_m}` scale it to thousands / millions, and `{{` / `}}`
+/// are literal braces. Unknown keys render as empty text.
+pub fn render(template: &str, data: &SegmentData) -> String {
+    let mut output = String::new();
+    let mut chars = template.chars().peekable();
+
+    while let Some(ch) = chars.next() {
+        match ch {
+            '{' if chars.peek() == Some(&'{') => {
+                chars.next();
+                output.push('{');
+            }
+            '}' if chars.peek() == Some(&'}') => {
+                chars.next();
+                output.push('}');
+            }
+            '{' => {
+                let mut placeholder = String::new();
+                let mut closed = false;
+                for ch in chars.by_ref() {
+                    if ch == '}' {
+                        closed = true;
+                        break;
+                    }
+                    placeholder.push(ch);
+                }
+                if closed {
+                    output.push_str(&expand(&placeholder, data));
+                } else {
+                    // Unterminated placeholder: keep it verbatim
+                    output.push('{');
+                    output.push_str(&placeholder);
+                }
+            }
+            _ => output.push(ch),
+        }
+    }
+
+    output
+}
+
+/// Expand one `key[:.N]` placeholder
+fn expand(placeholder: &str, data: &SegmentData) -> String {
+    let (key, precision) = match placeholder.split_once(':') {
+        Some((key, spec)) => (key.trim(), parse_precision(spec)),
+        None => (placeholder.trim(), None),
+    };
+
+    if let Some(value) = lookup(key, data) {
+        return match (precision, value.parse::<f64>()) {
+            (Some(precision), Ok(number)) => format!("{:.*}", precision, number),
+            _ => value,
+        };
+    }
+
+    // Scaled variants of numeric keys
+    let scaled = [("_k", 1_000.0, "k"), ("_m", 1_000_000.0, "M")]
+        .into_iter()
+        .find_map(|(suffix, divisor, unit)| {
+            let base = key.strip_suffix(suffix)?;
+            let number = lookup(base, data)?.parse::<f64>().ok()?;
+            Some((number / divisor, unit))
+        });
+    match scaled {
+        Some((number, unit)) => match precision {
+            Some(precision) => format!("{:.*}{}", precision, number, unit),
+            None => format!("{}{}", trim_decimal(number), unit),
+        },
+        None => String::new(),
+    }
+}
+
+fn lookup(key: &str, data: &SegmentData) -> Option<String> {
+    match key {
+        "primary" => Some(data.primary.clone()),
+        "secondary" => Some(data.secondary.clone()),
+        _ => data.metadata.get(key).cloned(),
+    }
+}
+
+/// `.2` -> 2
+fn parse_precision(spec: &str) -> Option<usize> {
+    spec.trim().strip_prefix('.')?.parse().ok()
+}
+
+/// One decimal, dropping a trailing `.0` (`12.0` -> `12`, `12.34` -> `12.3`)
+fn trim_decimal(number: f64) -> String {
+    let formatted = format!("{:.1}", number);
+    match formatted.strip_suffix(".0") {
+        Some(whole) => whole.to_string(),
+        None => formatted,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn data() -> SegmentData {
+        let mut metadata = HashMap::new();
+        metadata.insert("percentage".to_string(), "42.567".to_string());
+        metadata.insert("tokens".to_string(), "12345".to_string());
+        metadata.insert("plan".to_string(), "pro".to_string());
+        SegmentData {
+            primary: "P".to_string(),
+            secondary: "S".to_string(),
+            metadata,
+        }
+    }
+
+    #[test]
+    fn renders_placeholders() {
+        assert_eq!(render("{primary} · {secondary}", &data()), "P · S");
+        assert_eq!(render("{plan} {percentage}", &data()), "pro 42.567");
+        assert_eq!(render("x{missing}y", &data()), "xy");
+    }
+
+    #[test]
+    fn applies_precision() {
+        assert_eq!(render("{percentage:.0}%", &data()), "43%");
+        assert_eq!(render("{percentage:.1}", &data()), "42.6");
+        // Precision is ignored for text values
+        assert_eq!(render("{plan:.2}", &data()), "pro");
+    }
+
+    #[test]
+    fn scales_numbers() {
+        assert_eq!(render("{tokens_k}", &data()), "12.3k");
+        assert_eq!(render("{tokens_k:.0}", &data()), "12k");
+        assert_eq!(render("{tokens_m:.2}", &data()), "0.01M");
+        assert_eq!(render("{plan_k}", &data()), "");
+    }
+
+    #[test]
+    fn escapes_braces() {
+        assert_eq!(render("{{plan}} {plan}", &data()), "{plan} pro");
+        assert_eq!(render("open {plan", &data()), "open {plan");
+    }
+}
@@ -0,0 +1,286 @@
+use crate::api::{GlmApiConfig, QuotaLimitItem};
+use crate::utils::StateFile;
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Samples older than this are dropped (longer than any rate window)
+const RETENTION_MS: i64 = 6 * 60 * 60 * 1000;
+
+/// Below this much observed history the rate is too noisy to project
+const MIN_ELAPSED_MS: i64 = 5 * 60 * 1000;
+
+/// One observed quota percentage
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QuotaSample {
+    pub at: i64,
+    pub percentage: f64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reset_at: Option<i64>,
+}
+
+/// Percentages seen for one quota limit since its last reset
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct QuotaSeries {
+    pub samples: Vec<QuotaSample>,
+}
+
+/// Where the quota is heading at the current burn rate
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Forecast {
+    /// Not enough history yet, or no usage in the window
+    Unknown,
+    /// The limit will not be reached before it resets
+    Safe { rate_per_hour: f64 },
+    /// The limit will be reached after `in_ms`
+    Exhaust { rate_per_hour: f64, in_ms: i64 },
+}
+
+impl QuotaSeries {
+    /// Append a sample, starting over when the quota was reset
+    pub fn record(&mut self, sample: QuotaSample) {
+        if let Some(last) = self.samples.last_mut() {
+            let reset = sample.percentage < last.percentage
+                || last.reset_at.is_some_and(|reset_at| reset_at <= sample.at);
+            if reset {
+                self.samples.clear();
+            } else if last.percentage == sample.percentage {
+                // Unchanged: only follow the latest reset time
+                last.reset_at = sample.reset_at;
+                return;
+            }
+        }
+        self.samples.push(sample);
+
+        let cutoff = self.samples.last().map(|s| s.at - RETENTION_MS);
+        if let Some(cutoff) = cutoff {
+            // Keep the newest sample before the cutoff as the baseline
+            let keep_from = self
+                .samples
+                .iter()
+                .rposition(|s| s.at <= cutoff)
+                .unwrap_or(0);
+            self.samples.drain(..keep_from);
+        }
+    }
+
+    /// Percentage points per hour over the trailing `window`
+    pub fn burn_rate(&self, now_ms: i64, window: Duration) -> Option<f64> {
+        let current = self.samples.last()?;
+        let window_start = now_ms - window.as_millis() as i64;
+        let baseline = self
+            .samples
+            .iter()
+            .rev()
+            .find(|s| s.at <= window_start)
+            .or_else(|| self.samples.first())?;
+
+        let elapsed = now_ms - baseline.at.max(window_start);
+        if elapsed < MIN_ELAPSED_MS {
+            return None;
+        }
+        let rise = current.percentage - baseline.percentage;
+        Some(rise.max(0.0) / (elapsed as f64 / 3_600_000.0))
+    }
+
+    /// Project the current burn rate against the limit's reset time
+    pub fn forecast(&self, now_ms: i64, window: Duration) -> Forecast {
+        let (Some(rate), Some(current)) = (self.burn_rate(now_ms, window), self.samples.last())
+        else {
+            return Forecast::Unknown;
+        };
+        if rate <= 0.0 {
+            return match current.reset_at {
+                Some(_) => Forecast::Safe { rate_per_hour: 0.0 },
+                None => Forecast::Unknown,
+            };
+        }
+
+        let remaining = (100.0 - current.percentage).max(0.0);
+        let in_ms = (remaining / rate * 3_600_000.0) as i64;
+        match current.reset_at {
+            Some(reset_at) if now_ms + in_ms >= reset_at => Forecast::Safe {
+                rate_per_hour: rate,
+            },
+            _ => Forecast::Exhaust {
+                rate_per_hour: rate,
+                in_ms,
+            },
+        }
+    }
+}
+
+/// Record the limit's current percentage and forecast its exhaustion
+///
+/// The series is kept in `~/.claude/glmcode/quota_series/`, one file per
+/// endpoint and window.
+pub fn track_quota(config: &GlmApiConfig, limit: &QuotaLimitItem, window: Duration) -> Forecast {
+    let now_ms = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or_default();
+
+    let window_name = limit
+        .window_short()
+        .unwrap_or_else(|| "default".to_string());
+    let Some(state) = StateFile::new(&format!(
+        "quota_series/{}.json",
+        config.cache_key(&window_name)
+    )) else {
+        return Forecast::Unknown;
+    };
+
+    let forecast = track(&state, limit, window, now_ms);
+    crate::trace::record(format!("quota forecast {:?}", forecast));
+    forecast
+}
+
+fn track(state: &StateFile, limit: &QuotaLimitItem, window: Duration, now_ms: i64) -> Forecast {
+    let mut series: QuotaSeries = state.load().unwrap_or_default();
+
+    let count = series.samples.len();
+    let last = series.samples.last().map(|s| (s.percentage, s.reset_at));
+    series.record(QuotaSample {
+        at: now_ms,
+        percentage: limit.percentage,
+        reset_at: limit.next_reset_time,
+    });
+    let changed = series.samples.len() != count
+        || series.samples.last().map(|s| (s.percentage, s.reset_at)) != last;
+    if changed {
+        if let Err(e) = state.store(&series) {
+            crate::trace::record(format!("failed to write {}: {}", state.path().display(), e));
+        }
+    }
+
+    series.forecast(now_ms, window)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HOUR_MS: i64 = 3_600_000;
+
+    fn sample(at: i64, percentage: f64, reset_at: Option<i64>) -> QuotaSample {
+        QuotaSample {
+            at,
+            percentage,
+            reset_at,
+        }
+    }
+
+    fn series(samples: &[(i64, f64)], reset_at: Option<i64>) -> QuotaSeries {
+        let mut series = QuotaSeries::default();
+        for &(at, percentage) in samples {
+            series.record(sample(at, percentage, reset_at));
+        }
+        series
+    }
+
+    #[test]
+    fn unchanged_percentage_is_not_appended() {
+        let mut series = series(&[(0, 10.0)], Some(5 * HOUR_MS));
+        series.record(sample(60_000, 10.0, Some(6 * HOUR_MS)));
+        assert_eq!(series.samples.len(), 1);
+        assert_eq!(series.samples[0].reset_at, Some(6 * HOUR_MS));
+    }
+
+    #[test]
+    fn lower_percentage_starts_over() {
+        let mut series = series(&[(0, 10.0), (HOUR_MS, 40.0)], None);
+        series.record(sample(2 * HOUR_MS, 5.0, None));
+        assert_eq!(series.samples.len(), 1);
+        assert_eq!(series.samples[0].percentage, 5.0);
+    }
+
+    #[test]
+    fn passed_reset_time_starts_over() {
+        let mut series = series(&[(0, 10.0)], Some(HOUR_MS));
+        series.record(sample(2 * HOUR_MS, 20.0, Some(6 * HOUR_MS)));
+        assert_eq!(series.samples.len(), 1);
+        assert_eq!(series.samples[0].at, 2 * HOUR_MS);
+    }
+
+    #[test]
+    fn burn_rate_over_window() {
+        let series = series(&[(0, 10.0), (HOUR_MS, 30.0)], None);
+        let rate = series.burn_rate(HOUR_MS, Duration::from_secs(2 * 3600));
+        assert_eq!(rate, Some(20.0));
+        // A value holds until the next sample, so a shorter window sees a steeper rise
+        let rate = series.burn_rate(HOUR_MS, Duration::from_secs(1800));
+        assert_eq!(rate, Some(40.0));
+    }
+
+    #[test]
+    fn burn_rate_needs_enough_history() {
+        let series = series(&[(0, 10.0), (60_000, 12.0)], None);
+        assert_eq!(series.burn_rate(60_000, Duration::from_secs(3600)), None);
+        assert_eq!(
+            series.forecast(60_000, Duration::from_secs(3600)),
+            Forecast::Unknown
+        );
+    }
+
+    #[test]
+    fn forecast_against_reset_time() {
+        let window = Duration::from_secs(2 * 3600);
+        let exhausting = series(&[(0, 10.0), (HOUR_MS, 30.0)], Some(10 * HOUR_MS));
+        assert_eq!(
+            exhausting.forecast(HOUR_MS, window),
+            Forecast::Exhaust {
+                rate_per_hour: 20.0,
+                in_ms: 3 * HOUR_MS + HOUR_MS / 2,
+            }
+        );
+
+        let safe = series(&[(0, 10.0), (HOUR_MS, 30.0)], Some(2 * HOUR_MS));
+        assert_eq!(
+            safe.forecast(HOUR_MS, window),
+            Forecast::Safe {
+                rate_per_hour: 20.0
+            }
+        );
+
+        let idle = series(&[(0, 10.0)], Some(10 * HOUR_MS));
+        assert_eq!(
+            idle.forecast(HOUR_MS, window),
+            Forecast::Safe { rate_per_hour: 0.0 }
+        );
+    }
+
+    #[test]
+    fn track_stores_only_changed_series() {
+        let dir =
+            std::env::temp_dir().join(format!("glmcode-forecast-test-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let state = StateFile::at(dir.join("quota_series").join("tokens.json"));
+        let limit = |percentage: f64| -> QuotaLimitItem {
+            serde_json::from_value(serde_json::json!({
+                "type": "TOKENS_LIMIT",
+                "percentage": percentage,
+                "nextResetTime": 10 * HOUR_MS
+            }))
+            .unwrap()
+        };
+        let window = Duration::from_secs(2 * 3600);
+
+        assert_eq!(track(&state, &limit(10.0), window, 0), Forecast::Unknown);
+        let stored = std::fs::read_to_string(state.path()).unwrap();
+        assert_eq!(
+            track(&state, &limit(10.0), window, HOUR_MS / 2),
+            Forecast::Safe { rate_per_hour: 0.0 }
+        );
+        assert_eq!(std::fs::read_to_string(state.path()).unwrap(), stored);
+
+        assert_eq!(
+            track(&state, &limit(30.0), window, HOUR_MS),
+            Forecast::Exhaust {
+                rate_per_hour: 20.0,
+                in_ms: 3 * HOUR_MS + HOUR_MS / 2,
+            }
+        );
+        let series: QuotaSeries = state.load().unwrap();
+        assert_eq!(series.samples.len(), 2);
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
@@ -0,0 +1,555 @@
+use crate::cli::{HistoryFormat, HistoryGroup};
+use crate::config::{Config, HistoryConfig, InputData, SegmentConfig, SegmentId};
+use crate::core::segments::SegmentData;
+use crate::report::{format_number, print_rows};
+use crate::utils::StateFile;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fs::{self, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+
+/// The last snapshot a session wrote
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct LastWrite {
+    at: String,
+    counters: Counters,
+}
+
+/// Session counters that only move when something was spent or changed
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct Counters {
+    cost_usd: Option<f64>,
+    api_duration_ms: Option<u64>,
+    lines_added: Option<u64>,
+    lines_removed: Option<u64>,
+}
+
+/// One snapshot taken while rendering
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct HistoryRecord {
+    /// RFC 3339 timestamp
+    pub at: String,
+    pub session: String,
+    pub project: String,
+    pub model: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub context_percentage: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub context_tokens: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub glm_tokens_percentage: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub glm_time_percentage: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub five_hour_utilization: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub seven_day_utilization: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cost_usd: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub duration_ms: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub api_duration_ms: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lines_added: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lines_removed: Option<u64>,
+}
+
+impl HistoryRecord {
+    /// Build a snapshot from the render input and the collected segments
+    pub fn capture(input: &InputData, segments: &[(SegmentConfig, SegmentData)]) -> Self {
+        let metric = |id: SegmentId, key: &str| -> Option<f64> {
+            segments
+                .iter()
+                .filter(|(config, data)| config.id == id && !data.metadata.contains_key("fallback"))
+                .find_map(|(_, data)| data.metadata.get(key)?.parse().ok())
+        };
+        let cost = input.cost.as_ref();
+
+        Self {
+            at: chrono::Utc::now().to_rfc3339(),
+            session: session_id(&input.transcript_path),
+            project: input.workspace.current_dir.clone(),
+            model: input.model.id.clone(),
+            context_percentage: metric(SegmentId::ContextWindow, "percentage"),
+            context_tokens: metric(SegmentId::ContextWindow, "tokens").map(|v| v as u64),
+            glm_tokens_percentage: metric(SegmentId::GlmUsage, "tokens_percentage"),
+            glm_time_percentage: metric(SegmentId::GlmUsage, "time_percentage"),
+            five_hour_utilization: metric(SegmentId::Usage, "five_hour_utilization"),
+            seven_day_utilization: metric(SegmentId::Usage, "seven_day_utilization"),
+            cost_usd: cost.and_then(|c| c.total_cost_usd),
+            duration_ms: cost.and_then(|c| c.total_duration_ms),
+            api_duration_ms: cost.and_then(|c| c.total_api_duration_ms),
+            lines_added: cost.and_then(|c| c.total_lines_added).map(u64::from),
+            lines_removed: cost.and_then(|c| c.total_lines_removed).map(u64::from),
+        }
+    }
+
+    fn counters(&self) -> Counters {
+        Counters {
+            cost_usd: self.cost_usd,
+            api_duration_ms: self.api_duration_ms,
+            lines_added: self.lines_added,
+            lines_removed: self.lines_removed,
+        }
+    }
+
+    fn local_time(&self) -> Option<chrono::DateTime<chrono::Local>> {
+        chrono::DateTime::parse_from_rfc3339(&self.at)
+            .ok()
+            .map(|time| time.with_timezone(&chrono::Local))
+    }
+}
+
+/// Session id: the transcript file name without its extension
+fn session_id(transcript_path: &str) -> String {
+    Path::new(transcript_path)
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+/// History file location (`[history] path`, default ~/.claude/glmcode/history.jsonl)
+pub fn history_path(config: &HistoryConfig) -> Option<PathBuf> {
+    match &config.path {
+        Some(path) => match path.strip_prefix("~") {
+            Ok(rest) => dirs::home_dir().map(|home| home.join(rest)),
+            Err(_) => Some(path.clone()),
+        },
+        None => {
+            dirs::home_dir().map(|home| home.join(".claude").join("glmcode").join("history.jsonl"))
+        }
+    }
+}
+
+/// Append a snapshot when the session's counters moved, or otherwise once per
+/// `[history] interval`, so the last increment of a session is never dropped
+pub fn record(config: &Config, input: &InputData, segments: &[(SegmentConfig, SegmentData)]) {
+    if !config.history.enabled {
+        return;
+    }
+    let Some(path) = history_path(&config.history) else {
+        return;
+    };
+
+    let snapshot = HistoryRecord::capture(input, segments);
+    let now = chrono::Utc::now();
+    let interval = chrono::Duration::seconds(config.history.interval as i64);
+
+    // What each session last wrote, kept next to the history file
+    let last_state = StateFile::at(path.with_extension("state.json"));
+    let mut last_writes: HashMap<String, LastWrite> = last_state.load().unwrap_or_default();
+    let written_at = |last: &LastWrite| chrono::DateTime::parse_from_rfc3339(&last.at).ok();
+    if let Some(last) = last_writes.get(&snapshot.session) {
+        let recent = written_at(last).is_some_and(|at| now.signed_duration_since(at) < interval);
+        if recent && last.counters == snapshot.counters() {
+            return;
+        }
+    }
+
+    if let Err(e) = append(&path, &snapshot) {
+        crate::trace::record(format!("failed to append {}: {}", path.display(), e));
+        return;
+    }
+    crate::trace::record(format!("history snapshot appended to {}", path.display()));
+
+    // Forget sessions idle for a day
+    last_writes.retain(|_, last| {
+        written_at(last).is_some_and(|at| now.signed_duration_since(at) < chrono::Duration::days(1))
+    });
+    let counters = snapshot.counters();
+    last_writes.insert(
+        snapshot.session,
+        LastWrite {
+            at: snapshot.at,
+            counters,
+        },
+    );
+    if let Err(e) = last_state.store(&last_writes) {
+        crate::trace::record(format!(
+            "failed to write {}: {}",
+            last_state.path().display(),
+            e
+        ));
+    }
+}
+
+fn append(path: &Path, snapshot: &HistoryRecord) -> std::io::Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let mut line = serde_json::to_string(snapshot)?;
+    line.push('\n');
+    // A single write keeps concurrent appends from interleaving
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)?
+        .write_all(line.as_bytes())
+}
+
+/// Every readable record, oldest first
+pub fn load(path: &Path) -> std::io::Result<Vec<HistoryRecord>> {
+    let file = fs::File::open(path)?;
+    let mut records: Vec<HistoryRecord> = BufReader::new(file)
+        .lines()
+        .map_while(Result::ok)
+        .filter_map(|line| serde_json::from_str(&line).ok())
+        .collect();
+    records.sort_by(|a, b| a.at.cmp(&b.at));
+    Ok(records)
+}
+
+/// Aggregated history for one day, project or session
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct HistoryRow {
+    pub key: String,
+    pub sessions: usize,
+    pub snapshots: usize,
+    pub cost_usd: f64,
+    pub duration_ms: u64,
+    pub api_duration_ms: u64,
+    pub lines_added: u64,
+    pub lines_removed: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_context_percentage: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_glm_tokens_percentage: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_five_hour_utilization: Option<f64>,
+}
+
+/// Session counters are cumulative; what a snapshot adds is its rise over the
+/// previous snapshot of the same session (a drop means the counter restarted)
+struct Increments {
+    cost_usd: f64,
+    duration_ms: u64,
+    api_duration_ms: u64,
+    lines_added: u64,
+    lines_removed: u64,
+}
+
+fn increments(records: &[HistoryRecord]) -> Vec<Increments> {
+    fn rise_f64(current: Option<f64>, previous: Option<f64>) -> f64 {
+        match (current, previous) {
+            (Some(current), Some(previous)) if current >= previous => current - previous,
+            (Some(current), _) => current,
+            (None, _) => 0.0,
+        }
+    }
+    fn rise_u64(current: Option<u64>, previous: Option<u64>) -> u64 {
+        match (current, previous) {
+            (Some(current), Some(previous)) if current >= previous => current - previous,
+            (Some(current), _) => current,
+            (None, _) => 0,
+        }
+    }
+
+    let mut previous: HashMap<&str, &HistoryRecord> = HashMap::new();
+    records
+        .iter()
+        .map(|record| {
+            let last = previous.insert(&record.session, record);
+            let prev = |field: fn(&HistoryRecord) -> Option<u64>| last.and_then(field);
+            Increments {
+                cost_usd: rise_f64(record.cost_usd, last.and_then(|r| r.cost_usd)),
+                duration_ms: rise_u64(record.duration_ms, prev(|r| r.duration_ms)),
+                api_duration_ms: rise_u64(record.api_duration_ms, prev(|r| r.api_duration_ms)),
+                lines_added: rise_u64(record.lines_added, prev(|r| r.lines_added)),
+                lines_removed: rise_u64(record.lines_removed, prev(|r| r.lines_removed)),
+            }
+        })
+        .collect()
+}
+
+/// Group records from the last `days` days (0 keeps everything)
+pub fn aggregate(records: &[HistoryRecord], group: HistoryGroup, days: u32) -> Vec<HistoryRow> {
+    let since = (days > 0).then(|| chrono::Local::now() - chrono::Duration::days(days as i64));
+    let max = |a: Option<f64>, b: Option<f64>| match (a, b) {
+        (Some(a), Some(b)) => Some(a.max(b)),
+        (a, b) => a.or(b),
+    };
+
+    let mut rows: BTreeMap<String, (HistoryRow, BTreeSet<&str>)> = BTreeMap::new();
+    for (record, increment) in records.iter().zip(increments(records)) {
+        let Some(time) = record.local_time() else {
+            continue;
+        };
+        if since.is_some_and(|since| time < since) {
+            continue;
+        }
+
+        let key = match group {
+            HistoryGroup::Day => time.format("%Y-%m-%d").to_string(),
+            HistoryGroup::Project => record.project.clone(),
+            HistoryGroup::Session => record.session.clone(),
+        };
+        let (row, sessions) = rows.entry(key.clone()).or_insert_with(|| {
+            (
+                HistoryRow {
+                    key,
+                    ..Default::default()
+                },
+                BTreeSet::new(),
+            )
+        });
+        sessions.insert(&record.session);
+        row.snapshots += 1;
+        row.cost_usd += increment.cost_usd;
+        row.duration_ms += increment.duration_ms;
+        row.api_duration_ms += increment.api_duration_ms;
+        row.lines_added += increment.lines_added;
+        row.lines_removed += increment.lines_removed;
+        row.max_context_percentage = max(row.max_context_percentage, record.context_percentage);
+        row.max_glm_tokens_percentage =
+            max(row.max_glm_tokens_percentage, record.glm_tokens_percentage);
+        row.max_five_hour_utilization =
+            max(row.max_five_hour_utilization, record.five_hour_utilization);
+    }
+
+    rows.into_values()
+        .map(|(mut row, sessions)| {
+            row.sessions = sessions.len();
+            row
+        })
+        .collect()
+}
+
+const HEADERS: [&str; 9] = [
+    "KEY",
+    "SESSIONS",
+    "COST",
+    "DURATION",
+    "API TIME",
+    "LINES +/-",
+    "MAX CONTEXT",
+    "MAX GLM TOKENS",
+    "MAX 5H",
+];
+
+/// Run `glmcode history`
+pub fn run_history(
+    group: HistoryGroup,
+    days: u32,
+    format: HistoryFormat,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let config = Config::load().unwrap_or_else(|_| Config::default());
+    let path = history_path(&config.history).ok_or("could not determine the home directory")?;
+    let records = match load(&path) {
+        Ok(records) => records,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+        Err(e) => return Err(format!("failed to read {}: {}", path.display(), e).into()),
+    };
+    let rows = aggregate(&records, group, days);
+
+    match format {
+        HistoryFormat::Json => println!("{}", serde_json::to_string_pretty(&rows)?),
+        HistoryFormat::Csv => print_csv(&rows),
+        HistoryFormat::Table if rows.is_empty() => {
+            println!("No history recorded yet ({})", path.display());
+        }
+        HistoryFormat::Table => {
+            let percent = |value: Option<f64>| {
+                value
+                    .map(|v| format!("{:.1}%", v))
+                    .unwrap_or_else(|| "-".to_string())
+            };
+            let table = rows
+                .iter()
+                .map(|row| {
+                    vec![
+                        row.key.clone(),
+                        row.sessions.to_string(),
+                        format!("${:.2}", row.cost_usd),
+                        format_duration(row.duration_ms),
+                        format_duration(row.api_duration_ms),
+                        format!(
+                            "+{} -{}",
+                            format_number(row.lines_added as f64),
+                            format_number(row.lines_removed as f64)
+                        ),
+                        percent(row.max_context_percentage),
+                        percent(row.max_glm_tokens_percentage),
+                        percent(row.max_five_hour_utilization),
+                    ]
+                })
+                .collect();
+            print_rows(&HEADERS, table);
+        }
+    }
+    Ok(())
+}
+
+fn print_csv(rows: &[HistoryRow]) {
+    let optional = |value: Option<f64>| value.map(|v| format!("{:.1}", v)).unwrap_or_default();
+    println!(
+        "key,sessions,snapshots,cost_usd,duration_ms,api_duration_ms,lines_added,lines_removed,\
+         max_context_percentage,max_glm_tokens_percentage,max_five_hour_utilization"
+    );
+    for row in rows {
+        println!(
+            "{},{},{},{:.4},{},{},{},{},{},{},{}",
+            csv_field(&row.key),
+            row.sessions,
+            row.snapshots,
+            row.cost_usd,
+            row.duration_ms,
+            row.api_duration_ms,
+            row.lines_added,
+            row.lines_removed,
+            optional(row.max_context_percentage),
+            optional(row.max_glm_tokens_percentage),
+            optional(row.max_five_hour_utilization),
+        );
+    }
+}
+
+/// Quote a CSV field when it contains a separator, quote or newline
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// "2h 05m", "12m"
+fn format_duration(ms: u64) -> String {
+    let minutes = ms / 60_000;
+    if minutes >= 60 {
+        format!("{}h {:02}m", minutes / 60, minutes % 60)
+    } else {
+        format!("{}m", minutes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snapshot(
+        at: &str,
+        session: &str,
+        project: &str,
+        cost_usd: f64,
+        lines: u64,
+    ) -> HistoryRecord {
+        HistoryRecord {
+            at: at.to_string(),
+            session: session.to_string(),
+            project: project.to_string(),
+            cost_usd: Some(cost_usd),
+            lines_added: Some(lines),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn increments_follow_each_session() {
+        let records = [
+            snapshot("2026-01-01T10:00:00Z", "a", "p", 1.0, 10),
+            snapshot("2026-01-01T10:05:00Z", "b", "p", 0.5, 5),
+            snapshot("2026-01-01T10:10:00Z", "a", "p", 1.5, 30),
+            snapshot("2026-01-01T10:15:00Z", "b", "p", 2.0, 5),
+        ];
+        let rows = aggregate(&records, HistoryGroup::Session, 0);
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0].key, "a");
+        assert_eq!(rows[0].snapshots, 2);
+        assert_eq!(rows[0].cost_usd, 1.5);
+        assert_eq!(rows[0].lines_added, 30);
+        assert_eq!(rows[1].key, "b");
+        assert_eq!(rows[1].cost_usd, 2.0);
+        assert_eq!(rows[1].lines_added, 5);
+    }
+
+    #[test]
+    fn restarted_counter_counts_from_zero() {
+        let records = [
+            snapshot("2026-01-01T10:00:00Z", "a", "p", 3.0, 40),
+            snapshot("2026-01-01T10:05:00Z", "a", "p", 0.25, 2),
+        ];
+        let rows = aggregate(&records, HistoryGroup::Session, 0);
+        assert_eq!(rows[0].cost_usd, 3.25);
+        assert_eq!(rows[0].lines_added, 42);
+    }
+
+    #[test]
+    fn project_rows_count_sessions_and_peaks() {
+        let mut records = vec![
+            snapshot("2026-01-01T10:00:00Z", "a", "p", 1.0, 0),
+            snapshot("2026-01-01T10:05:00Z", "b", "p", 1.0, 0),
+            snapshot("2026-01-01T10:10:00Z", "c", "q", 1.0, 0),
+        ];
+        records[0].context_percentage = Some(40.0);
+        records[1].context_percentage = Some(65.0);
+        let rows = aggregate(&records, HistoryGroup::Project, 0);
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0].sessions, 2);
+        assert_eq!(rows[0].cost_usd, 2.0);
+        assert_eq!(rows[0].max_context_percentage, Some(65.0));
+        assert_eq!(rows[1].max_context_percentage, None);
+    }
+
+    #[test]
+    fn old_records_are_left_out() {
+        let records = [snapshot("2020-01-01T10:00:00Z", "a", "p", 1.0, 0)];
+        assert!(aggregate(&records, HistoryGroup::Day, 7).is_empty());
+        assert_eq!(aggregate(&records, HistoryGroup::Day, 0).len(), 1);
+    }
+
+    fn input(cost_usd: f64, duration_ms: u64) -> InputData {
+        serde_json::from_value(serde_json::json!({
+            "model": { "id": "glm-4.6", "display_name": "GLM-4.6" },
+            "workspace": { "current_dir": "/tmp/project" },
+            "transcript_path": "/tmp/project/session-a.jsonl",
+            "cost": {
+                "total_cost_usd": cost_usd,
+                "total_duration_ms": duration_ms,
+                "total_api_duration_ms": 100,
+                "total_lines_added": 1,
+                "total_lines_removed": 0
+            }
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn record_writes_when_counters_move_and_keeps_state_beside_the_file() {
+        let dir = std::env::temp_dir().join(format!("glmcode-history-test-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let path = dir.join("history.jsonl");
+        let mut config = Config {
+            history: HistoryConfig {
+                enabled: true,
+                interval: 3600,
+                path: Some(path.clone()),
+            },
+            ..Config::default()
+        };
+        let lines = || {
+            fs::read_to_string(&path)
+                .unwrap_or_default()
+                .lines()
+                .count()
+        };
+
+        record(&config, &input(1.0, 1000), &[]);
+        assert_eq!(lines(), 1);
+        assert!(dir.join("history.state.json").exists());
+
+        // Only the wall time moved: throttled by the interval
+        record(&config, &input(1.0, 2000), &[]);
+        assert_eq!(lines(), 1);
+
+        record(&config, &input(1.5, 3000), &[]);
+        assert_eq!(lines(), 2);
+
+        config.history.enabled = false;
+        record(&config, &input(2.0, 4000), &[]);
+        assert_eq!(lines(), 2);
+        let _ = fs::remove_dir_all(dir);
+    }
+}
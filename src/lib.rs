@@ -1,7 +1,21 @@
+/// Threshold alerts on segment metadata
+pub mod alerts;
 pub mod api;
 pub mod cli;
 pub mod config;
 pub mod core;
+/// Burn-rate forecasting for the GLM token quota
+pub mod forecast;
+/// Local usage history and the `glmcode history` report
+pub mod history;
+/// Background refresher for network-backed segments
+pub mod refresh;
+/// `glmcode usage` report
+pub mod report;
+/// Diagnostics recorded for `--doctor` / `--trace`
+pub mod trace;
+/// Incremental reading of Claude Code transcripts
+pub mod transcript;
 pub mod ui;
 pub mod utils;
 
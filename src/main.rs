@@ -1,4 +1,4 @@
-use glmcode::cli::Cli;
+use glmcode::cli::{Cli, Command};
 use glmcode::config::{Config, InputData};
 use glmcode::core::{collect_all_segments, StatusLineGenerator};
 use std::io::{self, IsTerminal};
@@ -6,6 +6,25 @@ use std::io::{self, IsTerminal};
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     let cli = Cli::parse_args();
 
+    if let Some(command) = &cli.command {
+        match command {
+            Command::Usage(args) => {
+                let config = Config::load().unwrap_or_else(|_| Config::default());
+                if let Err(e) = glmcode::report::run_usage(&config.glm, args.hours, args.json) {
+                    eprintln!("Error: {}", e);
+                    std::process::exit(1);
+                }
+            }
+            Command::History(args) => {
+                if let Err(e) = glmcode::history::run_history(args.by, args.days, args.format) {
+                    eprintln!("Error: {}", e);
+                    std::process::exit(1);
+                }
+            }
+        }
+        return Ok(());
+    }
+
     // Handle configuration commands
     if cli.init {
         use glmcode::config::InitResult;
@@ -37,6 +56,16 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         return Ok(());
     }
 
+    if cli.refresh {
+        let config = Config::load().unwrap_or_else(|_| Config::default());
+        glmcode::refresh::run(&config);
+        return Ok(());
+    }
+
+    if cli.daemon {
+        glmcode::refresh::run_daemon();
+    }
+
     if cli.config {
         #[cfg(feature = "tui")]
         {
@@ -96,6 +125,17 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         config = glmcode::ui::themes::ThemePresets::get_theme(&theme);
     }
 
+    if cli.doctor {
+        if io::stdin().is_terminal() {
+            eprintln!("Pipe a sample statusline input to diagnose it:");
+            eprintln!("   echo '{{...}}' | glmcode --doctor");
+            std::process::exit(1);
+        }
+        let input: InputData = serde_json::from_reader(io::stdin().lock())?;
+        glmcode::trace::run_doctor(&config, &input);
+        return Ok(());
+    }
+
     // Check if stdin has data
     if io::stdin().is_terminal() {
         // No input data available, show main menu
@@ -132,7 +172,9 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     let input: InputData = serde_json::from_reader(stdin.lock())?;
 
     // Collect segment data
-    let segments_data = collect_all_segments(&config, &input);
+    let mut segments_data = collect_all_segments(&config, &input);
+    glmcode::alerts::apply(&config, &mut segments_data);
+    glmcode::history::record(&config, &input, &segments_data);
 
     // Render statusline
     let generator = StatusLineGenerator::new(config);
@@ -0,0 +1,110 @@
+use crate::api::ApiCache;
+use crate::config::Config;
+use crate::core::segments;
+use crate::utils::FileLock;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+/// Set once a refresh has been requested, so one render spawns at most one process
+static REFRESH_REQUESTED: AtomicBool = AtomicBool::new(false);
+
+/// Interval between refresh passes in daemon mode
+pub const DAEMON_INTERVAL: Duration = Duration::from_secs(30);
+
+/// A daemon heartbeat younger than this means renders leave refreshing to it
+const DAEMON_HEARTBEAT_AGE: Duration = Duration::from_secs(DAEMON_INTERVAL.as_secs() * 2);
+
+fn lock_path() -> Option<PathBuf> {
+    ApiCache::default_dir().map(|dir| dir.join("refresh.lock"))
+}
+
+fn heartbeat_path() -> Option<PathBuf> {
+    ApiCache::default_dir().map(|dir| dir.join("daemon.heartbeat"))
+}
+
+/// Check whether a `--daemon` process has completed a pass recently
+fn daemon_running() -> bool {
+    heartbeat_path()
+        .and_then(|path| std::fs::metadata(path).ok())
+        .and_then(|m| m.modified().ok())
+        .and_then(|modified| modified.elapsed().ok())
+        .is_some_and(|age| age < DAEMON_HEARTBEAT_AGE)
+}
+
+/// Spawn a detached refresher unless one is already running
+pub fn request_refresh() {
+    if REFRESH_REQUESTED.swap(true, Ordering::SeqCst) {
+        return;
+    }
+
+    // current_exe is the test harness under `cargo test`
+    if cfg!(test) {
+        return;
+    }
+
+    if lock_path().map(FileLock::is_held).unwrap_or(false) || daemon_running() {
+        return;
+    }
+
+    let _ = spawn_detached();
+}
+
+fn spawn_detached() -> std::io::Result<()> {
+    let exe = std::env::current_exe()?;
+
+    let mut command = Command::new(exe);
+    command
+        .arg("--refresh")
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null());
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+        // New process group so the refresher outlives the statusline render
+        command.process_group(0);
+    }
+
+    #[cfg(windows)]
+    {
+        use std::os::windows::process::CommandExt;
+        const DETACHED_PROCESS: u32 = 0x0000_0008;
+        const CREATE_NEW_PROCESS_GROUP: u32 = 0x0000_0200;
+        command.creation_flags(DETACHED_PROCESS | CREATE_NEW_PROCESS_GROUP);
+    }
+
+    command.spawn().map(|_| ())
+}
+
+/// Run a single refresh pass for every enabled network-backed segment
+///
+/// Returns without doing anything if another refresher holds the lock.
+pub fn run(config: &Config) {
+    // The refresher itself must never spawn another refresher
+    REFRESH_REQUESTED.store(true, Ordering::SeqCst);
+
+    let Some(lock) = lock_path().and_then(FileLock::try_acquire) else {
+        return;
+    };
+
+    for segment_config in config.segments.iter().filter(|s| s.enabled) {
+        // Each fetch is bounded by its timeout, not the whole pass
+        lock.touch();
+        let _ = segments::refresh(segment_config, &config.glm);
+    }
+}
+
+/// Keep refreshing in the foreground, reloading config before every pass
+pub fn run_daemon() -> ! {
+    loop {
+        let config = Config::load().unwrap_or_else(|_| Config::default());
+        run(&config);
+        if let Some(path) = heartbeat_path() {
+            let _ = std::fs::write(path, std::process::id().to_string());
+        }
+        std::thread::sleep(DAEMON_INTERVAL);
+    }
+}
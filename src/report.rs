@@ -0,0 +1,261 @@
+use crate::api::{
+    self, format_reset_time, GlmApiConfig, GlmApiError, GlmModelUsage, GlmPlan, GlmPlatform,
+    GlmQuotaLimit, GlmToolUsage, PlanSource,
+};
+use crate::config::GlmConfig;
+use serde::Serialize;
+
+/// A failed section of the report
+#[derive(Debug, Serialize)]
+pub struct SectionError {
+    pub section: &'static str,
+    pub error: GlmApiError,
+}
+
+/// Everything `glmcode usage` prints
+#[derive(Debug, Serialize)]
+pub struct UsageReport {
+    pub platform: GlmPlatform,
+    pub monitor_base: String,
+    pub plan: GlmPlan,
+    pub plan_source: PlanSource,
+    pub window_hours: i64,
+    pub quota: Option<GlmQuotaLimit>,
+    pub model_usage: Option<GlmModelUsage>,
+    pub tool_usage: Option<GlmToolUsage>,
+    pub errors: Vec<SectionError>,
+}
+
+impl UsageReport {
+    /// Query every monitor endpoint; failures are collected per section
+    pub fn fetch(config: &GlmApiConfig, hours: i64) -> Self {
+        let mut errors = Vec::new();
+
+        let quota = collect_section(&mut errors, "quota", api::fetch_glm_quota_limit(config));
+        let (plan, plan_source) = quota
+            .as_ref()
+            .map(|quota| quota.detect_plan(&config.plan_thresholds))
+            .unwrap_or((GlmPlan::Unknown, PlanSource::Unknown));
+        let model_usage = collect_section(
+            &mut errors,
+            "model_usage",
+            api::fetch_glm_model_usage(config, hours),
+        );
+        let tool_usage = collect_section(
+            &mut errors,
+            "tool_usage",
+            api::fetch_glm_tool_usage(config, hours),
+        );
+
+        Self {
+            platform: config.platform(),
+            monitor_base: config.monitor_base(),
+            plan,
+            plan_source,
+            window_hours: hours,
+            quota,
+            model_usage,
+            tool_usage,
+            errors,
+        }
+    }
+
+    /// Print the report as aligned tables
+    pub fn print_table(&self) {
+        let platform = match self.platform {
+            GlmPlatform::ZAI => "Z.ai",
+            GlmPlatform::ZHIPU => "ZHIPU",
+        };
+        println!("Platform  {} ({})", platform, self.monitor_base);
+        println!(
+            "Plan      {} ({})",
+            self.plan.display_name(),
+            self.plan_source.name()
+        );
+
+        if let Some(quota) = &self.quota {
+            let rows = quota
+                .limits
+                .iter()
+                .map(|limit| {
+                    vec![
+                        limit.limit_type.clone(),
+                        limit.window_label().unwrap_or_else(|| "-".to_string()),
+                        format!("{:.1}%", limit.percentage),
+                        format_optional(limit.current_value),
+                        format_optional(limit.remaining_value()),
+                        format_optional(limit.usage),
+                        limit
+                            .next_reset_time
+                            .map(format_reset_at)
+                            .unwrap_or_else(|| "-".to_string()),
+                    ]
+                })
+                .collect();
+            println!();
+            println!("Quota");
+            print_rows(
+                &[
+                    "TYPE",
+                    "WINDOW",
+                    "USED",
+                    "CURRENT",
+                    "REMAINING",
+                    "LIMIT",
+                    "RESETS",
+                ],
+                rows,
+            );
+        }
+
+        if let Some(usage) = &self.model_usage {
+            let mut rows: Vec<Vec<String>> = usage
+                .models
+                .iter()
+                .map(|m| {
+                    vec![
+                        m.model.clone(),
+                        format_number(m.tokens),
+                        format_number(m.calls),
+                    ]
+                })
+                .collect();
+            rows.push(vec![
+                "TOTAL".to_string(),
+                format_number(usage.total_tokens),
+                format_number(usage.total_calls),
+            ]);
+            println!();
+            println!("Model usage (last {}h)", self.window_hours);
+            print_rows(&["MODEL", "TOKENS", "CALLS"], rows);
+        }
+
+        if let Some(usage) = &self.tool_usage {
+            let rows = usage
+                .tools
+                .iter()
+                .map(|t| vec![t.tool.clone(), format_number(t.count)])
+                .collect();
+            println!();
+            println!("Tool usage (last {}h)", self.window_hours);
+            print_rows(&["TOOL", "CALLS"], rows);
+        }
+
+        if !self.errors.is_empty() {
+            println!();
+            for error in &self.errors {
+                println!("✗ {}: {}", error.section, error.error);
+            }
+        }
+    }
+}
+
+/// Run `glmcode usage`
+pub fn run_usage(
+    glm: &GlmConfig,
+    hours: i64,
+    json: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let config = api::resolve_glm_api_config(glm).ok_or(GlmApiError::NotConfigured)?;
+    let report = UsageReport::fetch(&config, hours.max(1));
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    } else {
+        report.print_table();
+    }
+
+    // Only fail when nothing could be fetched at all
+    if report.quota.is_none() && report.model_usage.is_none() && report.tool_usage.is_none() {
+        if let Some(error) = report.errors.into_iter().next() {
+            return Err(error.error.into());
+        }
+    }
+    Ok(())
+}
+
+fn collect_section<T>(
+    errors: &mut Vec<SectionError>,
+    section: &'static str,
+    result: Result<T, GlmApiError>,
+) -> Option<T> {
+    result
+        .map_err(|error| errors.push(SectionError { section, error }))
+        .ok()
+}
+
+pub(crate) fn print_rows(headers: &[&str], rows: Vec<Vec<String>>) {
+    let mut widths: Vec<usize> = headers.iter().map(|h| h.chars().count()).collect();
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+
+    let format_row = |cells: Vec<String>| {
+        cells
+            .iter()
+            .zip(&widths)
+            .map(|(cell, width)| format!("{:<width$}", cell, width = *width))
+            .collect::<Vec<_>>()
+            .join("  ")
+            .trim_end()
+            .to_string()
+    };
+
+    println!(
+        "  {}",
+        format_row(headers.iter().map(|h| h.to_string()).collect())
+    );
+    for row in rows {
+        println!("  {}", format_row(row));
+    }
+}
+
+fn format_optional(value: Option<f64>) -> String {
+    value.map(format_number).unwrap_or_else(|| "-".to_string())
+}
+
+/// Format a count with thousands separators, e.g. 12,345,678
+pub(crate) fn format_number(value: f64) -> String {
+    let digits = format!("{:.0}", value.abs());
+    let mut grouped = String::new();
+    for (i, digit) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i) % 3 == 0 {
+            grouped.push(',');
+        }
+        grouped.push(digit);
+    }
+    if value < 0.0 {
+        format!("-{}", grouped)
+    } else {
+        grouped
+    }
+}
+
+/// "in 3h 40m (2026-01-01 12:00)"
+fn format_reset_at(timestamp_ms: i64) -> String {
+    let local = chrono::DateTime::from_timestamp_millis(timestamp_ms)
+        .map(|time| {
+            time.with_timezone(&chrono::Local)
+                .format("%Y-%m-%d %H:%M")
+                .to_string()
+        })
+        .unwrap_or_default();
+    format!("in {} ({})", format_reset_time(timestamp_ms), local)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn format_number_groups_thousands() {
+        assert_eq!(format_number(0.0), "0");
+        assert_eq!(format_number(999.0), "999");
+        assert_eq!(format_number(1000.0), "1,000");
+        assert_eq!(format_number(1234567.0), "1,234,567");
+        assert_eq!(format_number(-98765.4), "-98,765");
+        assert_eq!(format_number(1999.6), "2,000");
+    }
+}
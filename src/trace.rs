@@ -0,0 +1,186 @@
+use crate::api::{self, ApiCache};
+use crate::config::{Config, InputData, ModelConfig, SegmentId};
+use crate::core::StatusLineGenerator;
+use std::cell::RefCell;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+thread_local! {
+    static EVENTS: RefCell<Vec<String>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Turn on event recording for the whole process
+pub fn enable() {
+    ENABLED.store(true, Ordering::SeqCst);
+}
+
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// Record a diagnostic event on the current thread
+pub fn record(event: impl Into<String>) {
+    if is_enabled() {
+        EVENTS.with(|events| events.borrow_mut().push(event.into()));
+    }
+}
+
+/// Take all events recorded on the current thread
+pub fn take() -> Vec<String> {
+    EVENTS.with(|events| std::mem::take(&mut *events.borrow_mut()))
+}
+
+/// How a segment ended up in (or out of) the statusline
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SegmentOutcome {
+    Rendered,
+    /// `collect` returned None
+    Hidden,
+    /// Missed the render deadline, showing the last collected data
+    CachedFallback,
+    /// Missed the render deadline with nothing cached
+    Placeholder,
+}
+
+impl SegmentOutcome {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Rendered => "ok",
+            Self::Hidden => "hidden",
+            Self::CachedFallback => "deadline missed, cached",
+            Self::Placeholder => "deadline missed, placeholder",
+        }
+    }
+}
+
+/// Diagnostics collected for one segment during a render
+#[derive(Debug, Clone)]
+pub struct SegmentTrace {
+    pub id: SegmentId,
+    /// The segment's configured instance key, if any
+    pub key: Option<String>,
+    /// None when the segment had not finished by the deadline
+    pub duration: Option<Duration>,
+    pub outcome: SegmentOutcome,
+    pub events: Vec<String>,
+}
+
+fn describe_path(path: Option<std::path::PathBuf>) -> String {
+    match path {
+        Some(path) if path.exists() => format!("{} (found)", path.display()),
+        Some(path) => format!("{} (missing)", path.display()),
+        None => "(home directory not found)".to_string(),
+    }
+}
+
+/// `Usage`, or `GlmUsage [weekly]` for a keyed instance
+fn segment_label(id: SegmentId, key: Option<&str>) -> String {
+    match key {
+        Some(key) => format!("{:?} [{}]", id, key),
+        None => format!("{:?}", id),
+    }
+}
+
+fn format_duration(duration: Duration) -> String {
+    format!("{:.1}ms", duration.as_secs_f64() * 1000.0)
+}
+
+fn print_events(events: &[String]) {
+    for event in events {
+        println!("      · {}", event);
+    }
+}
+
+/// Run a full diagnostic render and print the report to stdout
+pub fn run_doctor(config: &Config, input: &InputData) {
+    enable();
+
+    println!("glmcode {} doctor", env!("CARGO_PKG_VERSION"));
+    println!();
+    println!("Files");
+    println!(
+        "  config        {}",
+        describe_path(Some(Config::get_config_path()))
+    );
+    for path in ModelConfig::config_paths() {
+        println!("  models        {}", describe_path(Some(path)));
+    }
+    println!(
+        "  settings      {}",
+        describe_path(api::get_claude_settings_path())
+    );
+    println!(
+        "  credentials   {}",
+        describe_path(crate::utils::credentials::get_credentials_path())
+    );
+    println!("  cache         {}", describe_path(ApiCache::default_dir()));
+    println!(
+        "  transcript    {}",
+        describe_path(Some(input.transcript_path.clone().into()))
+    );
+    println!();
+
+    // Refresh network data synchronously so request failures are visible
+    println!("Refresh");
+    api::set_force_refresh(true);
+    for segment_config in config.segments.iter().filter(|s| s.enabled) {
+        let start = Instant::now();
+        let result = crate::core::segments::refresh(segment_config, &config.glm);
+        let events = take();
+        if result.is_ok() && events.is_empty() {
+            continue;
+        }
+        let status = match &result {
+            Ok(()) => "ok".to_string(),
+            Err(e) => format!("error: {}", e),
+        };
+        println!(
+            "  {:<14} {:>9}  {}",
+            segment_label(segment_config.id, segment_config.key.as_deref()),
+            format_duration(start.elapsed()),
+            status
+        );
+        print_events(&events);
+    }
+    api::set_force_refresh(false);
+    println!();
+
+    println!("Segments (deadline {}ms)", config.render_deadline_ms);
+    let (segments_data, traces) =
+        crate::core::statusline::collect_all_segments_traced(config, input);
+    for trace in &traces {
+        let duration = trace
+            .duration
+            .map(format_duration)
+            .unwrap_or_else(|| "-".to_string());
+        println!(
+            "  {:<14} {:>9}  {}",
+            segment_label(trace.id, trace.key.as_deref()),
+            duration,
+            trace.outcome.label()
+        );
+        print_events(&trace.events);
+    }
+    println!();
+
+    // Keys available to `format` templates, `color_rules` and alerts
+    println!("Metadata");
+    for (segment_config, data) in &segments_data {
+        let mut keys: Vec<_> = data.metadata.iter().collect();
+        keys.sort();
+        println!(
+            "  {}",
+            segment_label(segment_config.id, segment_config.key.as_deref())
+        );
+        for (key, value) in keys {
+            println!("      {} = {}", key, value);
+        }
+    }
+    println!();
+
+    println!("Statusline");
+    let generator = StatusLineGenerator::new(config.clone());
+    println!("{}", generator.generate(segments_data));
+}
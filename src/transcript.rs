@@ -0,0 +1,769 @@
+use crate::api::ApiCache;
+use crate::config::{NormalizedUsage, TranscriptEntry};
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeMap, HashMap};
+use std::fs::{self, File};
+use std::hash::{Hash, Hasher};
+use std::io::{BufRead, BufReader, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Bytes read per step when scanning backwards
+const REVERSE_CHUNK: u64 = 64 * 1024;
+
+/// What the complete lines of one transcript have shown so far
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct TranscriptState {
+    inode: u64,
+    /// End of the last complete line read
+    offset: u64,
+    /// Context tokens of the last assistant message with usage
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    last_tokens: Option<u32>,
+    /// Set while the last entry is a summary
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    summary_leaf: Option<String>,
+}
+
+impl TranscriptState {
+    fn apply(&mut self, line: &str) {
+        let Some(entry) = parse_entry(line) else {
+            return;
+        };
+        if entry.r#type.as_deref() == Some("summary") {
+            if let Some(leaf_uuid) = entry.leaf_uuid {
+                self.summary_leaf = Some(leaf_uuid);
+                return;
+            }
+        }
+        self.summary_leaf = None;
+        if let Some(tokens) = assistant_tokens(&entry) {
+            self.last_tokens = Some(tokens);
+        }
+    }
+}
+
+/// Usage summed over the assistant messages of a session
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SessionTotals {
+    /// Assistant messages with usage
+    pub turns: u32,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    pub cache_read_tokens: u64,
+    pub cache_creation_tokens: u64,
+}
+
+impl SessionTotals {
+    fn add(&mut self, usage: &NormalizedUsage) {
+        self.turns += 1;
+        self.input_tokens += u64::from(usage.input_tokens);
+        self.output_tokens += u64::from(usage.output_tokens);
+        self.cache_read_tokens += u64::from(usage.cache_read_input_tokens);
+        self.cache_creation_tokens += u64::from(usage.cache_creation_input_tokens);
+    }
+
+    fn merge(&mut self, other: &SessionTotals) {
+        self.turns += other.turns;
+        self.input_tokens += other.input_tokens;
+        self.output_tokens += other.output_tokens;
+        self.cache_read_tokens += other.cache_read_tokens;
+        self.cache_creation_tokens += other.cache_creation_tokens;
+    }
+
+    /// Prompt tokens across all turns, cached or not
+    pub fn prompt_tokens(&self) -> u64 {
+        self.input_tokens + self.cache_read_tokens + self.cache_creation_tokens
+    }
+
+    /// Percentage of prompt tokens read from the cache
+    pub fn cache_hit_ratio(&self) -> Option<f64> {
+        let prompt_tokens = self.prompt_tokens();
+        (prompt_tokens > 0).then(|| self.cache_read_tokens as f64 / prompt_tokens as f64 * 100.0)
+    }
+}
+
+/// Assistant usage of one transcript by local day and model
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct FileUsage {
+    inode: u64,
+    /// End of the last complete line read
+    offset: u64,
+    /// Id of the last counted message; its content blocks repeat the usage
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    last_message_id: Option<String>,
+    /// Day ("YYYY-MM-DD", empty when unknown) -> model (empty when unknown) -> totals
+    days: BTreeMap<String, ModelUsage>,
+}
+
+impl FileUsage {
+    fn apply(&mut self, line: &str) {
+        let Some(entry) = parse_entry(line) else {
+            return;
+        };
+        if entry.r#type.as_deref() != Some("assistant") {
+            return;
+        }
+        let Some(message) = &entry.message else {
+            return;
+        };
+        let Some(raw_usage) = &message.usage else {
+            return;
+        };
+        if message.id.is_some() && message.id == self.last_message_id {
+            return;
+        }
+        self.last_message_id = message.id.clone();
+
+        let day = entry
+            .timestamp
+            .as_deref()
+            .and_then(|at| chrono::DateTime::parse_from_rfc3339(at).ok())
+            .map(|at| day_key(at.with_timezone(&chrono::Local).date_naive()))
+            .unwrap_or_default();
+        self.days
+            .entry(day)
+            .or_default()
+            .entry(message.model.clone().unwrap_or_default())
+            .or_default()
+            .add(&raw_usage.clone().normalize());
+    }
+}
+
+/// Usage of a project's session files, by file name
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct ProjectUsage {
+    files: HashMap<String, FileUsage>,
+}
+
+/// Usage totals by model; the model is empty when the transcript names none
+pub type ModelUsage = HashMap<String, SessionTotals>;
+
+/// Usage of one transcript and of what surrounds it
+#[derive(Debug, Clone, Default)]
+pub struct UsageBreakdown {
+    pub session: ModelUsage,
+    /// Today, across every project
+    pub today: ModelUsage,
+    /// All session files of the transcript's project
+    pub project: ModelUsage,
+}
+
+/// Where the messages of a project's session files can be found
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct UuidIndex {
+    /// Indexed session files by name: (inode, end of the last complete line)
+    files: HashMap<String, (u64, u64)>,
+    /// Assistant messages with usage: uuid -> context tokens
+    assistant: HashMap<String, u32>,
+    /// User messages: uuid -> parent uuid
+    user_parent: HashMap<String, String>,
+}
+
+impl UuidIndex {
+    fn apply(&mut self, line: &str) {
+        let Some(entry) = parse_entry(line) else {
+            return;
+        };
+        let Some(uuid) = &entry.uuid else {
+            return;
+        };
+        match entry.r#type.as_deref() {
+            Some("assistant") => {
+                if let Some(tokens) = assistant_tokens(&entry) {
+                    self.assistant.insert(uuid.clone(), tokens);
+                }
+            }
+            Some("user") => {
+                if let Some(parent_uuid) = &entry.parent_uuid {
+                    self.user_parent.insert(uuid.clone(), parent_uuid.clone());
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Context tokens at `uuid`: its own usage, or its parent's for a user message
+    fn tokens_for(&self, uuid: &str) -> Option<u32> {
+        self.assistant.get(uuid).copied().or_else(|| {
+            let parent_uuid = self.user_parent.get(uuid)?;
+            self.assistant.get(parent_uuid).copied()
+        })
+    }
+}
+
+/// Context tokens of the session: the last assistant usage in the transcript,
+/// or in the most recent session file when the transcript does not exist yet
+pub fn context_tokens<P: AsRef<Path>>(transcript_path: P) -> Option<u32> {
+    let path = transcript_path.as_ref();
+    if path.exists() {
+        return context_tokens_in_file(path);
+    }
+
+    let project_dir = path.parent()?;
+    let mut session_files = session_files(project_dir);
+    session_files.sort_by_key(|path| {
+        fs::metadata(path)
+            .and_then(|m| m.modified())
+            .unwrap_or(std::time::UNIX_EPOCH)
+    });
+    session_files
+        .iter()
+        .rev()
+        .find_map(|session_path| context_tokens_in_file(session_path))
+}
+
+fn context_tokens_in_file(path: &Path) -> Option<u32> {
+    let state = read_state(path)?;
+    match &state.summary_leaf {
+        Some(leaf_uuid) => find_tokens_by_uuid(path.parent()?, leaf_uuid),
+        None => state.last_tokens,
+    }
+}
+
+/// Usage summed over every assistant message in the transcript
+pub fn session_totals<P: AsRef<Path>>(transcript_path: P) -> Option<SessionTotals> {
+    let path = transcript_path.as_ref();
+    let name = file_name(path)?;
+    let usage = update_usage(path.parent()?, |file| file == name);
+    let file_usage = usage.files.get(name)?;
+    let mut totals = SessionTotals::default();
+    for model_usage in file_usage.days.values() {
+        for model_totals in model_usage.values() {
+            totals.merge(model_totals);
+        }
+    }
+    Some(totals)
+}
+
+/// Usage of the session, of today across all projects and of the project.
+/// The first call reads every session file of the project and every file
+/// changed today; later calls only read appended lines.
+pub fn usage_breakdown<P: AsRef<Path>>(transcript_path: P) -> Option<UsageBreakdown> {
+    let path = transcript_path.as_ref();
+    let name = file_name(path)?;
+    let project_dir = path.parent()?;
+    let today = day_key(chrono::Local::now().date_naive());
+    let mut breakdown = UsageBreakdown::default();
+
+    let usage = update_usage(project_dir, |_| true);
+    for (file, file_usage) in &usage.files {
+        for (day, model_usage) in &file_usage.days {
+            merge_usage(&mut breakdown.project, model_usage);
+            if file == name {
+                merge_usage(&mut breakdown.session, model_usage);
+            }
+            if *day == today {
+                merge_usage(&mut breakdown.today, model_usage);
+            }
+        }
+    }
+
+    // Other projects can only have spent today in files written today
+    let midnight = chrono::Local::now()
+        .date_naive()
+        .and_hms_opt(0, 0, 0)
+        .and_then(|t| t.and_local_timezone(chrono::Local).earliest())
+        .map(std::time::SystemTime::from)
+        .unwrap_or(std::time::UNIX_EPOCH);
+    let other_dirs = project_dir
+        .parent()
+        .and_then(|root| fs::read_dir(root).ok())
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|dir| dir.is_dir() && dir != project_dir);
+    for dir in other_dirs {
+        let usage = update_usage(&dir, |file| {
+            fs::metadata(dir.join(file))
+                .and_then(|m| m.modified())
+                .is_ok_and(|modified| modified >= midnight)
+        });
+        for file_usage in usage.files.values() {
+            if let Some(model_usage) = file_usage.days.get(&today) {
+                merge_usage(&mut breakdown.today, model_usage);
+            }
+        }
+    }
+
+    Some(breakdown)
+}
+
+/// Bring the usage of a project's session files up to date, reading only the
+/// files `include` selects. Totals cannot take a line back, so an unfinished
+/// line waits for its newline.
+fn update_usage(project_dir: &Path, include: impl Fn(&str) -> bool) -> ProjectUsage {
+    let cache = ApiCache::new(cache_key("project_usage", project_dir), Duration::MAX);
+    let mut usage = cache
+        .load::<ProjectUsage>()
+        .map(|(usage, _)| usage)
+        .unwrap_or_default();
+
+    let mut changed = false;
+    for path in session_files(project_dir) {
+        let Some(name) = file_name(&path).filter(|name| include(name)) else {
+            continue;
+        };
+        let Ok(mut file) = File::open(&path) else {
+            continue;
+        };
+        let Ok(metadata) = file.metadata() else {
+            continue;
+        };
+        let inode = file_id(&metadata);
+        let len = metadata.len();
+        let file_usage = usage.files.entry(name.to_string()).or_default();
+        if file_usage.inode != inode || file_usage.offset > len {
+            *file_usage = FileUsage {
+                inode,
+                ..Default::default()
+            };
+        }
+
+        let offset = file_usage.offset;
+        if offset == len {
+            continue;
+        }
+        if let Ok(new_offset) = scan_forward(&mut file, offset, |line| {
+            if line.ends_with('\n') {
+                file_usage.apply(line);
+            }
+        }) {
+            file_usage.offset = new_offset;
+            changed |= new_offset != offset;
+        }
+    }
+    if changed {
+        crate::trace::record(format!(
+            "transcript: updated usage for {}",
+            project_dir.display()
+        ));
+        cache.store(&usage);
+    }
+    usage
+}
+
+fn merge_usage(into: &mut ModelUsage, usage: &ModelUsage) {
+    for (model, totals) in usage {
+        into.entry(model.clone()).or_default().merge(totals);
+    }
+}
+
+fn day_key(day: chrono::NaiveDate) -> String {
+    day.format("%Y-%m-%d").to_string()
+}
+
+/// Bring the transcript's persisted state up to date with the file
+fn read_state(path: &Path) -> Option<TranscriptState> {
+    let mut file = File::open(path).ok()?;
+    let metadata = file.metadata().ok()?;
+    let inode = file_id(&metadata);
+    let len = metadata.len();
+
+    let cache = ApiCache::new(cache_key("transcript", path), Duration::MAX);
+    let cached = cache
+        .load::<TranscriptState>()
+        .map(|(state, _)| state)
+        .filter(|state| state.inode == inode && state.offset <= len);
+
+    let state = match cached {
+        Some(mut state) => {
+            let offset = state.offset;
+            state.offset = scan_forward(&mut file, offset, |line| state.apply(line)).ok()?;
+            if state.offset != offset {
+                crate::trace::record(format!(
+                    "transcript: read {} new bytes",
+                    state.offset - offset
+                ));
+                cache.store(&state);
+            }
+            state
+        }
+        None => {
+            crate::trace::record(format!(
+                "transcript: indexing {} from the end",
+                path.display()
+            ));
+            let state = scan_backward(&mut file, inode, len).ok()?;
+            cache.store(&state);
+            state
+        }
+    };
+    Some(state)
+}
+
+/// Build a transcript's state from its tail: the last entry decides whether it
+/// is a summary, then lines are read backwards until an assistant usage shows up
+fn scan_backward(file: &mut File, inode: u64, len: u64) -> std::io::Result<TranscriptState> {
+    let complete = len == 0 || ends_with_newline(file, len)?;
+    let mut lines = ReverseLines::new(file, if complete { len.saturating_sub(1) } else { len });
+    let mut state = TranscriptState {
+        inode,
+        offset: len,
+        ..Default::default()
+    };
+
+    let mut first = true;
+    let mut last_entry_seen = false;
+    while let Some(line) = lines.next_line()? {
+        if std::mem::take(&mut first) && !complete {
+            // A trailing line without a newline may still be being written;
+            // it is read, but read again once finished
+            state.offset -= line.len() as u64;
+        }
+        let Some(entry) = parse_entry(&String::from_utf8_lossy(&line)) else {
+            continue;
+        };
+        if !std::mem::replace(&mut last_entry_seen, true)
+            && entry.r#type.as_deref() == Some("summary")
+            && entry.leaf_uuid.is_some()
+        {
+            state.summary_leaf = entry.leaf_uuid;
+            continue;
+        }
+        if let Some(tokens) = assistant_tokens(&entry) {
+            state.last_tokens = Some(tokens);
+            break;
+        }
+    }
+    Ok(state)
+}
+
+/// Look a message up in the project's uuid index, indexing new lines first
+fn find_tokens_by_uuid(project_dir: &Path, uuid: &str) -> Option<u32> {
+    let cache = ApiCache::new(cache_key("uuid_index", project_dir), Duration::MAX);
+    let mut index = cache
+        .load::<UuidIndex>()
+        .map(|(index, _)| index)
+        .unwrap_or_default();
+
+    let mut changed = false;
+    for path in session_files(project_dir) {
+        let Some(name) = file_name(&path).map(str::to_string) else {
+            continue;
+        };
+        let Ok(mut file) = File::open(&path) else {
+            continue;
+        };
+        let Ok(metadata) = file.metadata() else {
+            continue;
+        };
+        let inode = file_id(&metadata);
+        let offset = match index.files.get(&name) {
+            Some(&(indexed_inode, offset))
+                if indexed_inode == inode && offset <= metadata.len() =>
+            {
+                offset
+            }
+            _ => 0,
+        };
+        if offset == metadata.len() {
+            continue;
+        }
+        if let Ok(new_offset) = scan_forward(&mut file, offset, |line| index.apply(line)) {
+            index.files.insert(name, (inode, new_offset));
+            changed |= new_offset != offset;
+        }
+    }
+    if changed {
+        crate::trace::record(format!(
+            "transcript: updated uuid index for {}",
+            project_dir.display()
+        ));
+        cache.store(&index);
+    }
+
+    index.tokens_for(uuid)
+}
+
+/// Feed the lines after `offset` to `apply` and return the end of the last
+/// complete line. A trailing line without a newline is also fed, but the
+/// offset stays before it so it is read again once finished.
+fn scan_forward(file: &mut File, offset: u64, mut apply: impl FnMut(&str)) -> std::io::Result<u64> {
+    file.seek(SeekFrom::Start(offset))?;
+    let mut reader = BufReader::new(file);
+    let mut offset = offset;
+    let mut line = Vec::new();
+    loop {
+        line.clear();
+        let read = reader.read_until(b'\n', &mut line)?;
+        if read == 0 {
+            break;
+        }
+        apply(&String::from_utf8_lossy(&line));
+        if line.last() != Some(&b'\n') {
+            break;
+        }
+        offset += read as u64;
+    }
+    Ok(offset)
+}
+
+/// Lines of a file from last to first, read in chunks from the end
+struct ReverseLines<'a> {
+    file: &'a mut File,
+    /// Start of the bytes not yet read into `buffer`
+    position: u64,
+    buffer: Vec<u8>,
+}
+
+impl<'a> ReverseLines<'a> {
+    /// Lines ending before `end`, which should exclude the file's final newline
+    fn new(file: &'a mut File, end: u64) -> Self {
+        Self {
+            file,
+            position: end,
+            buffer: Vec::new(),
+        }
+    }
+
+    /// The previous line without its newline; `None` once the start is reached
+    fn next_line(&mut self) -> std::io::Result<Option<Vec<u8>>> {
+        loop {
+            if let Some(newline) = self.buffer.iter().rposition(|&b| b == b'\n') {
+                let line = self.buffer.split_off(newline + 1);
+                self.buffer.pop();
+                return Ok(Some(line));
+            }
+            if self.position == 0 {
+                return Ok((!self.buffer.is_empty()).then(|| std::mem::take(&mut self.buffer)));
+            }
+
+            let step = REVERSE_CHUNK.min(self.position);
+            self.position -= step;
+            let mut chunk = vec![0; step as usize];
+            self.file.seek(SeekFrom::Start(self.position))?;
+            self.file.read_exact(&mut chunk)?;
+            chunk.append(&mut self.buffer);
+            self.buffer = chunk;
+        }
+    }
+}
+
+fn ends_with_newline(file: &mut File, len: u64) -> std::io::Result<bool> {
+    let mut last = [0u8; 1];
+    file.seek(SeekFrom::Start(len - 1))?;
+    file.read_exact(&mut last)?;
+    Ok(last[0] == b'\n')
+}
+
+fn parse_entry(line: &str) -> Option<TranscriptEntry> {
+    let line = line.trim();
+    if line.is_empty() {
+        return None;
+    }
+    serde_json::from_str(line).ok()
+}
+
+/// Context tokens of an assistant entry that carries usage
+fn assistant_tokens(entry: &TranscriptEntry) -> Option<u32> {
+    if entry.r#type.as_deref() != Some("assistant") {
+        return None;
+    }
+    let raw_usage = entry.message.as_ref()?.usage.as_ref()?;
+    Some(raw_usage.clone().normalize().display_tokens())
+}
+
+fn session_files(project_dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(project_dir) else {
+        return Vec::new();
+    };
+    entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().and_then(|s| s.to_str()) == Some("jsonl"))
+        .collect()
+}
+
+/// Identifies the file behind a path, so a replaced transcript is re-read
+fn file_id(metadata: &fs::Metadata) -> u64 {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        metadata.ino()
+    }
+    #[cfg(not(unix))]
+    {
+        metadata
+            .created()
+            .ok()
+            .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+            .map(|d| d.as_nanos() as u64)
+            .unwrap_or_default()
+    }
+}
+
+fn file_name(path: &Path) -> Option<&str> {
+    path.file_name().and_then(|n| n.to_str())
+}
+
+fn cache_key(prefix: &str, path: &Path) -> String {
+    let mut hasher = DefaultHasher::new();
+    path.hash(&mut hasher);
+    format!("{}_{:016x}", prefix, hasher.finish())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    fn temp_file(name: &str, content: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!(
+            "glmcode-transcript-test-{}-{}.jsonl",
+            name,
+            std::process::id()
+        ));
+        fs::write(&path, content).unwrap();
+        path
+    }
+
+    fn append(path: &Path, content: &str) {
+        let mut file = fs::OpenOptions::new().append(true).open(path).unwrap();
+        file.write_all(content.as_bytes()).unwrap();
+    }
+
+    fn assistant(id: &str, input_tokens: u32, output_tokens: u32) -> String {
+        format!(
+            r#"{{"type":"assistant","timestamp":"2026-01-02T03:04:05Z","message":{{"id":"{}","model":"glm-4.6","usage":{{"input_tokens":{},"output_tokens":{}}}}}}}"#,
+            id, input_tokens, output_tokens
+        )
+    }
+
+    #[test]
+    fn scan_forward_stops_before_unfinished_line() {
+        let first = format!("{}\n", assistant("msg_1", 10, 1));
+        let path = temp_file("offsets", &format!("{}{{\"type\":", first));
+        let mut file = File::open(&path).unwrap();
+
+        let mut lines = Vec::new();
+        let offset = scan_forward(&mut file, 0, |line| lines.push(line.to_string())).unwrap();
+        assert_eq!(offset, first.len() as u64);
+        assert_eq!(lines.len(), 2);
+        assert!(!lines[1].ends_with('\n'));
+
+        // The unfinished line is read again once its newline arrives
+        append(&path, "\"user\"}\n");
+        let mut file = File::open(&path).unwrap();
+        let mut lines = Vec::new();
+        let offset = scan_forward(&mut file, offset, |line| lines.push(line.to_string())).unwrap();
+        assert_eq!(offset, fs::metadata(&path).unwrap().len());
+        assert_eq!(lines, vec!["{\"type\":\"user\"}\n".to_string()]);
+        let _ = fs::remove_file(path);
+    }
+
+    #[test]
+    fn file_usage_counts_each_message_once() {
+        let mut usage = FileUsage::default();
+        // Content blocks of one message repeat its usage
+        usage.apply(&assistant("msg_1", 100, 10));
+        usage.apply(&assistant("msg_1", 100, 10));
+        usage.apply(&assistant("msg_2", 50, 5));
+        usage.apply(r#"{"type":"user","message":{"content":"hi"}}"#);
+
+        let totals: Vec<&SessionTotals> = usage
+            .days
+            .values()
+            .flat_map(|models| models.values())
+            .collect();
+        assert_eq!(totals.len(), 1);
+        assert_eq!(totals[0].turns, 2);
+        assert_eq!(totals[0].input_tokens, 150);
+        assert_eq!(totals[0].output_tokens, 15);
+    }
+
+    #[test]
+    fn incremental_reads_match_a_full_read() {
+        let path = temp_file("incremental", &format!("{}\n", assistant("msg_1", 100, 10)));
+        let mut usage = FileUsage::default();
+        let mut file = File::open(&path).unwrap();
+        let offset = scan_forward(&mut file, 0, |line| {
+            if line.ends_with('\n') {
+                usage.apply(line);
+            }
+        })
+        .unwrap();
+
+        append(&path, &format!("{}\n", assistant("msg_1", 100, 10)));
+        append(&path, &format!("{}\n", assistant("msg_2", 20, 2)));
+        let mut file = File::open(&path).unwrap();
+        scan_forward(&mut file, offset, |line| {
+            if line.ends_with('\n') {
+                usage.apply(line);
+            }
+        })
+        .unwrap();
+
+        let mut full = FileUsage::default();
+        for line in fs::read_to_string(&path).unwrap().lines() {
+            full.apply(line);
+        }
+        let totals = |usage: &FileUsage| {
+            let mut totals = SessionTotals::default();
+            for models in usage.days.values() {
+                for model_totals in models.values() {
+                    totals.merge(model_totals);
+                }
+            }
+            (totals.turns, totals.input_tokens, totals.output_tokens)
+        };
+        assert_eq!(totals(&usage), (2, 120, 12));
+        assert_eq!(totals(&usage), totals(&full));
+        let _ = fs::remove_file(path);
+    }
+
+    #[test]
+    fn cache_hit_ratio_covers_every_prompt_token() {
+        let totals = SessionTotals {
+            turns: 2,
+            input_tokens: 100,
+            output_tokens: 40,
+            cache_read_tokens: 300,
+            cache_creation_tokens: 100,
+        };
+        assert_eq!(totals.prompt_tokens(), 500);
+        assert_eq!(totals.cache_hit_ratio(), Some(60.0));
+        assert_eq!(SessionTotals::default().cache_hit_ratio(), None);
+    }
+
+    #[test]
+    fn reverse_lines_walks_back_from_the_end() {
+        let path = temp_file("reverse", "a\nbb\n\nccc\n");
+        let mut file = File::open(&path).unwrap();
+        let len = fs::metadata(&path).unwrap().len();
+        let mut lines = ReverseLines::new(&mut file, len - 1);
+        let mut seen = Vec::new();
+        while let Some(line) = lines.next_line().unwrap() {
+            seen.push(String::from_utf8(line).unwrap());
+        }
+        assert_eq!(seen, vec!["ccc", "", "bb", "a"]);
+        let _ = fs::remove_file(path);
+    }
+
+    #[test]
+    fn state_follows_summary_leaf() {
+        let mut state = TranscriptState::default();
+        state.apply(&assistant("msg_1", 100, 10));
+        assert_eq!(state.last_tokens, Some(110));
+        state.apply(r#"{"type":"summary","summary":"s","leafUuid":"uuid-1"}"#);
+        assert_eq!(state.summary_leaf.as_deref(), Some("uuid-1"));
+        state.apply(&assistant("msg_2", 200, 20));
+        assert_eq!(state.summary_leaf, None);
+        assert_eq!(state.last_tokens, Some(220));
+    }
+
+    #[test]
+    fn uuid_index_resolves_user_messages_through_their_parent() {
+        let mut index = UuidIndex::default();
+        index.apply(
+            r#"{"type":"assistant","uuid":"a-1","message":{"usage":{"input_tokens":300,"output_tokens":30}}}"#,
+        );
+        index.apply(r#"{"type":"user","uuid":"u-1","parentUuid":"a-1"}"#);
+        index.apply(r#"{"type":"user","uuid":"u-2","parentUuid":"missing"}"#);
+
+        assert_eq!(index.tokens_for("a-1"), Some(330));
+        assert_eq!(index.tokens_for("u-1"), Some(330));
+        assert_eq!(index.tokens_for("u-2"), None);
+        assert_eq!(index.tokens_for("unknown"), None);
+    }
+}
@@ -507,6 +507,12 @@ impl App {
                         SegmentId::Update => "更新",
                         SegmentId::GlmUsage => "GLM用量",
                         SegmentId::DecodeSpeed => "解码速度",
+                        SegmentId::GlmModelUsage => "GLM模型用量",
+                        SegmentId::GlmToolUsage => "GLM工具调用",
+                        SegmentId::SessionTokens => "会话Token",
+                        SegmentId::EstimatedCost => "费用估算",
+                        SegmentId::Cost => "费用",
+                        SegmentId::Custom => "自定义",
                     };
                     let is_enabled = segment.enabled;
                     self.status_message = Some(format!(
@@ -535,6 +541,12 @@ impl App {
                                 SegmentId::Update => "更新",
                                 SegmentId::GlmUsage => "GLM用量",
                                 SegmentId::DecodeSpeed => "解码速度",
+                                SegmentId::GlmModelUsage => "GLM模型用量",
+                                SegmentId::GlmToolUsage => "GLM工具调用",
+                                SegmentId::SessionTokens => "会话Token",
+                                SegmentId::EstimatedCost => "费用估算",
+                                SegmentId::Cost => "费用",
+                                SegmentId::Custom => "自定义",
                             };
                             let is_enabled = segment.enabled;
                             self.status_message = Some(format!(
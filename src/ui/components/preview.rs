@@ -197,6 +197,78 @@ impl PreviewComponent {
                         map
                     },
                 },
+                SegmentId::GlmModelUsage => SegmentData {
+                    primary: "glm-4.6 12.3M · glm-4.5-air 1.2M".to_string(),
+                    secondary: "24h".to_string(),
+                    metadata: {
+                        let mut map = HashMap::new();
+                        map.insert("hours".to_string(), "24".to_string());
+                        map.insert("total_tokens".to_string(), "13500000".to_string());
+                        map.insert("model_count".to_string(), "2".to_string());
+                        map
+                    },
+                },
+                SegmentId::GlmToolUsage => SegmentData {
+                    primary: "搜索 42 · 读取 17 · 视觉 3".to_string(),
+                    secondary: "24h".to_string(),
+                    metadata: {
+                        let mut map = HashMap::new();
+                        map.insert("hours".to_string(), "24".to_string());
+                        map.insert("networkSearch_count".to_string(), "42".to_string());
+                        map.insert("webRead_count".to_string(), "17".to_string());
+                        map.insert("vision_count".to_string(), "3".to_string());
+                        map
+                    },
+                },
+                SegmentId::SessionTokens => SegmentData {
+                    primary: "↑2.4k ↓18.6k 缓存 1.3M".to_string(),
+                    secondary: "命中 93% · 42 轮".to_string(),
+                    metadata: {
+                        let mut map = HashMap::new();
+                        map.insert("input_tokens".to_string(), "2400".to_string());
+                        map.insert("output_tokens".to_string(), "18600".to_string());
+                        map.insert("cache_read_tokens".to_string(), "1300000".to_string());
+                        map.insert("cache_hit_ratio".to_string(), "93.0".to_string());
+                        map.insert("turns".to_string(), "42".to_string());
+                        map
+                    },
+                },
+                SegmentId::EstimatedCost => SegmentData {
+                    primary: "¥1.86".to_string(),
+                    secondary: "今日 ¥12.40 · 项目 ¥57.31".to_string(),
+                    metadata: {
+                        let mut map = HashMap::new();
+                        map.insert("session_cost".to_string(), "1.8600".to_string());
+                        map.insert("today_cost".to_string(), "12.4000".to_string());
+                        map.insert("project_cost".to_string(), "57.3100".to_string());
+                        map.insert("currency".to_string(), "CNY".to_string());
+                        map.insert("symbol".to_string(), "¥".to_string());
+                        map
+                    },
+                },
+                SegmentId::Cost => SegmentData {
+                    primary: "$0.42".to_string(),
+                    secondary: "API 3m12s/15m · 日 31% · 月 12%".to_string(),
+                    metadata: {
+                        let mut map = HashMap::new();
+                        map.insert("cost_usd".to_string(), "0.4200".to_string());
+                        map.insert("today_cost".to_string(), "1.5500".to_string());
+                        map.insert("month_cost".to_string(), "23.8000".to_string());
+                        map.insert("daily_percentage".to_string(), "31.0".to_string());
+                        map.insert("monthly_percentage".to_string(), "11.9".to_string());
+                        map
+                    },
+                },
+                SegmentId::Custom => SegmentData {
+                    primary: segment_config
+                        .options
+                        .get("name")
+                        .and_then(|v| v.as_str())
+                        .unwrap_or("custom")
+                        .to_string(),
+                    secondary: String::new(),
+                    metadata: HashMap::new(),
+                },
             };
 
             segments_data.push((segment_config.clone(), mock_data));
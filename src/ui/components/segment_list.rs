@@ -58,6 +58,16 @@ impl SegmentListComponent {
                     SegmentId::Update => "更新",
                     SegmentId::GlmUsage => "GLM用量",
                     SegmentId::DecodeSpeed => "解码速度",
+                    SegmentId::GlmModelUsage => "GLM模型用量",
+                    SegmentId::GlmToolUsage => "GLM工具调用",
+                    SegmentId::SessionTokens => "会话Token",
+                    SegmentId::EstimatedCost => "费用估算",
+                    SegmentId::Cost => "费用",
+                    SegmentId::Custom => "自定义",
+                };
+                let segment_name = match &segment.key {
+                    Some(key) => format!("{} [{}]", segment_name, key),
+                    None => segment_name.to_string(),
                 };
 
                 if is_selected {
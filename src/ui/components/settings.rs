@@ -37,6 +37,12 @@ impl SettingsComponent {
                 SegmentId::Update => "更新",
                 SegmentId::GlmUsage => "GLM用量",
                 SegmentId::DecodeSpeed => "解码速度",
+                SegmentId::GlmModelUsage => "GLM模型用量",
+                SegmentId::GlmToolUsage => "GLM工具调用",
+                SegmentId::SessionTokens => "会话Token",
+                SegmentId::EstimatedCost => "费用估算",
+                SegmentId::Cost => "费用",
+                SegmentId::Custom => "自定义",
             };
             let current_icon = match config.style.mode {
                 StyleMode::Plain => &segment.icon.plain,
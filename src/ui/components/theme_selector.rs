@@ -106,6 +106,7 @@ impl ThemeSelectorComponent {
         preview_spans.push(Span::raw("预览: "));
 
         // Helper function to find segment by ID and get its background color
+        #[allow(clippy::redundant_closure)]
         let get_bg_color = |segment_id: crate::config::types::SegmentId| -> Color {
             config.segments
                 .iter()
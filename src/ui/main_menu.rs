@@ -84,6 +84,7 @@ impl MainMenu {
                     KeyCode::Esc | KeyCode::Char('q') => {
                         self.should_quit = true;
                     }
+                    #[allow(clippy::collapsible_match)]
                     KeyCode::Up => {
                         if self.selected_item > 0 {
                             self.selected_item -= 1;
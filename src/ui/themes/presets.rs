@@ -1,6 +1,8 @@
 // Theme presets for TUI configuration
 
-use crate::config::{Config, StyleConfig, StyleMode};
+use crate::config::{
+    Config, GlmConfig, HistoryConfig, StyleConfig, StyleMode, DEFAULT_RENDER_DEADLINE_MS,
+};
 
 // Import all theme modules
 use super::{
@@ -136,10 +138,21 @@ impl ThemePresets {
                 theme_cometix::usage_segment(),
                 theme_cometix::glm_usage_segment(),
                 theme_cometix::decode_speed_segment(),
+                theme_cometix::glm_model_usage_segment(),
+                theme_cometix::glm_tool_usage_segment(),
+                theme_cometix::session_tokens_segment(),
+                theme_cometix::estimated_cost_segment(),
+                theme_cometix::cost_segment(),
                 theme_cometix::session_segment(),
                 theme_cometix::output_style_segment(),
             ],
             theme: "cometix".to_string(),
+            render_deadline_ms: DEFAULT_RENDER_DEADLINE_MS,
+            max_width: None,
+            glm: GlmConfig::default(),
+            history: HistoryConfig::default(),
+            alerts: Vec::new(),
+            rows: Vec::new(),
         }
     }
 
@@ -157,10 +170,21 @@ impl ThemePresets {
                 theme_default::usage_segment(),
                 theme_default::glm_usage_segment(),
                 theme_default::decode_speed_segment(),
+                theme_default::glm_model_usage_segment(),
+                theme_default::glm_tool_usage_segment(),
+                theme_default::session_tokens_segment(),
+                theme_default::estimated_cost_segment(),
+                theme_default::cost_segment(),
                 theme_default::session_segment(),
                 theme_default::output_style_segment(),
             ],
             theme: "default".to_string(),
+            render_deadline_ms: DEFAULT_RENDER_DEADLINE_MS,
+            max_width: None,
+            glm: GlmConfig::default(),
+            history: HistoryConfig::default(),
+            alerts: Vec::new(),
+            rows: Vec::new(),
         }
     }
 
@@ -178,10 +202,21 @@ impl ThemePresets {
                 theme_minimal::usage_segment(),
                 theme_minimal::glm_usage_segment(),
                 theme_minimal::decode_speed_segment(),
+                theme_minimal::glm_model_usage_segment(),
+                theme_minimal::glm_tool_usage_segment(),
+                theme_minimal::session_tokens_segment(),
+                theme_minimal::estimated_cost_segment(),
+                theme_minimal::cost_segment(),
                 theme_minimal::session_segment(),
                 theme_minimal::output_style_segment(),
             ],
             theme: "minimal".to_string(),
+            render_deadline_ms: DEFAULT_RENDER_DEADLINE_MS,
+            max_width: None,
+            glm: GlmConfig::default(),
+            history: HistoryConfig::default(),
+            alerts: Vec::new(),
+            rows: Vec::new(),
         }
     }
 
@@ -199,10 +234,21 @@ impl ThemePresets {
                 theme_gruvbox::usage_segment(),
                 theme_gruvbox::glm_usage_segment(),
                 theme_gruvbox::decode_speed_segment(),
+                theme_gruvbox::glm_model_usage_segment(),
+                theme_gruvbox::glm_tool_usage_segment(),
+                theme_gruvbox::session_tokens_segment(),
+                theme_gruvbox::estimated_cost_segment(),
+                theme_gruvbox::cost_segment(),
                 theme_gruvbox::session_segment(),
                 theme_gruvbox::output_style_segment(),
             ],
             theme: "gruvbox".to_string(),
+            render_deadline_ms: DEFAULT_RENDER_DEADLINE_MS,
+            max_width: None,
+            glm: GlmConfig::default(),
+            history: HistoryConfig::default(),
+            alerts: Vec::new(),
+            rows: Vec::new(),
         }
     }
 
@@ -220,10 +266,21 @@ impl ThemePresets {
                 theme_nord::usage_segment(),
                 theme_nord::glm_usage_segment(),
                 theme_nord::decode_speed_segment(),
+                theme_nord::glm_model_usage_segment(),
+                theme_nord::glm_tool_usage_segment(),
+                theme_nord::session_tokens_segment(),
+                theme_nord::estimated_cost_segment(),
+                theme_nord::cost_segment(),
                 theme_nord::session_segment(),
                 theme_nord::output_style_segment(),
             ],
             theme: "nord".to_string(),
+            render_deadline_ms: DEFAULT_RENDER_DEADLINE_MS,
+            max_width: None,
+            glm: GlmConfig::default(),
+            history: HistoryConfig::default(),
+            alerts: Vec::new(),
+            rows: Vec::new(),
         }
     }
 
@@ -241,10 +298,21 @@ impl ThemePresets {
                 theme_powerline_dark::usage_segment(),
                 theme_powerline_dark::glm_usage_segment(),
                 theme_powerline_dark::decode_speed_segment(),
+                theme_powerline_dark::glm_model_usage_segment(),
+                theme_powerline_dark::glm_tool_usage_segment(),
+                theme_powerline_dark::session_tokens_segment(),
+                theme_powerline_dark::estimated_cost_segment(),
+                theme_powerline_dark::cost_segment(),
                 theme_powerline_dark::session_segment(),
                 theme_powerline_dark::output_style_segment(),
             ],
             theme: "powerline-dark".to_string(),
+            render_deadline_ms: DEFAULT_RENDER_DEADLINE_MS,
+            max_width: None,
+            glm: GlmConfig::default(),
+            history: HistoryConfig::default(),
+            alerts: Vec::new(),
+            rows: Vec::new(),
         }
     }
 
@@ -262,10 +330,21 @@ impl ThemePresets {
                 theme_powerline_light::usage_segment(),
                 theme_powerline_light::glm_usage_segment(),
                 theme_powerline_light::decode_speed_segment(),
+                theme_powerline_light::glm_model_usage_segment(),
+                theme_powerline_light::glm_tool_usage_segment(),
+                theme_powerline_light::session_tokens_segment(),
+                theme_powerline_light::estimated_cost_segment(),
+                theme_powerline_light::cost_segment(),
                 theme_powerline_light::session_segment(),
                 theme_powerline_light::output_style_segment(),
             ],
             theme: "powerline-light".to_string(),
+            render_deadline_ms: DEFAULT_RENDER_DEADLINE_MS,
+            max_width: None,
+            glm: GlmConfig::default(),
+            history: HistoryConfig::default(),
+            alerts: Vec::new(),
+            rows: Vec::new(),
         }
     }
 
@@ -283,10 +362,21 @@ impl ThemePresets {
                 theme_powerline_rose_pine::usage_segment(),
                 theme_powerline_rose_pine::glm_usage_segment(),
                 theme_powerline_rose_pine::decode_speed_segment(),
+                theme_powerline_rose_pine::glm_model_usage_segment(),
+                theme_powerline_rose_pine::glm_tool_usage_segment(),
+                theme_powerline_rose_pine::session_tokens_segment(),
+                theme_powerline_rose_pine::estimated_cost_segment(),
+                theme_powerline_rose_pine::cost_segment(),
                 theme_powerline_rose_pine::session_segment(),
                 theme_powerline_rose_pine::output_style_segment(),
             ],
             theme: "powerline-rose-pine".to_string(),
+            render_deadline_ms: DEFAULT_RENDER_DEADLINE_MS,
+            max_width: None,
+            glm: GlmConfig::default(),
+            history: HistoryConfig::default(),
+            alerts: Vec::new(),
+            rows: Vec::new(),
         }
     }
 
@@ -304,10 +394,21 @@ impl ThemePresets {
                 theme_powerline_tokyo_night::usage_segment(),
                 theme_powerline_tokyo_night::glm_usage_segment(),
                 theme_powerline_tokyo_night::decode_speed_segment(),
+                theme_powerline_tokyo_night::glm_model_usage_segment(),
+                theme_powerline_tokyo_night::glm_tool_usage_segment(),
+                theme_powerline_tokyo_night::session_tokens_segment(),
+                theme_powerline_tokyo_night::estimated_cost_segment(),
+                theme_powerline_tokyo_night::cost_segment(),
                 theme_powerline_tokyo_night::session_segment(),
                 theme_powerline_tokyo_night::output_style_segment(),
             ],
             theme: "powerline-tokyo-night".to_string(),
+            render_deadline_ms: DEFAULT_RENDER_DEADLINE_MS,
+            max_width: None,
+            glm: GlmConfig::default(),
+            history: HistoryConfig::default(),
+            alerts: Vec::new(),
+            rows: Vec::new(),
         }
     }
 }
@@ -6,6 +6,7 @@ use std::collections::HashMap;
 pub fn model_segment() -> SegmentConfig {
     SegmentConfig {
         id: SegmentId::Model,
+        key: None,
         enabled: true,
         icon: IconConfig {
             plain: "🤖".to_string(),
@@ -24,6 +25,7 @@ pub fn model_segment() -> SegmentConfig {
 pub fn directory_segment() -> SegmentConfig {
     SegmentConfig {
         id: SegmentId::Directory,
+        key: None,
         enabled: true,
         icon: IconConfig {
             plain: "📁".to_string(),
@@ -42,6 +44,7 @@ pub fn directory_segment() -> SegmentConfig {
 pub fn git_segment() -> SegmentConfig {
     SegmentConfig {
         id: SegmentId::Git,
+        key: None,
         enabled: true,
         icon: IconConfig {
             plain: "🌿".to_string(),
@@ -64,6 +67,7 @@ pub fn git_segment() -> SegmentConfig {
 pub fn context_window_segment() -> SegmentConfig {
     SegmentConfig {
         id: SegmentId::ContextWindow,
+        key: None,
         enabled: true,
         icon: IconConfig {
             plain: "⚡️".to_string(),
@@ -83,6 +87,7 @@ pub fn context_window_segment() -> SegmentConfig {
 pub fn session_segment() -> SegmentConfig {
     SegmentConfig {
         id: SegmentId::Session,
+        key: None,
         enabled: false,
         icon: IconConfig {
             plain: "⏱️".to_string(),
@@ -101,6 +106,7 @@ pub fn session_segment() -> SegmentConfig {
 pub fn output_style_segment() -> SegmentConfig {
     SegmentConfig {
         id: SegmentId::OutputStyle,
+        key: None,
         enabled: false,
         icon: IconConfig {
             plain: "🎯".to_string(),
@@ -119,6 +125,7 @@ pub fn output_style_segment() -> SegmentConfig {
 pub fn usage_segment() -> SegmentConfig {
     SegmentConfig {
         id: SegmentId::Usage,
+        key: None,
         enabled: false,
         icon: IconConfig {
             plain: "📊".to_string(),
@@ -149,6 +156,7 @@ pub fn usage_segment() -> SegmentConfig {
 pub fn glm_usage_segment() -> SegmentConfig {
     SegmentConfig {
         id: SegmentId::GlmUsage,
+        key: None,
         enabled: false,
         icon: IconConfig {
             plain: "📊".to_string(),
@@ -160,13 +168,31 @@ pub fn glm_usage_segment() -> SegmentConfig {
             background: None,
         },
         styles: TextStyleConfig { text_bold: true },
-        options: HashMap::new(),
+        options: {
+            let mut opts = HashMap::new();
+            opts.insert(
+                "cache_duration".to_string(),
+                serde_json::Value::Number(60.into()),
+            );
+            opts.insert("timeout".to_string(), serde_json::Value::Number(3.into()));
+            opts.insert(
+                "color_rules".to_string(),
+                serde_json::json!([
+                    { "metric": "exhaust_in", "max": 3600, "text": { "c256": 167 } },
+                    { "metric": "tokens_percentage", "min": 80, "text": { "c256": 167 } },
+                    { "metric": "tokens_percentage", "min": 50, "text": { "c256": 179 } },
+                    { "metric": "tokens_percentage", "text": { "c256": 114 } },
+                ]),
+            );
+            opts
+        },
     }
 }
 
 pub fn decode_speed_segment() -> SegmentConfig {
     SegmentConfig {
         id: SegmentId::DecodeSpeed,
+        key: None,
         enabled: false,
         icon: IconConfig {
             plain: "⚡".to_string(),
@@ -178,6 +204,128 @@ pub fn decode_speed_segment() -> SegmentConfig {
             background: None,
         },
         styles: TextStyleConfig { text_bold: true },
+        options: {
+            let mut opts = HashMap::new();
+            opts.insert(
+                "cache_duration".to_string(),
+                serde_json::Value::Number(300.into()),
+            );
+            opts.insert("timeout".to_string(), serde_json::Value::Number(3.into()));
+            opts
+        },
+    }
+}
+
+pub fn glm_model_usage_segment() -> SegmentConfig {
+    SegmentConfig {
+        id: SegmentId::GlmModelUsage,
+        key: None,
+        enabled: false,
+        icon: IconConfig {
+            plain: "🧠".to_string(),
+            nerd_font: "\u{f2db}".to_string(),
+        },
+        colors: ColorConfig {
+            icon: Some(AnsiColor::Color256 { c256: 175 }), // Gruvbox purple
+            text: Some(AnsiColor::Color256 { c256: 175 }),
+            background: None,
+        },
+        styles: TextStyleConfig { text_bold: true },
+        options: {
+            let mut opts = HashMap::new();
+            opts.insert(
+                "cache_duration".to_string(),
+                serde_json::Value::Number(300.into()),
+            );
+            opts.insert("timeout".to_string(), serde_json::Value::Number(3.into()));
+            opts.insert("hours".to_string(), serde_json::Value::Number(24.into()));
+            opts.insert("max_models".to_string(), serde_json::Value::Number(2.into()));
+            opts
+        },
+    }
+}
+
+pub fn glm_tool_usage_segment() -> SegmentConfig {
+    SegmentConfig {
+        id: SegmentId::GlmToolUsage,
+        key: None,
+        enabled: false,
+        icon: IconConfig {
+            plain: "🛠".to_string(),
+            nerd_font: "\u{f0ad}".to_string(),
+        },
+        colors: ColorConfig {
+            icon: Some(AnsiColor::Color256 { c256: 175 }), // Gruvbox purple
+            text: Some(AnsiColor::Color256 { c256: 175 }),
+            background: None,
+        },
+        styles: TextStyleConfig { text_bold: true },
+        options: {
+            let mut opts = HashMap::new();
+            opts.insert(
+                "cache_duration".to_string(),
+                serde_json::Value::Number(300.into()),
+            );
+            opts.insert("timeout".to_string(), serde_json::Value::Number(3.into()));
+            opts.insert("hours".to_string(), serde_json::Value::Number(24.into()));
+            opts
+        },
+    }
+}
+
+pub fn session_tokens_segment() -> SegmentConfig {
+    SegmentConfig {
+        id: SegmentId::SessionTokens,
+        key: None,
+        enabled: false,
+        icon: IconConfig {
+            plain: "Σ".to_string(),
+            nerd_font: "\u{f080}".to_string(),
+        },
+        colors: ColorConfig {
+            icon: Some(AnsiColor::Color16 { c16: 5 }),
+            text: Some(AnsiColor::Color16 { c16: 5 }),
+            background: None,
+        },
+        styles: TextStyleConfig { text_bold: true },
+        options: HashMap::new(),
+    }
+}
+
+pub fn estimated_cost_segment() -> SegmentConfig {
+    SegmentConfig {
+        id: SegmentId::EstimatedCost,
+        key: None,
+        enabled: false,
+        icon: IconConfig {
+            plain: "¥".to_string(),
+            nerd_font: "\u{f155}".to_string(),
+        },
+        colors: ColorConfig {
+            icon: Some(AnsiColor::Color16 { c16: 5 }),
+            text: Some(AnsiColor::Color16 { c16: 5 }),
+            background: None,
+        },
+        styles: TextStyleConfig { text_bold: true },
+        options: HashMap::new(),
+    }
+}
+
+pub fn cost_segment() -> SegmentConfig {
+    SegmentConfig {
+        id: SegmentId::Cost,
+        key: None,
+        enabled: false,
+        icon: IconConfig {
+            plain: "$".to_string(),
+            nerd_font: "\u{f155}".to_string(),
+        },
+        colors: ColorConfig {
+            icon: Some(AnsiColor::Color16 { c16: 5 }),
+            text: Some(AnsiColor::Color16 { c16: 5 }),
+            background: None,
+        },
+        styles: TextStyleConfig { text_bold: true },
         options: HashMap::new(),
     }
 }
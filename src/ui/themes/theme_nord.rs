@@ -6,6 +6,7 @@ use std::collections::HashMap;
 pub fn model_segment() -> SegmentConfig {
     SegmentConfig {
         id: SegmentId::Model,
+        key: None,
         enabled: true,
         icon: IconConfig {
             plain: "🤖".to_string(),
@@ -36,6 +37,7 @@ pub fn model_segment() -> SegmentConfig {
 pub fn directory_segment() -> SegmentConfig {
     SegmentConfig {
         id: SegmentId::Directory,
+        key: None,
         enabled: true,
         icon: IconConfig {
             plain: "📁".to_string(),
@@ -66,6 +68,7 @@ pub fn directory_segment() -> SegmentConfig {
 pub fn git_segment() -> SegmentConfig {
     SegmentConfig {
         id: SegmentId::Git,
+        key: None,
         enabled: true,
         icon: IconConfig {
             plain: "🌿".to_string(),
@@ -100,6 +103,7 @@ pub fn git_segment() -> SegmentConfig {
 pub fn context_window_segment() -> SegmentConfig {
     SegmentConfig {
         id: SegmentId::ContextWindow,
+        key: None,
         enabled: true,
         icon: IconConfig {
             plain: "⚡️".to_string(),
@@ -131,6 +135,7 @@ pub fn context_window_segment() -> SegmentConfig {
 pub fn session_segment() -> SegmentConfig {
     SegmentConfig {
         id: SegmentId::Session,
+        key: None,
         enabled: false,
         icon: IconConfig {
             plain: "⏱️".to_string(),
@@ -161,6 +166,7 @@ pub fn session_segment() -> SegmentConfig {
 pub fn output_style_segment() -> SegmentConfig {
     SegmentConfig {
         id: SegmentId::OutputStyle,
+        key: None,
         enabled: false,
         icon: IconConfig {
             plain: "🎯".to_string(),
@@ -191,6 +197,7 @@ pub fn output_style_segment() -> SegmentConfig {
 pub fn usage_segment() -> SegmentConfig {
     SegmentConfig {
         id: SegmentId::Usage,
+        key: None,
         enabled: false,
         icon: IconConfig {
             plain: "📊".to_string(),
@@ -221,6 +228,7 @@ pub fn usage_segment() -> SegmentConfig {
 pub fn glm_usage_segment() -> SegmentConfig {
     SegmentConfig {
         id: SegmentId::GlmUsage,
+        key: None,
         enabled: false,
         icon: IconConfig {
             plain: "📊".to_string(),
@@ -244,13 +252,46 @@ pub fn glm_usage_segment() -> SegmentConfig {
             }), // Nord yellow background
         },
         styles: TextStyleConfig::default(),
-        options: HashMap::new(),
+        options: {
+            let mut opts = HashMap::new();
+            opts.insert(
+                "cache_duration".to_string(),
+                serde_json::Value::Number(60.into()),
+            );
+            opts.insert("timeout".to_string(), serde_json::Value::Number(3.into()));
+            opts.insert(
+                "color_rules".to_string(),
+                serde_json::json!([
+                    {
+                        "metric": "exhaust_in",
+                        "max": 3600,
+                        "background": { "r": 191, "g": 97, "b": 106 },
+                    },
+                    {
+                        "metric": "tokens_percentage",
+                        "min": 80,
+                        "background": { "r": 191, "g": 97, "b": 106 },
+                    },
+                    {
+                        "metric": "tokens_percentage",
+                        "min": 50,
+                        "background": { "r": 235, "g": 203, "b": 139 },
+                    },
+                    {
+                        "metric": "tokens_percentage",
+                        "background": { "r": 163, "g": 190, "b": 140 },
+                    },
+                ]),
+            );
+            opts
+        },
     }
 }
 
 pub fn decode_speed_segment() -> SegmentConfig {
     SegmentConfig {
         id: SegmentId::DecodeSpeed,
+        key: None,
         enabled: false,
         icon: IconConfig {
             plain: "⚡".to_string(),
@@ -262,6 +303,188 @@ pub fn decode_speed_segment() -> SegmentConfig {
             background: None,
         },
         styles: TextStyleConfig::default(),
+        options: {
+            let mut opts = HashMap::new();
+            opts.insert(
+                "cache_duration".to_string(),
+                serde_json::Value::Number(300.into()),
+            );
+            opts.insert("timeout".to_string(), serde_json::Value::Number(3.into()));
+            opts
+        },
+    }
+}
+
+pub fn glm_model_usage_segment() -> SegmentConfig {
+    SegmentConfig {
+        id: SegmentId::GlmModelUsage,
+        key: None,
+        enabled: false,
+        icon: IconConfig {
+            plain: "🧠".to_string(),
+            nerd_font: "\u{f2db}".to_string(),
+        },
+        colors: ColorConfig {
+            icon: Some(AnsiColor::Rgb {
+                r: 46,
+                g: 52,
+                b: 64,
+            }),
+            text: Some(AnsiColor::Rgb {
+                r: 46,
+                g: 52,
+                b: 64,
+            }),
+            background: Some(AnsiColor::Rgb {
+                r: 235,
+                g: 203,
+                b: 139,
+            }), // Nord yellow background
+        },
+        styles: TextStyleConfig::default(),
+        options: {
+            let mut opts = HashMap::new();
+            opts.insert(
+                "cache_duration".to_string(),
+                serde_json::Value::Number(300.into()),
+            );
+            opts.insert("timeout".to_string(), serde_json::Value::Number(3.into()));
+            opts.insert("hours".to_string(), serde_json::Value::Number(24.into()));
+            opts.insert("max_models".to_string(), serde_json::Value::Number(2.into()));
+            opts
+        },
+    }
+}
+
+pub fn glm_tool_usage_segment() -> SegmentConfig {
+    SegmentConfig {
+        id: SegmentId::GlmToolUsage,
+        key: None,
+        enabled: false,
+        icon: IconConfig {
+            plain: "🛠".to_string(),
+            nerd_font: "\u{f0ad}".to_string(),
+        },
+        colors: ColorConfig {
+            icon: Some(AnsiColor::Rgb {
+                r: 46,
+                g: 52,
+                b: 64,
+            }),
+            text: Some(AnsiColor::Rgb {
+                r: 46,
+                g: 52,
+                b: 64,
+            }),
+            background: Some(AnsiColor::Rgb {
+                r: 235,
+                g: 203,
+                b: 139,
+            }), // Nord yellow background
+        },
+        styles: TextStyleConfig::default(),
+        options: {
+            let mut opts = HashMap::new();
+            opts.insert(
+                "cache_duration".to_string(),
+                serde_json::Value::Number(300.into()),
+            );
+            opts.insert("timeout".to_string(), serde_json::Value::Number(3.into()));
+            opts.insert("hours".to_string(), serde_json::Value::Number(24.into()));
+            opts
+        },
+    }
+}
+
+pub fn session_tokens_segment() -> SegmentConfig {
+    SegmentConfig {
+        id: SegmentId::SessionTokens,
+        key: None,
+        enabled: false,
+        icon: IconConfig {
+            plain: "Σ".to_string(),
+            nerd_font: "\u{f080}".to_string(),
+        },
+        colors: ColorConfig {
+            icon: Some(AnsiColor::Rgb {
+                r: 46,
+                g: 52,
+                b: 64,
+            }),
+            text: Some(AnsiColor::Rgb {
+                r: 46,
+                g: 52,
+                b: 64,
+            }),
+            background: Some(AnsiColor::Rgb {
+                r: 180,
+                g: 142,
+                b: 173,
+            }),
+        },
+        styles: TextStyleConfig::default(),
+        options: HashMap::new(),
+    }
+}
+
+pub fn estimated_cost_segment() -> SegmentConfig {
+    SegmentConfig {
+        id: SegmentId::EstimatedCost,
+        key: None,
+        enabled: false,
+        icon: IconConfig {
+            plain: "¥".to_string(),
+            nerd_font: "\u{f155}".to_string(),
+        },
+        colors: ColorConfig {
+            icon: Some(AnsiColor::Rgb {
+                r: 46,
+                g: 52,
+                b: 64,
+            }),
+            text: Some(AnsiColor::Rgb {
+                r: 46,
+                g: 52,
+                b: 64,
+            }),
+            background: Some(AnsiColor::Rgb {
+                r: 180,
+                g: 142,
+                b: 173,
+            }),
+        },
+        styles: TextStyleConfig::default(),
+        options: HashMap::new(),
+    }
+}
+
+pub fn cost_segment() -> SegmentConfig {
+    SegmentConfig {
+        id: SegmentId::Cost,
+        key: None,
+        enabled: false,
+        icon: IconConfig {
+            plain: "$".to_string(),
+            nerd_font: "\u{f155}".to_string(),
+        },
+        colors: ColorConfig {
+            icon: Some(AnsiColor::Rgb {
+                r: 46,
+                g: 52,
+                b: 64,
+            }),
+            text: Some(AnsiColor::Rgb {
+                r: 46,
+                g: 52,
+                b: 64,
+            }),
+            background: Some(AnsiColor::Rgb {
+                r: 180,
+                g: 142,
+                b: 173,
+            }),
+        },
+        styles: TextStyleConfig::default(),
         options: HashMap::new(),
     }
 }
@@ -77,74 +77,11 @@ impl UpdateState {
     pub fn load() -> Self {
         #[cfg(feature = "self-update")]
         {
-            let config_dir = dirs::home_dir()
-                .unwrap_or_default()
-                .join(".claude")
-                .join("glmcode");
-
-            let state_file = config_dir.join(".update_state.json");
-
-            let mut state = if let Ok(content) = std::fs::read_to_string(&state_file) {
-                if let Ok(state) = serde_json::from_str::<UpdateState>(&content) {
-                    state
-                } else {
-                    UpdateState {
-                        current_version: env!("CARGO_PKG_VERSION").to_string(),
-                        ..Default::default()
-                    }
-                }
-            } else {
-                UpdateState {
-                    current_version: env!("CARGO_PKG_VERSION").to_string(),
-                    ..Default::default()
-                }
-            };
+            let state = Self::load_state();
 
             // Trigger background update check if needed
             if state.should_check_update() {
-                // Check if another update process is running
-                let should_start_check = if let Some(pid) = state.update_pid {
-                    !Self::is_process_running(pid)
-                } else {
-                    true
-                };
-
-                if should_start_check {
-                    // Perform synchronous update check for simplicity and reliability
-                    use crate::updater::github::check_for_updates;
-
-                    state.update_pid = Some(std::process::id());
-                    state.last_check = Some(chrono::Utc::now());
-                    let _ = state.save();
-
-                    // Perform update check
-                    match check_for_updates() {
-                        Ok(Some(release)) => {
-                            if release.find_asset_for_platform().is_some() {
-                                // Set Ready status with timestamp, user must run --update manually
-                                state.status = UpdateStatus::Ready {
-                                    version: release.version(),
-                                    found_at: chrono::Utc::now(),
-                                };
-                            } else {
-                                state.status = UpdateStatus::Failed {
-                                    error: "No compatible asset found".to_string(),
-                                };
-                            }
-                            state.latest_version = Some(release.version());
-                        }
-                        Ok(None) => {
-                            state.status = UpdateStatus::Idle;
-                        }
-                        Err(_) => {
-                            state.status = UpdateStatus::Idle;
-                        }
-                    }
-
-                    // Clear PID and save final state
-                    state.update_pid = None;
-                    let _ = state.save();
-                }
+                crate::refresh::request_refresh();
             }
 
             state
@@ -157,6 +94,77 @@ impl UpdateState {
         }
     }
 
+    /// Check GitHub for a new release and persist the result
+    ///
+    /// Called by the background refresher so rendering never blocks on the network.
+    #[cfg(feature = "self-update")]
+    pub fn refresh() -> Result<(), Box<dyn std::error::Error>> {
+        let mut state = Self::load_state();
+        if !state.should_check_update() {
+            return Ok(());
+        }
+
+        // Check if another update process is running
+        if let Some(pid) = state.update_pid {
+            if Self::is_process_running(pid) {
+                return Ok(());
+            }
+        }
+
+        use crate::updater::github::check_for_updates;
+
+        state.update_pid = Some(std::process::id());
+        state.last_check = Some(chrono::Utc::now());
+        state.save()?;
+
+        match check_for_updates() {
+            Ok(Some(release)) => {
+                if release.find_asset_for_platform().is_some() {
+                    // Set Ready status with timestamp, user must run --update manually
+                    state.status = UpdateStatus::Ready {
+                        version: release.version(),
+                        found_at: chrono::Utc::now(),
+                    };
+                } else {
+                    state.status = UpdateStatus::Failed {
+                        error: "No compatible asset found".to_string(),
+                    };
+                }
+                state.latest_version = Some(release.version());
+            }
+            Ok(None) | Err(_) => {
+                state.status = UpdateStatus::Idle;
+            }
+        }
+
+        // Clear PID and save final state
+        state.update_pid = None;
+        state.save()?;
+        Ok(())
+    }
+
+    /// Read the persisted update state without triggering any checks
+    #[cfg(feature = "self-update")]
+    fn load_state() -> Self {
+        let config_dir = dirs::home_dir()
+            .unwrap_or_default()
+            .join(".claude")
+            .join("glmcode");
+
+        let state_file = config_dir.join(".update_state.json");
+
+        if let Ok(content) = std::fs::read_to_string(&state_file) {
+            if let Ok(state) = serde_json::from_str::<UpdateState>(&content) {
+                return state;
+            }
+        }
+
+        UpdateState {
+            current_version: env!("CARGO_PKG_VERSION").to_string(),
+            ..Default::default()
+        }
+    }
+
     /// Check if a process with given PID is still running
     #[cfg(feature = "self-update")]
     fn is_process_running(pid: u32) -> bool {
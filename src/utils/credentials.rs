@@ -65,6 +65,10 @@ fn get_oauth_token_file() -> Option<String> {
     let credentials_path = get_credentials_path()?;
 
     if !credentials_path.exists() {
+        crate::trace::record(format!(
+            "credentials file not found: {}",
+            credentials_path.display()
+        ));
         return None;
     }
 
@@ -74,7 +78,7 @@ fn get_oauth_token_file() -> Option<String> {
     creds_file.claude_ai_oauth.map(|oauth| oauth.access_token)
 }
 
-fn get_credentials_path() -> Option<PathBuf> {
+pub fn get_credentials_path() -> Option<PathBuf> {
     let home = dirs::home_dir()?;
     Some(home.join(".claude").join(".credentials.json"))
 }
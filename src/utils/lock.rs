@@ -0,0 +1,76 @@
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+/// Lock files not touched for this long are considered abandoned by a crashed process
+const STALE_LOCK_AGE: Duration = Duration::from_secs(60);
+
+/// Cross-process lock backed by a `create_new` file, removed on drop
+#[derive(Debug)]
+pub struct FileLock {
+    path: PathBuf,
+}
+
+impl FileLock {
+    /// Try to acquire the lock, returning None if another process holds it
+    pub fn try_acquire<P: AsRef<Path>>(path: P) -> Option<Self> {
+        let path = path.as_ref();
+        if let Some(parent) = path.parent() {
+            let _ = fs::create_dir_all(parent);
+        }
+
+        for _ in 0..2 {
+            match fs::OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(path)
+            {
+                Ok(mut file) => {
+                    let _ = write!(file, "{}", std::process::id());
+                    return Some(Self {
+                        path: path.to_path_buf(),
+                    });
+                }
+                Err(_) => {
+                    // Remove an abandoned lock and retry once
+                    if !Self::is_stale(path) {
+                        return None;
+                    }
+                    let _ = fs::remove_file(path);
+                }
+            }
+        }
+
+        None
+    }
+
+    /// Bump the lock file's mtime so a long-running holder is not taken for stale
+    pub fn touch(&self) {
+        let _ = fs::OpenOptions::new()
+            .write(true)
+            .open(&self.path)
+            .and_then(|file| file.set_modified(SystemTime::now()));
+    }
+
+    /// Check whether a lock file exists and is still held
+    pub fn is_held<P: AsRef<Path>>(path: P) -> bool {
+        let path = path.as_ref();
+        path.exists() && !Self::is_stale(path)
+    }
+
+    fn is_stale(path: &Path) -> bool {
+        fs::metadata(path)
+            .and_then(|m| m.modified())
+            .ok()
+            .and_then(|modified| modified.elapsed().ok())
+            .map(|age| age > STALE_LOCK_AGE)
+            .unwrap_or(false)
+    }
+}
+
+impl Drop for FileLock {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
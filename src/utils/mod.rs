@@ -1,4 +1,10 @@
 pub mod claude_code_patcher;
 pub mod credentials;
+pub mod lock;
+pub mod shell;
+pub mod state;
 
 pub use claude_code_patcher::{ClaudeCodePatcher, LocationResult};
+pub use lock::FileLock;
+pub use shell::shell_command;
+pub use state::StateFile;
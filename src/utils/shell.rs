@@ -0,0 +1,17 @@
+use std::process::Command;
+
+/// Run `command` through the platform shell (`sh -c` / `cmd /C`)
+pub fn shell_command(command: &str) -> Command {
+    #[cfg(windows)]
+    {
+        let mut process = Command::new("cmd");
+        process.arg("/C").arg(command);
+        process
+    }
+    #[cfg(not(windows))]
+    {
+        let mut process = Command::new("sh");
+        process.arg("-c").arg(command);
+        process
+    }
+}
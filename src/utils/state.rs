@@ -0,0 +1,51 @@
+use super::FileLock;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// JSON file kept under `~/.claude/glmcode`, outside the disposable API cache
+#[derive(Debug, Clone)]
+pub struct StateFile {
+    path: PathBuf,
+}
+
+impl StateFile {
+    /// `~/.claude/glmcode/<name>`
+    pub fn new(name: &str) -> Option<Self> {
+        dirs::home_dir().map(|home| Self::at(home.join(".claude").join("glmcode").join(name)))
+    }
+
+    pub fn at(path: PathBuf) -> Self {
+        Self { path }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Read the file, or None when it is missing or unreadable
+    pub fn load<T: DeserializeOwned>(&self) -> Option<T> {
+        let content = fs::read_to_string(&self.path).ok()?;
+        serde_json::from_str(&content).ok()
+    }
+
+    /// Write through a temporary file so a concurrent reader never sees half a file
+    pub fn store<T: Serialize>(&self, value: &T) -> std::io::Result<()> {
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let tmp_path = self
+            .path
+            .with_extension(format!("{}.tmp", std::process::id()));
+        fs::write(&tmp_path, serde_json::to_string(value)?)?;
+        fs::rename(&tmp_path, &self.path).inspect_err(|_| {
+            let _ = fs::remove_file(&tmp_path);
+        })
+    }
+
+    /// Lock guarding a read-modify-write of this file across processes
+    pub fn lock(&self) -> Option<FileLock> {
+        FileLock::try_acquire(self.path.with_extension("lock"))
+    }
+}